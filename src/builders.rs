@@ -1 +1,5 @@
+pub mod activity;
+pub mod cases;
+pub mod channels;
 pub mod roles;
+pub mod server;
@@ -0,0 +1,116 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::guild::Member;
+
+use crate::handler::presence::{LiveActivity, MemberActivity};
+
+const MAX_GAMES: usize = 10;
+
+/// Seconds as `1d 2h`, `3h 12m` or `5m`.
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86_400, seconds / 3_600 % 24, seconds / 60 % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+pub fn member_activity_to_embed(
+    member: &Member,
+    live: Option<&LiveActivity>,
+    stored: Option<&MemberActivity>,
+    now: i64,
+) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!(
+            "{}#{:04}",
+            member.user.name, member.user.discriminator
+        ))
+        .thumbnail(member.user.face());
+
+    let online = matches!(live, Some(live) if live.online());
+    embed.field(
+        "Status",
+        match live {
+            Some(live) => format!("{:?}", live.status),
+            None => String::from("Unknown"),
+        },
+        true,
+    );
+    embed.field(
+        "Last seen",
+        match stored.and_then(|s| s.last_seen) {
+            _ if online => String::from("Now"),
+            Some(last_seen) => format!("<t:{}:R>", last_seen),
+            None => String::from("Never"),
+        },
+        true,
+    );
+    embed.field(
+        "Current activity",
+        match live.and_then(|l| l.game.as_ref()) {
+            Some((game, started)) => format!(
+                "{} for {}",
+                game,
+                format_duration((now - started).max(0) as u64)
+            ),
+            None => String::from("Nothing"),
+        },
+        true,
+    );
+
+    let mut games: Vec<(&String, u64)> = stored
+        .into_iter()
+        .flat_map(|s| s.games.iter().map(|(game, seconds)| (game, *seconds)))
+        .collect();
+    // Count the running session as well.
+    if let Some((game, started)) = live.and_then(|l| l.game.as_ref()) {
+        let running = (now - started).max(0) as u64;
+        match games.iter_mut().find(|(g, _)| *g == game) {
+            Some((_, seconds)) => *seconds += running,
+            None => games.push((game, running)),
+        }
+    }
+    games.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let lines: Vec<String> = games
+        .iter()
+        .take(MAX_GAMES)
+        .map(|(game, seconds)| format!("**{}** {}", game, format_duration(*seconds)))
+        .collect();
+    embed.field(
+        "Time spent",
+        if lines.is_empty() {
+            String::from("Nothing recorded yet.")
+        } else {
+            lines.join("\n")
+        },
+        false,
+    );
+    embed
+}
+
+pub fn top_games_to_embed(server: &str, games: &[(String, usize)]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("Playing on {} right now", server));
+    let lines: Vec<String> = games
+        .iter()
+        .take(MAX_GAMES)
+        .enumerate()
+        .map(|(i, (game, players))| {
+            format!(
+                "``{}.`` **{}** {} {}",
+                i + 1,
+                game,
+                players,
+                if *players == 1 { "member" } else { "members" }
+            )
+        })
+        .collect();
+    embed.description(if lines.is_empty() {
+        String::from("Nobody is playing anything.")
+    } else {
+        lines.join("\n")
+    });
+    embed
+}
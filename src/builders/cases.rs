@@ -0,0 +1,60 @@
+use serenity::builder::CreateEmbed;
+use serenity::utils::Color;
+
+use crate::handler::cases::{Case, CaseAction};
+
+const REASON_PREVIEW_LEN: usize = 80;
+
+fn action_color(action: CaseAction) -> Color {
+    match action {
+        CaseAction::Warn => Color::GOLD,
+        CaseAction::Kick | CaseAction::Timeout => Color::ORANGE,
+        CaseAction::Ban => Color::RED,
+        CaseAction::Unban | CaseAction::TimeoutRemoved => Color::DARK_GREEN,
+    }
+}
+
+pub fn case_to_embed(case: &Case) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("Case #{} | {}", case.id, case.action))
+        .color(action_color(case.action))
+        .field("Member", format!("<@{}>", case.target), true)
+        .field("Moderator", format!("<@{}>", case.moderator), true)
+        .field("Date", format!("<t:{}:f>", case.created_at), true)
+        .field("Reason", &case.reason, false);
+    if let Some(edited_at) = case.edited_at {
+        embed.field("Edited", format!("<t:{}:R>", edited_at), true);
+    }
+    embed
+}
+
+pub fn cases_to_field<'b>(
+    cases: &[&Case],
+    limit: usize,
+    embed: &'b mut CreateEmbed,
+) -> &'b mut CreateEmbed {
+    let lines: Vec<String> = cases
+        .iter()
+        .take(limit)
+        .map(|case| {
+            let mut reason: String = case.reason.chars().take(REASON_PREVIEW_LEN).collect();
+            if reason.len() < case.reason.len() {
+                reason.push('…');
+            }
+            format!(
+                "``#{}`` **{}** <t:{}:d> by <@{}>: {}",
+                case.id, case.action, case.created_at, case.moderator, reason
+            )
+        })
+        .collect();
+    embed.field(
+        format!("**Cases (``{}``)**", cases.len()),
+        if lines.is_empty() {
+            String::from("No cases.")
+        } else {
+            lines.join("\n")
+        },
+        false,
+    )
+}
@@ -0,0 +1,124 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::channel::{
+    ChannelCategory, GuildChannel, PermissionOverwrite, PermissionOverwriteType,
+};
+use serenity::model::id::GuildId;
+
+use crate::util::truncate;
+
+const FIELD_LIMIT: usize = 1024;
+
+fn overwrite_target(overwrite: &PermissionOverwrite, guild_id: GuildId) -> String {
+    match overwrite.kind {
+        PermissionOverwriteType::Role(role) if role.0 == guild_id.0 => String::from("@everyone"),
+        PermissionOverwriteType::Role(role) => format!("<@&{}>", role),
+        PermissionOverwriteType::Member(user) => format!("<@{}>", user),
+        _ => String::from("Unknown"),
+    }
+}
+
+/// One line per overwrite listing what it allows and denies.
+fn overwrites_to_field<'b>(
+    overwrites: &[PermissionOverwrite],
+    guild_id: GuildId,
+    embed: &'b mut CreateEmbed,
+) -> &'b mut CreateEmbed {
+    let lines: Vec<String> = overwrites
+        .iter()
+        .map(|overwrite| {
+            let mut changes: Vec<String> = overwrite
+                .allow
+                .get_permission_names()
+                .into_iter()
+                .map(|p| format!("+{}", p))
+                .collect();
+            changes.extend(
+                overwrite
+                    .deny
+                    .get_permission_names()
+                    .into_iter()
+                    .map(|p| format!("-{}", p)),
+            );
+            format!(
+                "{}: {}",
+                overwrite_target(overwrite, guild_id),
+                if changes.is_empty() {
+                    String::from("no changes")
+                } else {
+                    changes.join(", ")
+                }
+            )
+        })
+        .collect();
+    embed.field(
+        format!("Permission overwrites ({})", overwrites.len()),
+        if lines.is_empty() {
+            String::from("None, synced with the server.")
+        } else {
+            truncate(&lines.join("\n"), FIELD_LIMIT)
+        },
+        false,
+    )
+}
+
+pub fn channel_to_embed(channel: &GuildChannel) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("#{}", channel.name))
+        .description(
+            channel
+                .topic
+                .as_deref()
+                .filter(|t| !t.is_empty())
+                .unwrap_or("No topic set."),
+        )
+        .field("Channel", format!("<#{}>", channel.id), true)
+        .field("Id", channel.id, true)
+        .field("Type", format!("{:?}", channel.kind), true)
+        .field(
+            "Category",
+            match channel.parent_id {
+                Some(parent) => format!("<#{}>", parent),
+                None => String::from("None"),
+            },
+            true,
+        )
+        .field("Position", channel.position, true)
+        .field("NSFW", if channel.nsfw { "Yes" } else { "No" }, true)
+        .field(
+            "Slowmode",
+            match channel.rate_limit_per_user {
+                Some(seconds) if seconds > 0 => format!("{}s", seconds),
+                _ => String::from("Off"),
+            },
+            true,
+        )
+        .field(
+            "Created",
+            format!("<t:{}:f>", channel.id.created_at().unix_timestamp()),
+            true,
+        );
+    overwrites_to_field(&channel.permission_overwrites, channel.guild_id, &mut embed);
+    embed
+}
+
+pub fn category_to_embed(category: &ChannelCategory) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(&category.name)
+        .field("Id", category.id, true)
+        .field("Type", "Category", true)
+        .field("Position", category.position, true)
+        .field("NSFW", if category.nsfw { "Yes" } else { "No" }, true)
+        .field(
+            "Created",
+            format!("<t:{}:f>", category.id.created_at().unix_timestamp()),
+            true,
+        );
+    overwrites_to_field(
+        &category.permission_overwrites,
+        category.guild_id,
+        &mut embed,
+    );
+    embed
+}
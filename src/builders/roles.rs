@@ -1,28 +1,239 @@
-use serenity::builder::CreateEmbed;
+use std::collections::HashMap;
+
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::application::component::ButtonStyle;
 use serenity::model::channel::EmbedField;
+use serenity::model::guild::Role;
 use serenity::model::id::RoleId;
+use serenity::model::permissions::Permissions;
+
+use crate::handler::role_menus::{MenuStyle, RoleMenu, CUSTOM_ID_PREFIX};
+use crate::util::truncate;
+
+const BUTTONS_PER_ROW: usize = 5;
+const LABEL_LIMIT: usize = 80;
+const FIELD_LIMIT: usize = 1024;
+const ROLES_PER_PAGE: usize = 10;
+const MEMBERS_PER_PAGE: usize = 30;
+
+const NOTABLE_PERMISSIONS: [(Permissions, &str); 9] = [
+    (Permissions::MANAGE_GUILD, "Manage Server"),
+    (Permissions::MANAGE_ROLES, "Manage Roles"),
+    (Permissions::MANAGE_CHANNELS, "Manage Channels"),
+    (Permissions::MANAGE_MESSAGES, "Manage Messages"),
+    (Permissions::MANAGE_WEBHOOKS, "Manage Webhooks"),
+    (Permissions::KICK_MEMBERS, "Kick"),
+    (Permissions::BAN_MEMBERS, "Ban"),
+    (Permissions::MODERATE_MEMBERS, "Timeout"),
+    (Permissions::MENTION_EVERYONE, "Mention Everyone"),
+];
+
+/// Mentions joined until the field limit, with a count of the ones left out.
+fn mentions_within_limit(mentions: &[String], separator: &str) -> String {
+    let mut text = String::new();
+    for (i, mention) in mentions.iter().enumerate() {
+        let more = format!("{}and {} more", separator, mentions.len() - i);
+        let needed = if i == 0 { 0 } else { separator.len() } + mention.len();
+        if text.len() + needed + more.len() > FIELD_LIMIT {
+            text += &more;
+            break;
+        }
+        if i > 0 {
+            text += separator;
+        }
+        text += mention;
+    }
+    text
+}
 
 pub fn roles_to_field<'b>(
-    roles: &Vec<RoleId>,
+    roles: &[RoleId],
     inline: Option<bool>,
     embed: &'b mut CreateEmbed,
 ) -> &'b mut CreateEmbed {
-    let roles: Vec<String> = roles
-        .iter()
-        .map(|role| format!("<@&{}>", role.to_string()))
-        .collect();
+    let roles: Vec<String> = roles.iter().map(|role| format!("<@&{}>", role)).collect();
     let field = EmbedField::new(
         format!("**Roles (``{}``)**", roles.len()),
-        roles.join(" ``|`` ").to_string(),
+        mentions_within_limit(&roles, " ``|`` "),
         inline.unwrap_or(false),
     );
     embed.field(field.name, field.value, field.inline)
 }
 
-pub fn roles_to_text(roles: &Vec<RoleId>) -> String {
-    let roles: Vec<String> = roles
+pub fn roles_to_text(roles: &[RoleId]) -> String {
+    let roles: Vec<String> = roles.iter().map(|role| format!("<@&{}>", role)).collect();
+    roles.join(" | ")
+}
+
+pub fn role_menu_embed(menu: &RoleMenu) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(&menu.title)
+        .description(roles_to_text(&menu.roles));
+    let mut rules = vec![];
+    if menu.exclusive {
+        rules.push(String::from("Pick one"));
+    } else if let Some(max) = menu.max_selections {
+        rules.push(format!("Pick up to {}", max));
+    }
+    if let Some(required) = menu.required_role {
+        rules.push(format!("Requires <@&{}>", required));
+    }
+    if !rules.is_empty() {
+        embed.field("Rules", rules.join("\n"), false);
+    }
+    embed.footer(|f| f.text(format!("Role menu #{}", menu.id)));
+    embed
+}
+
+fn role_label(roles: &HashMap<RoleId, Role>, id: RoleId) -> String {
+    roles
+        .get(&id)
+        .map(|role| truncate(&role.name, LABEL_LIMIT))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Buttons or a select menu whose custom ids point back at the menu and role.
+pub fn role_menu_components(menu: &RoleMenu, roles: &HashMap<RoleId, Role>) -> CreateComponents {
+    let mut components = CreateComponents::default();
+    match menu.style {
+        MenuStyle::Buttons => {
+            for chunk in menu.roles.chunks(BUTTONS_PER_ROW) {
+                components.create_action_row(|row| {
+                    for role in chunk {
+                        row.create_button(|b| {
+                            b.custom_id(format!("{}:{}:{}", CUSTOM_ID_PREFIX, menu.id, role))
+                                .label(role_label(roles, *role))
+                                .style(ButtonStyle::Secondary)
+                        });
+                    }
+                    row
+                });
+            }
+        }
+        MenuStyle::Select if !menu.roles.is_empty() => {
+            let max = if menu.exclusive {
+                1
+            } else {
+                menu.max_selections
+                    .unwrap_or(menu.roles.len() as u64)
+                    .min(menu.roles.len() as u64)
+            };
+            components.create_action_row(|row| {
+                row.create_select_menu(|select| {
+                    select
+                        .custom_id(format!("{}:{}", CUSTOM_ID_PREFIX, menu.id))
+                        .placeholder("Pick your roles")
+                        .min_values(0)
+                        .max_values(max)
+                        .options(|options| {
+                            for role in &menu.roles {
+                                options.create_option(|o| {
+                                    o.label(role_label(roles, *role)).value(role.to_string())
+                                });
+                            }
+                            options
+                        })
+                })
+            });
+        }
+        MenuStyle::Select => {}
+    }
+    components
+}
+
+/// Administrator, or the moderation-relevant permissions a role grants.
+pub fn permission_summary(permissions: Permissions) -> String {
+    if permissions.administrator() {
+        return String::from("Administrator");
+    }
+    let notable: Vec<&str> = NOTABLE_PERMISSIONS
         .iter()
-        .map(|role| format!("<@&{}>", role.to_string()))
+        .filter(|(permission, _)| permissions.contains(*permission))
+        .map(|(_, name)| *name)
         .collect();
-    roles.join(" | ")
+    if notable.is_empty() {
+        String::from("No elevated permissions")
+    } else {
+        notable.join(", ")
+    }
+}
+
+fn role_flags(role: &Role) -> String {
+    let mut flags = vec![format!("#{}", role.colour.hex())];
+    if role.hoist {
+        flags.push(String::from("hoisted"));
+    }
+    if role.mentionable {
+        flags.push(String::from("mentionable"));
+    }
+    if role.managed {
+        flags.push(String::from("managed"));
+    }
+    flags.join(" · ")
+}
+
+/// Roles from highest to lowest, a page of fields at a time.
+pub fn roles_to_pages(roles: &[&Role], counts: &HashMap<RoleId, usize>) -> Vec<CreateEmbed> {
+    let mut sorted = roles.to_vec();
+    sorted.sort_by_key(|role| std::cmp::Reverse(role.position));
+    sorted
+        .chunks(ROLES_PER_PAGE)
+        .map(|chunk| {
+            let mut embed = CreateEmbed::default();
+            embed.title(format!("Roles ({})", roles.len()));
+            for role in chunk {
+                embed.field(
+                    truncate(&role.name, LABEL_LIMIT),
+                    format!(
+                        "<@&{}> · {} members · {}\n{}",
+                        role.id,
+                        counts.get(&role.id).copied().unwrap_or(0),
+                        role_flags(role),
+                        permission_summary(role.permissions)
+                    ),
+                    false,
+                );
+            }
+            embed
+        })
+        .collect()
+}
+
+pub fn role_to_embed(role: &Role, members: usize) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(&role.name)
+        .color(role.colour)
+        .description(format!("<@&{}>", role.id))
+        .field("Id", role.id, true)
+        .field("Members", members, true)
+        .field("Position", role.position, true)
+        .field(
+            "Created",
+            format!("<t:{}:f>", role.id.created_at().unix_timestamp()),
+            true,
+        )
+        .field("Flags", role_flags(role), true)
+        .field("Permissions", permission_summary(role.permissions), false);
+    embed
+}
+
+/// Member mentions split over as many pages as needed.
+pub fn members_to_pages(title: &str, members: &[String]) -> Vec<CreateEmbed> {
+    if members.is_empty() {
+        let mut embed = CreateEmbed::default();
+        embed.title(title).description("Nobody has this role.");
+        return vec![embed];
+    }
+    members
+        .chunks(MEMBERS_PER_PAGE)
+        .map(|chunk| {
+            let mut embed = CreateEmbed::default();
+            embed
+                .title(format!("{} ({})", title, members.len()))
+                .description(chunk.join("\n"));
+            embed
+        })
+        .collect()
 }
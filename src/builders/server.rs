@@ -0,0 +1,59 @@
+use serenity::builder::CreateEmbed;
+
+use crate::handler::member_growth::{change_since, DailyMembers};
+
+const RECENT_DAYS: usize = 14;
+
+fn signed(change: Option<i64>) -> String {
+    match change {
+        Some(change) => format!("{:+}", change),
+        None => String::from("n/a"),
+    }
+}
+
+pub fn growth_to_embed(server: &str, history: &[DailyMembers], today: i64) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("Member growth of {}", server));
+
+    let Some(current) = history.last() else {
+        embed.description("No member counts recorded yet.");
+        return embed;
+    };
+
+    let week = history.iter().filter(|d| d.day > today - 7);
+    let (joins, leaves) = week.fold((0, 0), |(j, l), d| (j + d.joins, l + d.leaves));
+    embed
+        .field("Members", current.members, true)
+        .field(
+            "Change",
+            format!(
+                "{} today\n{} this week\n{} this month",
+                signed(change_since(history, today, 1)),
+                signed(change_since(history, today, 7)),
+                signed(change_since(history, today, 30)),
+            ),
+            true,
+        )
+        .field(
+            "Last 7 days",
+            format!("{} joined\n{} left", joins, leaves),
+            true,
+        );
+
+    let recent: Vec<String> = history
+        .iter()
+        .rev()
+        .take(RECENT_DAYS)
+        .map(|d| {
+            format!(
+                "<t:{}:d> **{}** (+{} / -{})",
+                d.day * 86_400,
+                d.members,
+                d.joins,
+                d.leaves
+            )
+        })
+        .collect();
+    embed.field("Daily", recent.join("\n"), false);
+    embed
+}
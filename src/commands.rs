@@ -1,27 +1,35 @@
 use std::{collections::HashMap, fmt::Debug, hash::Hash, str::FromStr};
 
-use crate::handler::{Handler, HandlerError};
+use crate::handler::{invocation::Invocation, response::CommandResponse, Handler, HandlerError};
 use crate::util::LocalizedString;
 use async_trait::async_trait;
 use serenity::{
     builder::CreateApplicationCommand,
-    model::prelude::{interaction::application_command::ApplicationCommandInteraction, CommandId},
+    model::prelude::CommandId,
     prelude::{Context, TypeMapKey},
 };
 
+#[macro_use]
+mod macros;
+
+pub mod global;
 pub mod guild;
 mod option_data;
 
 #[async_trait]
 trait AppCmd {
+    /// Commands that usually take longer than Discord waits for an answer.
+    /// They're deferred before they're handled.
+    const SLOW: bool = false;
+
     fn to_application_command() -> CreateApplicationCommand
     where
         Self: Sized;
     async fn handle(
-        cmd: &ApplicationCommandInteraction,
+        cmd: &Invocation,
         handler: &Handler,
         context: &Context,
-    ) -> Result<(), HandlerError>
+    ) -> Result<CommandResponse, HandlerError>
     where
         Self: Sized;
     fn name() -> LocalizedString;
@@ -31,10 +39,15 @@ trait AppCmd {
 pub trait CommandsEnum:
     FromStr + TypeMapKey<Value = HashMap<CommandId, Self>> + Debug + Copy + Eq + Hash
 {
+    /// Commands that can't be used in direct messages.
+    const GUILD_ONLY: bool;
+
+    fn is_slow(self) -> bool;
+
     async fn handle(
         self,
-        cmd: &ApplicationCommandInteraction,
+        cmd: &Invocation,
         handler: &Handler,
         context: &Context,
-    ) -> Result<(), HandlerError>;
+    ) -> Result<CommandResponse, HandlerError>;
 }
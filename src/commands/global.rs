@@ -0,0 +1,97 @@
+pub mod ml;
+pub mod stock;
+
+use std::{collections::HashMap, str::FromStr};
+
+use async_trait::async_trait;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::CommandId,
+    prelude::{Context, TypeMapKey},
+};
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, Display, EnumIter};
+use thiserror::Error;
+
+use self::{ml::MLCmd, stock::StockCmd};
+use crate::handler::{invocation::Invocation, response::CommandResponse};
+use crate::{config::Config, util::LocalizedString, Handler, HandlerError};
+
+use super::{AppCmd, CommandsEnum};
+
+/// Commands registered globally, usable in servers and direct messages.
+#[derive(Debug, Clone, Copy, AsRefStr, Display, EnumIter, PartialEq, Eq, Hash)]
+pub enum GlobalCommands {
+    Stock,
+    ML,
+}
+
+impl GlobalCommands {
+    pub fn to_application_command(self) -> CreateApplicationCommand {
+        let mut command = match self {
+            GlobalCommands::Stock => StockCmd::to_application_command(),
+            GlobalCommands::ML => MLCmd::to_application_command(),
+        };
+        command.dm_permission(true);
+        command
+    }
+
+    /// The commands `config` doesn't disable.
+    pub fn application_commands(
+        config: &Config,
+    ) -> impl Iterator<Item = CreateApplicationCommand> + '_ {
+        Self::iter()
+            .filter(|command| config.is_enabled(command.name().en))
+            .map(Self::to_application_command)
+    }
+
+    pub fn name(self) -> LocalizedString {
+        match self {
+            GlobalCommands::Stock => StockCmd::name(),
+            GlobalCommands::ML => MLCmd::name(),
+        }
+    }
+}
+
+#[async_trait]
+impl CommandsEnum for GlobalCommands {
+    const GUILD_ONLY: bool = false;
+
+    fn is_slow(self) -> bool {
+        match self {
+            GlobalCommands::Stock => StockCmd::SLOW,
+            GlobalCommands::ML => MLCmd::SLOW,
+        }
+    }
+
+    async fn handle(
+        self,
+        cmd: &Invocation,
+        handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError> {
+        match self {
+            GlobalCommands::Stock => StockCmd::handle(cmd, handler, context),
+            GlobalCommands::ML => MLCmd::handle(cmd, handler, context),
+        }
+        .await
+    }
+}
+
+impl TypeMapKey for GlobalCommands {
+    type Value = HashMap<CommandId, Self>;
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("Not a valid command: {0}")]
+pub struct InvalidGlobalCommand(String);
+
+impl FromStr for GlobalCommands {
+    type Err = InvalidGlobalCommand;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GlobalCommands::iter()
+            .find(|cmd| cmd.name().any_eq(s))
+            .ok_or_else(|| InvalidGlobalCommand(s.to_string()))
+    }
+}
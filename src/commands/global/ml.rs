@@ -1,22 +1,12 @@
-use async_trait::async_trait;
 use rand::Rng;
 use serenity::builder::CreateEmbed;
-use serenity::{
-    builder::CreateApplicationCommand,
-    model::prelude::{
-        command::{CommandOptionType, CommandType},
-        interaction::application_command::ApplicationCommandInteraction,
-    },
-    prelude::Context,
-};
-use std::str::FromStr;
+use serenity::prelude::Context;
 use tracing::*;
 
-use crate::{
-    commands::{option_data::*, AppCmd},
-    util::LocalizedString,
-    Handler, HandlerError,
-};
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, Subcommand};
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
 
 pub const NAME: LocalizedString = LocalizedString { en: "ml" };
 pub const DESC: LocalizedString = LocalizedString {
@@ -25,25 +15,18 @@ pub const DESC: LocalizedString = LocalizedString {
 
 pub struct MLCmd;
 
-enum MLPropertyTypes {
-    And,
-    Or,
-    Nand,
-    Nor,
-    Xor,
-}
-
-impl FromStr for MLPropertyTypes {
-    type Err = ();
-    fn from_str(input: &str) -> Result<MLPropertyTypes, Self::Err> {
-        match input {
-            "and" => Ok(MLPropertyTypes::And),
-            "or" => Ok(MLPropertyTypes::Or),
-            "nand" => Ok(MLPropertyTypes::Nand),
-            "nor" => Ok(MLPropertyTypes::Nor),
-            "xor" => Ok(MLPropertyTypes::Xor),
-            _ => Err(()),
-        }
+app_command! {
+    command: MLCmd,
+    name: NAME,
+    desc: DESC,
+    slow: true,
+    handler: handle_ml,
+    subcommands: enum MLPropertyTypes {
+        And => AND, AND_DESC [],
+        Or => OR, OR_DESC [],
+        Nand => NAND, NAND_DESC [],
+        Nor => NOR, NOR_DESC [],
+        Xor => XOR, XOR_DESC [],
     }
 }
 
@@ -170,7 +153,11 @@ fn finite_diff(neurons: &[Neuron], eps: f64, train_type: &MLPropertyTypes) -> Ve
     neurons_g
 }
 
-fn train_and_test(train_type: MLPropertyTypes, embed: &mut CreateEmbed) -> &CreateEmbed {
+fn train_and_test(
+    train_type: MLPropertyTypes,
+    iterations: u32,
+    embed: &mut CreateEmbed,
+) -> &CreateEmbed {
     let mut rng = rand::thread_rng();
 
     let mut neurons_m: Vec<Neuron> = vec![
@@ -194,7 +181,7 @@ fn train_and_test(train_type: MLPropertyTypes, embed: &mut CreateEmbed) -> &Crea
     let eps = 0.1;
     let rate = 0.1;
 
-    for _n in 0..(300 * 1000) {
+    for _n in 0..iterations {
         let neurons_g = finite_diff(&neurons_m, eps, &train_type);
         for i in 0..neurons_m.len() {
             neurons_m[i].weight1 -= rate * neurons_g[i].weight1;
@@ -276,119 +263,82 @@ fn train_and_test(train_type: MLPropertyTypes, embed: &mut CreateEmbed) -> &Crea
     embed
 }
 
-async fn create_field_from_embed_types<'b>(
-    embed_types: &Vec<MLPropertyTypes>,
-    _params: &[String],
-    embed: &'b mut CreateEmbed,
-) -> &'b CreateEmbed {
-    for i in embed_types {
-        match i {
-            MLPropertyTypes::And => train_and_test(MLPropertyTypes::And, embed),
-            MLPropertyTypes::Or => train_and_test(MLPropertyTypes::Or, embed),
-            MLPropertyTypes::Nand => train_and_test(MLPropertyTypes::Nand, embed),
-            MLPropertyTypes::Nor => train_and_test(MLPropertyTypes::Nor, embed),
-            MLPropertyTypes::Xor => train_and_test(MLPropertyTypes::Xor, embed),
-        };
-    }
-    embed
-}
-
-async fn create_embed_single_stock(embed_type: &str, params: &Vec<String>) -> CreateEmbed {
+#[instrument(skip(cmd, handler, _context))]
+async fn handle_ml(
+    cmd: &Invocation,
+    handler: &Handler,
+    _context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let (train_type, _) = parse_subcommand::<MLPropertyTypes>(&cmd.data.options)?;
     let mut embed = CreateEmbed::default();
-    embed.title(format!("ML ({})", embed_type));
-    if let Ok(guild_user_embed_type) = MLPropertyTypes::from_str(embed_type) {
-        let embed_types = vec![guild_user_embed_type];
-        embed = create_field_from_embed_types(&embed_types, params, &mut embed)
-            .await
-            .to_owned();
-    }
-    embed
+    embed.title(format!("ML ({})", train_type.name().en));
+    train_and_test(train_type, handler.config.limits.ml_iterations, &mut embed);
+    Ok(CommandResponse::embed(embed))
 }
 
-async fn create_response_stocks(
-    embed_type: &str,
-    stocks: &Vec<String>,
-) -> (String, Vec<CreateEmbed>) {
-    let mut embeds = vec![];
-    let content = String::from("");
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
 
-    embeds.push(create_embed_single_stock(embed_type, stocks).await);
+    use serde_json::json;
 
-    (content, embeds)
-}
+    use super::*;
+    use crate::commands::{global::GlobalCommands, guild::GuildCommands, AppCmd};
+    use crate::testing::{embeds, field, fixtures, MockDiscord};
 
-#[async_trait]
-impl AppCmd for MLCmd {
-    fn to_application_command() -> CreateApplicationCommand
-    where
-        Self: Sized,
-    {
-        let mut cmd = CreateApplicationCommand::default();
-        cmd.name(NAME.en)
-            .kind(CommandType::ChatInput)
-            .description(DESC.en)
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(AND.en)
-                    .description(AND_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(OR.en)
-                    .description(OR_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(NAND.en)
-                    .description(NAND_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(NOR.en)
-                    .description(NOR_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(XOR.en)
-                    .description(XOR_DESC.en)
-            });
-        cmd
-    }
-
-    #[instrument(skip(cmd, _handler, context))]
-    async fn handle(
-        cmd: &ApplicationCommandInteraction,
-        _handler: &Handler,
-        context: &Context,
-    ) -> Result<(), HandlerError>
-    where
-        Self: Sized,
-    {
-        let selected_stocks = Vec::new();
-
-        let mut embeds = vec![];
-        let mut content = String::from("");
-
-        if let Some(response_type) = cmd.data.options.first() {
-            let response = create_response_stocks(&response_type.name, &selected_stocks).await;
-            embeds = response.1;
-            content = response.0;
+    #[tokio::test]
+    async fn learns_and() {
+        let discord = MockDiscord::start().await;
+        let cmd = fixtures::subcommand(NAME.en, AND.en, json!([]));
+        let response = MLCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        let embed = &embeds[0];
+        assert_eq!(embed["title"], "ML (and)");
+        for neuron in ["Neuron 1", "Neuron 2", "Neuron 3"] {
+            assert!(field(embed, neuron).is_some());
         }
 
-        cmd.create_interaction_response(context, |res| {
-            res.interaction_response_data(|d| {
-                if !embeds.is_empty() {
-                    d.add_embeds(embeds)
-                } else {
-                    d.content(content)
-                }
-            })
-        })
-        .await?;
-        Ok(())
+        let outputs: Vec<f64> = field(embed, "Output")
+            .unwrap()
+            .lines()
+            .map(|line| line.rsplit(" | ").next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(outputs.len(), 4);
+        let expected = [0.0, 0.0, 0.0, 1.0];
+        for (output, expected) in outputs.iter().zip(expected) {
+            assert!((output - expected).abs() < 0.1, "{:?}", outputs);
+        }
     }
 
-    fn name() -> LocalizedString {
-        NAME
+    #[tokio::test]
+    async fn is_deferred_before_training() {
+        let discord = MockDiscord::start().await;
+        let original = format!(
+            "/webhooks/{}/interaction-token/messages/@original",
+            fixtures::APPLICATION_ID
+        );
+        discord.route("PATCH", &original, fixtures::message(2000, ""));
+        let context = discord.context();
+        let cmd = fixtures::subcommand(NAME.en, XOR.en, json!([]));
+        {
+            let mut data = context.data.write().await;
+            data.insert::<GuildCommands>(HashMap::new());
+            data.insert::<GlobalCommands>(HashMap::from([(cmd.data.id, GlobalCommands::ML)]));
+        }
+        Handler::default().dispatch(&context, &cmd).await;
+
+        let requests = discord.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].path.ends_with("/callback"));
+        assert_eq!(requests[0].body["type"], 5);
+        assert_eq!(
+            (requests[1].method.as_str(), &requests[1].path),
+            ("PATCH", &original)
+        );
+        assert_eq!(requests[1].body["embeds"][0]["title"], "ML (xor)");
     }
 }
@@ -0,0 +1,217 @@
+use serenity::builder::CreateEmbed;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use tracing::*;
+
+use crate::config::{Config, ProviderConfig};
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
+
+use ascii_table::AsciiTable;
+
+pub const NAME: LocalizedString = LocalizedString { en: "fear" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands accessing the fear-index!",
+};
+
+pub struct StockCmd;
+
+app_command! {
+    command: StockCmd,
+    name: NAME,
+    desc: DESC,
+    slow: true,
+    handler: handle_stock,
+    subcommands: enum StockPropertyTypes {
+        Info => INFO, INFO_DESC [req(String, STOCK, STOCK_DESC)],
+        History => HISTORY, HISTORY_DESC [req(String, STOCK, STOCK_DESC)],
+    }
+}
+
+command_options! {
+    struct StockArgs {
+        stocks: String = STOCK,
+    }
+}
+
+async fn create_field_from_embed_types<'b>(
+    embed_types: &Vec<StockPropertyTypes>,
+    stock: &str,
+    provider: &ProviderConfig,
+    embed: &'b mut CreateEmbed,
+) -> &'b CreateEmbed {
+    for i in embed_types {
+        match i {
+            StockPropertyTypes::Info | StockPropertyTypes::History => {
+                match provider
+                    .connector()
+                    .get_latest_quotes(stock, &provider.interval)
+                    .await
+                {
+                    Ok(quotes) => {
+                        embed.description(format!(
+                            "{} ({})",
+                            quotes.metadata().unwrap().exchange_name,
+                            provider.interval
+                        ));
+                        embed.field(
+                            "High / Low",
+                            format!(
+                                "{} / {}",
+                                quotes.last_quote().unwrap().high.round(),
+                                quotes.last_quote().unwrap().low.round()
+                            ),
+                            false,
+                        );
+                        embed.field(
+                            "Open / Close",
+                            format!(
+                                "{} / {}",
+                                quotes.last_quote().unwrap().open.round(),
+                                quotes.last_quote().unwrap().close.round()
+                            ),
+                            false,
+                        );
+                    }
+                    Err(err) => {
+                        embed.description(err.to_string());
+                    }
+                }
+            }
+        };
+    }
+    embed
+}
+
+fn create_table_from_embed_types(
+    embed_types: &Vec<StockPropertyTypes>,
+    stocks: &[String],
+    width: usize,
+) -> String {
+    let mut ascii_table = AsciiTable::default();
+    ascii_table.set_max_width(width);
+
+    let mut data: Vec<Vec<String>> = vec![];
+
+    let mut m = 0;
+
+    stocks.iter().for_each(|stock| {
+        data.push(vec![]);
+        ascii_table.column(0);
+        data[m].push(stock.to_string());
+        for i in embed_types {
+            match i {
+                StockPropertyTypes::Info | StockPropertyTypes::History => {
+                    data[m].push(stock.to_string());
+                }
+            };
+        }
+        m += 1
+    });
+
+    let text = ascii_table.format(&data);
+    String::from("```\n") + &text + &*String::from("\n```")
+}
+
+async fn create_embed_single_stock(
+    embed_type: StockPropertyTypes,
+    stock: &String,
+    provider: &ProviderConfig,
+) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(stock.to_string());
+    create_field_from_embed_types(&vec![embed_type], stock, provider, &mut embed).await;
+    embed
+}
+
+async fn create_response_stocks(
+    embed_type: StockPropertyTypes,
+    stocks: &[String],
+    config: &Config,
+) -> (String, Vec<CreateEmbed>) {
+    let mut embeds = vec![];
+    let mut content = String::from("");
+
+    match stocks.len() {
+        0 => {
+            let mut embed = CreateEmbed::default();
+            embed
+                .title("Error".to_string())
+                .description("No stock found!")
+                .color(Color::RED);
+            embeds.push(embed)
+        }
+        1 => {
+            embeds.push(
+                create_embed_single_stock(embed_type, stocks.first().unwrap(), &config.provider)
+                    .await,
+            );
+        }
+        _ => {
+            content =
+                create_table_from_embed_types(&vec![embed_type], stocks, config.limits.table_width);
+        }
+    }
+    (content, embeds)
+}
+
+#[instrument(skip(cmd, handler, _context))]
+async fn handle_stock(
+    cmd: &Invocation,
+    handler: &Handler,
+    _context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+    let selected_stocks: Vec<String> = StockArgs::from_options(options)?
+        .stocks
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    let (content, embeds) =
+        create_response_stocks(embed_type, &selected_stocks, &handler.config).await;
+    Ok(CommandResponse::embeds_or_content(embeds, content))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::commands::AppCmd;
+    use crate::testing::{embeds, fixtures, MockDiscord};
+
+    #[tokio::test]
+    async fn requires_a_stock() {
+        let discord = MockDiscord::start().await;
+        let cmd = fixtures::subcommand(NAME.en, INFO.en, json!([]));
+        let result = StockCmd::handle(&cmd, &Handler::default(), &discord.context()).await;
+        assert!(matches!(result, Err(HandlerError::MissingOption(_))));
+
+        let options = json!([{ "name": STOCK.en, "type": 3, "value": "  " }]);
+        let cmd = fixtures::subcommand(NAME.en, INFO.en, options);
+        let response = StockCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(embeds[0]["title"], "Error");
+        assert_eq!(embeds[0]["description"], "No stock found!");
+    }
+
+    #[tokio::test]
+    async fn lists_several_stocks_in_a_table() {
+        let discord = MockDiscord::start().await;
+        let options = json!([{ "name": STOCK.en, "type": 3, "value": "^VIX ^GSPC" }]);
+        let cmd = fixtures::subcommand(NAME.en, HISTORY.en, options);
+        let response = StockCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+
+        let content = response.content.unwrap();
+        assert!(content.starts_with("```"));
+        assert!(content.contains("^VIX") && content.contains("^GSPC"));
+        assert!(discord.requests().is_empty());
+    }
+}
@@ -1,70 +1,136 @@
-pub mod ml;
+pub mod automod;
+pub mod botinfo;
+pub mod botstatus;
+pub mod case;
+pub mod cases;
+pub mod channel;
+pub mod moderation;
+pub mod ping;
+pub mod role;
+pub mod roles;
 pub mod server;
-pub mod stock;
+pub mod stats;
 pub mod user;
+pub mod warn;
 
 use std::{collections::HashMap, str::FromStr};
 
 use async_trait::async_trait;
 use serenity::{
     builder::CreateApplicationCommand,
-    model::prelude::{interaction::application_command::ApplicationCommandInteraction, CommandId},
+    model::prelude::CommandId,
     prelude::{Context, TypeMapKey},
 };
 use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, Display, EnumIter};
 use thiserror::Error;
 
-use self::{ml::MLCmd, server::GuildServerCmd, stock::StockCmd, user::GuildUserCmd};
-use crate::{util::LocalizedString, Handler, HandlerError};
+use self::{
+    automod::AutomodCmd, botinfo::BotInfoCmd, botstatus::BotStatusCmd, case::CaseCmd,
+    cases::CasesCmd, channel::ChannelCmd, moderation::ModCmd, ping::PingCmd, role::RoleCmd,
+    roles::RolesCmd, server::GuildServerCmd, stats::StatsCmd, user::GuildUserCmd, warn::WarnCmd,
+};
+use crate::handler::{invocation::Invocation, response::CommandResponse};
+use crate::{config::Config, util::LocalizedString, Handler, HandlerError};
 
 use super::{AppCmd, CommandsEnum};
 
 #[derive(Debug, Clone, Copy, AsRefStr, Display, EnumIter, PartialEq, Eq, Hash)]
 pub enum GuildCommands {
     Server,
-    Stock,
-    ML,
     User,
+    Stats,
+    Mod,
+    Warn,
+    Cases,
+    Case,
+    Automod,
+    Roles,
+    Role,
+    Channel,
+    BotStatus,
+    BotInfo,
+    Ping,
 }
 
 impl GuildCommands {
     pub fn to_application_command(self) -> CreateApplicationCommand {
         match self {
             GuildCommands::User => GuildUserCmd::to_application_command(),
-            GuildCommands::Stock => StockCmd::to_application_command(),
-            GuildCommands::ML => MLCmd::to_application_command(),
             GuildCommands::Server => GuildServerCmd::to_application_command(),
+            GuildCommands::Stats => StatsCmd::to_application_command(),
+            GuildCommands::Mod => ModCmd::to_application_command(),
+            GuildCommands::Warn => WarnCmd::to_application_command(),
+            GuildCommands::Cases => CasesCmd::to_application_command(),
+            GuildCommands::Case => CaseCmd::to_application_command(),
+            GuildCommands::Automod => AutomodCmd::to_application_command(),
+            GuildCommands::Roles => RolesCmd::to_application_command(),
+            GuildCommands::Role => RoleCmd::to_application_command(),
+            GuildCommands::Channel => ChannelCmd::to_application_command(),
+            GuildCommands::BotStatus => BotStatusCmd::to_application_command(),
+            GuildCommands::BotInfo => BotInfoCmd::to_application_command(),
+            GuildCommands::Ping => PingCmd::to_application_command(),
         }
     }
 
-    pub fn application_commands() -> impl Iterator<Item = CreateApplicationCommand> {
-        Self::iter().map(Self::to_application_command)
+    /// The commands `config` doesn't disable.
+    pub fn application_commands(
+        config: &Config,
+    ) -> impl Iterator<Item = CreateApplicationCommand> + '_ {
+        Self::iter()
+            .filter(|command| config.is_enabled(command.name().en))
+            .map(Self::to_application_command)
     }
 
     pub fn name(self) -> LocalizedString {
         match self {
             GuildCommands::User => GuildUserCmd::name(),
-            GuildCommands::Stock => StockCmd::name(),
-            GuildCommands::ML => MLCmd::name(),
             GuildCommands::Server => GuildServerCmd::name(),
+            GuildCommands::Stats => StatsCmd::name(),
+            GuildCommands::Mod => ModCmd::name(),
+            GuildCommands::Warn => WarnCmd::name(),
+            GuildCommands::Cases => CasesCmd::name(),
+            GuildCommands::Case => CaseCmd::name(),
+            GuildCommands::Automod => AutomodCmd::name(),
+            GuildCommands::Roles => RolesCmd::name(),
+            GuildCommands::Role => RoleCmd::name(),
+            GuildCommands::Channel => ChannelCmd::name(),
+            GuildCommands::BotStatus => BotStatusCmd::name(),
+            GuildCommands::BotInfo => BotInfoCmd::name(),
+            GuildCommands::Ping => PingCmd::name(),
         }
     }
 }
 
 #[async_trait]
 impl CommandsEnum for GuildCommands {
+    const GUILD_ONLY: bool = true;
+
+    fn is_slow(self) -> bool {
+        false
+    }
+
     async fn handle(
         self,
-        cmd: &ApplicationCommandInteraction,
+        cmd: &Invocation,
         handler: &Handler,
         context: &Context,
-    ) -> Result<(), HandlerError> {
+    ) -> Result<CommandResponse, HandlerError> {
         match self {
             GuildCommands::User => GuildUserCmd::handle(cmd, handler, context),
-            GuildCommands::Stock => StockCmd::handle(cmd, handler, context),
-            GuildCommands::ML => MLCmd::handle(cmd, handler, context),
             GuildCommands::Server => GuildServerCmd::handle(cmd, handler, context),
+            GuildCommands::Stats => StatsCmd::handle(cmd, handler, context),
+            GuildCommands::Mod => ModCmd::handle(cmd, handler, context),
+            GuildCommands::Warn => WarnCmd::handle(cmd, handler, context),
+            GuildCommands::Cases => CasesCmd::handle(cmd, handler, context),
+            GuildCommands::Case => CaseCmd::handle(cmd, handler, context),
+            GuildCommands::Automod => AutomodCmd::handle(cmd, handler, context),
+            GuildCommands::Roles => RolesCmd::handle(cmd, handler, context),
+            GuildCommands::Role => RoleCmd::handle(cmd, handler, context),
+            GuildCommands::Channel => ChannelCmd::handle(cmd, handler, context),
+            GuildCommands::BotStatus => BotStatusCmd::handle(cmd, handler, context),
+            GuildCommands::BotInfo => BotInfoCmd::handle(cmd, handler, context),
+            GuildCommands::Ping => PingCmd::handle(cmd, handler, context),
         }
         .await
     }
@@ -0,0 +1,185 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use tracing::*;
+
+use crate::handler::automod::{Automod, AutomodMessage, Rule, RuleAction, RuleKind, MAX_RULES};
+use crate::handler::command_details::require_permissions;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
+
+pub const NAME: LocalizedString = LocalizedString { en: "automod" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands configuring auto-moderation!",
+};
+
+pub struct AutomodCmd;
+
+app_command! {
+    command: AutomodCmd,
+    name: NAME,
+    desc: DESC,
+    dm_permission: false,
+    handler: handle_automod,
+    subcommands: enum AutomodPropertyTypes {}
+    groups: {
+        RULE, RULE_DESC {
+            Add => RULE_ADD, RULE_ADD_DESC [
+                req(String, RULE_KIND, RULE_KIND_DESC)
+                    .add_string_choice("Banned words", "words")
+                    .add_string_choice("Regex", "regex")
+                    .add_string_choice("Invite links", "invites")
+                    .add_string_choice("Mention spam", "mentions")
+                    .add_string_choice("Duplicate messages", "duplicates")
+                    .add_string_choice("Excessive caps", "caps")
+                    .add_string_choice("Attachment types", "attachments"),
+                req(String, RULE_ACTION, RULE_ACTION_DESC)
+                    .add_string_choice("Delete", "delete")
+                    .add_string_choice("Delete and warn", "warn")
+                    .add_string_choice("Delete and time out", "timeout")
+                    .add_string_choice("Log only", "log"),
+                opt(String, RULE_VALUE, RULE_VALUE_DESC),
+                opt(Integer, RULE_THRESHOLD, RULE_THRESHOLD_DESC).min_int_value(1),
+                opt(Integer, MINUTES, RULE_MINUTES_DESC).min_int_value(1),
+            ],
+            List => RULE_LIST, RULE_LIST_DESC [],
+            Remove => RULE_REMOVE, RULE_REMOVE_DESC [
+                req(Integer, RULE_ID, RULE_ID_DESC).min_int_value(1),
+            ],
+            Test => RULE_TEST, RULE_TEST_DESC [req(String, RULE_TEXT, RULE_TEXT_DESC)],
+        },
+    }
+}
+
+command_options! {
+    struct AddArgs {
+        kind: String = RULE_KIND,
+        value: Option<String> = RULE_VALUE,
+        threshold: Option<i64> = RULE_THRESHOLD,
+        action: String = RULE_ACTION,
+        minutes: Option<i64> = MINUTES,
+    }
+}
+
+command_options! {
+    struct RemoveArgs {
+        id: i64 = RULE_ID,
+    }
+}
+
+command_options! {
+    struct TestArgs {
+        text: String = RULE_TEXT,
+    }
+}
+
+fn rule_to_field(rule: &Rule, embed: &mut CreateEmbed) {
+    embed.field(
+        format!("#{}", rule.id),
+        format!("{}\n*{}*", rule.kind.describe(), rule.action.describe()),
+        false,
+    );
+}
+
+async fn create_embed_automod(
+    embed_type: AutomodPropertyTypes,
+    options: &[CommandDataOption],
+    context: &Context,
+    guild_id: GuildId,
+) -> Result<CreateEmbed, HandlerError> {
+    let mut embed = CreateEmbed::default();
+    match embed_type {
+        AutomodPropertyTypes::Add => {
+            let args = AddArgs::from_options(options)?;
+            let kind = RuleKind::parse(&args.kind, args.value.as_deref(), args.threshold)?;
+            let action = RuleAction::parse(&args.action, args.minutes)?;
+
+            let mut data = context.data.write().await;
+            let automod = data.entry::<Automod>().or_insert_with(Automod::default);
+            if automod.rules(guild_id).len() >= MAX_RULES {
+                return Err(HandlerError::RuleLimit);
+            }
+            let rule = automod.add_rule(guild_id, kind, action)?;
+
+            embed.title("Automod rule added").color(Color::DARK_GREEN);
+            rule_to_field(&rule, &mut embed);
+        }
+        AutomodPropertyTypes::List => {
+            let data = context.data.read().await;
+            let automod = data.get::<Automod>().ok_or(HandlerError::TypeMapNotFound)?;
+            let rules = automod.rules(guild_id);
+
+            embed.title("Automod rules");
+            if rules.is_empty() {
+                embed.description("No rules set up.");
+            }
+            for rule in rules {
+                rule_to_field(rule, &mut embed);
+            }
+        }
+        AutomodPropertyTypes::Remove => {
+            let id = RemoveArgs::from_options(options)?.id.max(0) as u64;
+            let rule = context
+                .data
+                .write()
+                .await
+                .entry::<Automod>()
+                .or_insert_with(Automod::default)
+                .remove_rule(guild_id, id)?
+                .ok_or(HandlerError::RuleNotFound(id))?;
+
+            embed
+                .title(format!("Automod rule #{} removed", rule.id))
+                .color(Color::DARK_GREY);
+        }
+        AutomodPropertyTypes::Test => {
+            let message = AutomodMessage {
+                content: TestArgs::from_options(options)?.text,
+                ..Default::default()
+            };
+            let data = context.data.read().await;
+            let automod = data.get::<Automod>().ok_or(HandlerError::TypeMapNotFound)?;
+            let violations = automod.evaluate(guild_id, &message, 1);
+
+            embed.title("Automod test");
+            if violations.is_empty() {
+                embed
+                    .color(Color::DARK_GREEN)
+                    .description("No rule would be triggered.");
+            } else {
+                embed.color(Color::ORANGE);
+            }
+            for violation in violations {
+                embed.field(
+                    format!(
+                        "#{} {}",
+                        violation.rule.id,
+                        violation.rule.action.describe()
+                    ),
+                    violation.reason,
+                    false,
+                );
+            }
+        }
+    }
+    Ok(embed)
+}
+
+#[instrument(skip(cmd, _handler, context))]
+async fn handle_automod(
+    cmd: &Invocation,
+    _handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    require_permissions(cmd, Permissions::MANAGE_GUILD)?;
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+
+    let embed = create_embed_automod(embed_type, options, context, guild_id).await?;
+    Ok(CommandResponse::embed(embed).ephemeral(true))
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::client::bridge::gateway::ShardId;
+use serenity::gateway::ConnectionStage;
+use serenity::{
+    builder::CreateApplicationCommand, model::prelude::command::CommandType, prelude::Context,
+};
+use tracing::*;
+
+use crate::builders::activity::format_duration;
+use crate::commands::{global::GlobalCommands, guild::GuildCommands};
+use crate::handler::invocation::Invocation;
+use crate::handler::response::CommandResponse;
+use crate::handler::shards::{ShardManagerContainer, Shards};
+use crate::{commands::AppCmd, util::LocalizedString, Handler, HandlerError};
+
+pub const NAME: LocalizedString = LocalizedString { en: "botinfo" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Shows how the bot is doing!",
+};
+
+/// The crate version, with the commit when it was built from a checkout.
+const VERSION: &str = match option_env!("GIT_COMMIT") {
+    Some(_) => concat!(env!("CARGO_PKG_VERSION"), " (", env!("GIT_COMMIT"), ")"),
+    None => env!("CARGO_PKG_VERSION"),
+};
+
+pub struct BotInfoCmd;
+
+/// What the shard manager knows about one of this process' shards.
+struct ShardStatus {
+    id: u64,
+    latency: Option<Duration>,
+    stage: ConnectionStage,
+}
+
+async fn shard_statuses(context: &Context) -> Vec<ShardStatus> {
+    let manager = context
+        .data
+        .read()
+        .await
+        .get::<ShardManagerContainer>()
+        .cloned();
+    let Some(manager) = manager else {
+        return vec![];
+    };
+    let runners = manager.lock().await.runners.clone();
+    let mut statuses: Vec<ShardStatus> = runners
+        .lock()
+        .await
+        .iter()
+        .map(|(id, runner)| ShardStatus {
+            id: id.0,
+            latency: runner.latency,
+            stage: runner.stage,
+        })
+        .collect();
+    statuses.sort_by_key(|status| status.id);
+    statuses
+}
+
+/// The heartbeat latency of the shard `context` belongs to, once it has one.
+pub async fn gateway_latency(context: &Context) -> Option<Duration> {
+    let manager = context
+        .data
+        .read()
+        .await
+        .get::<ShardManagerContainer>()
+        .cloned()?;
+    let runners = manager.lock().await.runners.clone();
+    let latency = runners
+        .lock()
+        .await
+        .get(&ShardId(context.shard_id))?
+        .latency;
+    latency
+}
+
+/// How long a small REST request takes to be answered.
+pub async fn rest_latency(context: &Context) -> Result<Duration, HandlerError> {
+    let start = Instant::now();
+    context.http.get_current_user().await?;
+    Ok(start.elapsed())
+}
+
+pub fn format_latency(latency: Option<Duration>) -> String {
+    latency
+        .map(|latency| format!("{} ms", latency.as_millis()))
+        .unwrap_or_else(|| "no heartbeat yet".to_string())
+}
+
+/// The resident set size in bytes, from a `/proc/<pid>/status` file.
+fn parse_resident_memory(status: &str) -> Option<u64> {
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// The process' memory usage, where `/proc` exists.
+fn resident_memory() -> Option<u64> {
+    parse_resident_memory(&fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// How many distinct guild and global commands were registered. Guild
+/// commands have an id in every guild, so the ids themselves are no count.
+async fn registered_commands(context: &Context) -> (usize, usize) {
+    let data = context.data.read().await;
+    let guild = data
+        .get::<GuildCommands>()
+        .map_or(0, |ids| ids.values().collect::<HashSet<_>>().len());
+    let global = data
+        .get::<GlobalCommands>()
+        .map_or(0, |ids| ids.values().collect::<HashSet<_>>().len());
+    (guild, global)
+}
+
+/// One line per shard with its heartbeat latency, ready ones checked.
+fn shards_field(shards: &[ShardStatus], ready: &BTreeSet<u64>, current: u64) -> String {
+    shards
+        .iter()
+        .map(|shard| {
+            format!(
+                "{} ``#{}`` {} - {}{}",
+                if ready.contains(&shard.id) {
+                    "✅"
+                } else {
+                    "❌"
+                },
+                shard.id,
+                format_latency(shard.latency),
+                shard.stage,
+                if shard.id == current {
+                    " (this one)"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[async_trait]
+impl AppCmd for BotInfoCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en);
+        cmd
+    }
+
+    #[instrument(skip(_cmd, handler, context))]
+    async fn handle(
+        _cmd: &Invocation,
+        handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        let rest = rest_latency(context).await?;
+        let (ready, expected, total) = match context.data.read().await.get::<Shards>() {
+            Some(shards) => (
+                shards.ready_shards().clone(),
+                shards.expected(),
+                shards.total(),
+            ),
+            None => Default::default(),
+        };
+        let statuses = shard_statuses(context).await;
+        let gateway = statuses
+            .iter()
+            .find(|status| status.id == context.shard_id)
+            .and_then(|status| status.latency);
+        let (guild_commands, global_commands) = registered_commands(context).await;
+
+        let guilds = context.cache.guilds();
+        let (cached_members, members) = guilds
+            .iter()
+            .filter_map(|guild| {
+                context
+                    .cache
+                    .guild_field(guild, |g| (g.members.len() as u64, g.member_count))
+            })
+            .fold((0, 0), |(cached, total), (c, t)| (cached + c, total + t));
+
+        let unknown = || "?".to_string();
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Bot info")
+            .field("Version", VERSION, true)
+            .field(
+                "Uptime",
+                format_duration(handler.started.elapsed().as_secs()),
+                true,
+            )
+            .field(
+                "Memory",
+                resident_memory()
+                    .map(|bytes| format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)))
+                    .unwrap_or_else(unknown),
+                true,
+            )
+            .field("Gateway", format_latency(gateway), true)
+            .field("REST", format!("{} ms", rest.as_millis()), true)
+            .field(
+                "Shard",
+                format!(
+                    "{} of {}",
+                    context.shard_id,
+                    total.map_or_else(unknown, |total| total.to_string())
+                ),
+                true,
+            )
+            .field(
+                "Ready",
+                format!(
+                    "{}/{}",
+                    ready.len(),
+                    expected.map_or_else(unknown, |expected| expected.to_string())
+                ),
+                true,
+            )
+            .field("Guilds", guilds.len(), true)
+            .field(
+                "Members",
+                format!("{} cached of {}", cached_members, members),
+                true,
+            )
+            .field(
+                "Commands",
+                format!("{} guild, {} global", guild_commands, global_commands),
+                true,
+            );
+        if !statuses.is_empty() {
+            embed.field(
+                "Shards",
+                shards_field(&statuses, &ready, context.shard_id),
+                false,
+            );
+        }
+        Ok(CommandResponse::embed(embed))
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use serenity::model::id::CommandId;
+
+    use super::*;
+    use crate::testing::{embeds, field, fixtures, MockDiscord};
+
+    #[test]
+    fn lists_every_shard() {
+        let shards = [
+            ShardStatus {
+                id: 0,
+                latency: Some(Duration::from_millis(42)),
+                stage: ConnectionStage::Connected,
+            },
+            ShardStatus {
+                id: 1,
+                latency: None,
+                stage: ConnectionStage::Resuming,
+            },
+        ];
+        assert_eq!(
+            shards_field(&shards, &BTreeSet::from([0]), 1),
+            "✅ ``#0`` 42 ms - connected\n❌ ``#1`` no heartbeat yet - resuming (this one)"
+        );
+    }
+
+    #[test]
+    fn reads_resident_memory() {
+        let status = "Name:\tdiscord-bot\nVmPeak:\t  20000 kB\nVmRSS:\t   1536 kB\n";
+        assert_eq!(parse_resident_memory(status), Some(1536 * 1024));
+        assert_eq!(parse_resident_memory("Name:\tdiscord-bot\n"), None);
+    }
+
+    #[tokio::test]
+    async fn counts_distinct_commands() {
+        let discord = MockDiscord::start().await;
+        discord.route("GET", "/users/@me", fixtures::current_user());
+        let context = discord.context();
+        {
+            let mut data = context.data.write().await;
+            data.insert::<GuildCommands>(HashMap::from([
+                (CommandId(1), GuildCommands::BotInfo),
+                (CommandId(2), GuildCommands::BotInfo),
+                (CommandId(3), GuildCommands::Server),
+            ]));
+            data.insert::<GlobalCommands>(HashMap::new());
+        }
+        let cmd = fixtures::command(NAME.en, json!([]), json!({}));
+        let response = BotInfoCmd::handle(&cmd, &Handler::default(), &context)
+            .await
+            .unwrap();
+
+        let embeds = embeds(&response);
+        assert_eq!(field(&embeds[0], "Commands"), Some("2 guild, 0 global"));
+        assert_eq!(field(&embeds[0], "Gateway"), Some("no heartbeat yet"));
+        assert_eq!(field(&embeds[0], "Guilds"), Some("0"));
+    }
+}
@@ -0,0 +1,224 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use tracing::*;
+
+use crate::handler::bot_status::{
+    render_status, set_activity, status_values, BotStatus, StatusKind, StatusSettings,
+    MIN_INTERVAL_SECS,
+};
+use crate::handler::command_details::require_bot_owner;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
+
+pub const NAME: LocalizedString = LocalizedString { en: "botstatus" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands managing the bot's status!",
+};
+
+const MAX_TEMPLATES: usize = 10;
+const MAX_TEMPLATE_LEN: usize = 128;
+
+pub struct BotStatusCmd;
+
+app_command! {
+    command: BotStatusCmd,
+    name: NAME,
+    desc: DESC,
+    handler: handle_botstatus,
+    subcommands: enum BotStatusPropertyTypes {
+        Set => SET, STATUS_SET_DESC [
+            req(String, STATUS_TEMPLATES, STATUS_TEMPLATES_DESC),
+            opt(String, STATUS_KIND, STATUS_KIND_DESC)
+                .add_string_choice("Playing", "playing")
+                .add_string_choice("Watching", "watching")
+                .add_string_choice("Listening to", "listening")
+                .add_string_choice("Competing in", "competing"),
+            opt(Integer, STATUS_INTERVAL, STATUS_INTERVAL_DESC).min_int_value(MIN_INTERVAL_SECS),
+        ],
+        Show => STATUS_SHOW, STATUS_SHOW_DESC [],
+    }
+}
+
+command_options! {
+    struct SetArgs {
+        templates: String = STATUS_TEMPLATES,
+        kind: Option<String> = STATUS_KIND,
+        interval: Option<i64> = STATUS_INTERVAL,
+    }
+}
+
+/// Reads the new rotation, changing every `default_interval_secs` unless
+/// an interval is given.
+fn parse_settings(
+    options: &[CommandDataOption],
+    default_interval_secs: u64,
+) -> Result<StatusSettings, HandlerError> {
+    let args = SetArgs::from_options(options)?;
+    let templates: Vec<String> = args
+        .templates
+        .split('|')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect();
+    if templates.is_empty()
+        || templates.len() > MAX_TEMPLATES
+        || templates
+            .iter()
+            .any(|t| t.chars().count() > MAX_TEMPLATE_LEN)
+    {
+        return Err(HandlerError::InvalidArgument(format!(
+            "between 1 and {} statuses of at most {} characters",
+            MAX_TEMPLATES, MAX_TEMPLATE_LEN
+        )));
+    }
+    let defaults = StatusSettings::default();
+    Ok(StatusSettings {
+        kind: args
+            .kind
+            .as_deref()
+            .and_then(StatusKind::parse)
+            .unwrap_or(defaults.kind),
+        templates,
+        interval_secs: args
+            .interval
+            .map(|secs| (secs.max(0) as u64).max(MIN_INTERVAL_SECS))
+            .unwrap_or(default_interval_secs),
+    })
+}
+
+fn settings_to_embed(settings: &StatusSettings) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("Bot status")
+        .field("Kind", format!("{:?}", settings.kind), true)
+        .field("Interval", format!("{}s", settings.interval_secs), true)
+        .field(
+            "Statuses",
+            settings
+                .templates
+                .iter()
+                .enumerate()
+                .map(|(i, t)| format!("``{}.`` {}", i + 1, t))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            false,
+        );
+    embed
+}
+
+#[instrument(skip(cmd, handler, context))]
+async fn handle_botstatus(
+    cmd: &Invocation,
+    handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    require_bot_owner(context, cmd.user.id).await?;
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+
+    let interval_secs = handler.config.limits.activity_interval_secs;
+    let embed = match embed_type {
+        BotStatusPropertyTypes::Set => {
+            let settings = parse_settings(options, interval_secs)?;
+            context
+                .data
+                .write()
+                .await
+                .entry::<BotStatus>()
+                .or_insert_with(|| BotStatus::new(interval_secs))
+                .set(settings.clone())?;
+
+            // Show the first status right away instead of at the next tick.
+            let first = &settings.templates[0];
+            let values = status_values(context, &handler.config.provider, first).await;
+            let activity = settings.kind.activity(&render_status(first, &values));
+            set_activity(context, Some(activity)).await;
+
+            let mut embed = settings_to_embed(&settings);
+            embed.color(Color::DARK_GREEN);
+            embed
+        }
+        BotStatusPropertyTypes::Show => match context.data.read().await.get::<BotStatus>() {
+            Some(status) => settings_to_embed(status.settings()),
+            None => settings_to_embed(&StatusSettings {
+                interval_secs,
+                ..StatusSettings::default()
+            }),
+        },
+    };
+    Ok(CommandResponse::embed(embed).ephemeral(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn options(options: Value) -> Vec<CommandDataOption> {
+        serde_json::from_value(options).unwrap()
+    }
+
+    fn templates(templates: &str) -> Value {
+        json!({ "name": STATUS_TEMPLATES.en, "type": 3, "value": templates })
+    }
+
+    #[test]
+    fn splits_templates() {
+        let settings = parse_settings(
+            &options(json!([
+                templates(" {guilds} servers | | the market at {market}|"),
+                { "name": STATUS_KIND.en, "type": 3, "value": "playing" },
+            ])),
+            90,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings.templates,
+            ["{guilds} servers", "the market at {market}"]
+        );
+        assert_eq!(settings.kind, StatusKind::Playing);
+        assert_eq!(settings.interval_secs, 90);
+    }
+
+    #[test]
+    fn limits_templates() {
+        let too_many = ["status"; MAX_TEMPLATES + 1].join("|");
+        let too_long = "a".repeat(MAX_TEMPLATE_LEN + 1);
+        for input in ["|", too_many.as_str(), too_long.as_str()] {
+            assert!(matches!(
+                parse_settings(&options(json!([templates(input)])), 60),
+                Err(HandlerError::InvalidArgument(_))
+            ));
+        }
+        let longest = "a".repeat(MAX_TEMPLATE_LEN);
+        assert!(parse_settings(&options(json!([templates(&longest)])), 60).is_ok());
+        assert!(matches!(
+            parse_settings(&[], 60),
+            Err(HandlerError::MissingOption(_))
+        ));
+    }
+
+    #[test]
+    fn clamps_the_interval() {
+        let interval = |secs: i64| {
+            parse_settings(
+                &options(json!([
+                    templates("status"),
+                    { "name": STATUS_INTERVAL.en, "type": 4, "value": secs },
+                ])),
+                60,
+            )
+            .unwrap()
+            .interval_secs
+        };
+        assert_eq!(interval(-5), MIN_INTERVAL_SECS);
+        assert_eq!(interval(1), MIN_INTERVAL_SECS);
+        assert_eq!(interval(300), 300);
+    }
+}
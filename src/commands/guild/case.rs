@@ -0,0 +1,218 @@
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use serenity::utils::Color;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::command::{CommandOptionType, CommandType},
+    prelude::Context,
+};
+use std::str::FromStr;
+use tracing::*;
+
+use crate::builders::cases::case_to_embed;
+use crate::handler::cases::{refresh_log_entry, remove_log_entry, CaseLog};
+use crate::handler::command_details::require_permissions;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::FromCommandOptions;
+use crate::handler::response::CommandResponse;
+use crate::{
+    commands::{option_data::*, AppCmd},
+    util::LocalizedString,
+    Handler, HandlerError,
+};
+
+pub const NAME: LocalizedString = LocalizedString { en: "case" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands managing moderation cases!",
+};
+
+pub struct CaseCmd;
+
+command_options! {
+    struct EditArgs {
+        id: i64 = CASE_ID,
+        reason: String = REASON,
+    }
+}
+
+command_options! {
+    struct DeleteArgs {
+        id: i64 = CASE_ID,
+    }
+}
+
+command_options! {
+    struct LogArgs {
+        channel: Option<ChannelId> = CUSTOMCHANNEL,
+    }
+}
+
+enum CasePropertyTypes {
+    Edit,
+    Delete,
+    Log,
+}
+
+impl FromStr for CasePropertyTypes {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<CasePropertyTypes, Self::Err> {
+        match input {
+            "edit" => Ok(CasePropertyTypes::Edit),
+            "delete" => Ok(CasePropertyTypes::Delete),
+            "log" => Ok(CasePropertyTypes::Log),
+            _ => Err(()),
+        }
+    }
+}
+
+fn case_id(id: i64) -> u64 {
+    id.max(0) as u64
+}
+
+async fn create_embed_case(
+    embed_type: CasePropertyTypes,
+    option: &CommandDataOption,
+    context: &Context,
+    guild_id: GuildId,
+) -> Result<CreateEmbed, HandlerError> {
+    match embed_type {
+        CasePropertyTypes::Edit => {
+            let args = EditArgs::from_options(&option.options)?;
+            let id = case_id(args.id);
+            let case = context
+                .data
+                .write()
+                .await
+                .entry::<CaseLog>()
+                .or_insert_with(CaseLog::default)
+                .edit_reason(guild_id, id, &args.reason)?
+                .ok_or(HandlerError::CaseNotFound(id))?;
+            refresh_log_entry(context, &case).await;
+            Ok(case_to_embed(&case))
+        }
+        CasePropertyTypes::Delete => {
+            let id = case_id(DeleteArgs::from_options(&option.options)?.id);
+            let case = context
+                .data
+                .write()
+                .await
+                .entry::<CaseLog>()
+                .or_insert_with(CaseLog::default)
+                .delete(guild_id, id)?
+                .ok_or(HandlerError::CaseNotFound(id))?;
+            remove_log_entry(context, &case).await;
+
+            let mut embed = CreateEmbed::default();
+            embed
+                .title(format!("Case #{} deleted", case.id))
+                .color(Color::DARK_GREY);
+            Ok(embed)
+        }
+        CasePropertyTypes::Log => {
+            let channel_id = LogArgs::from_options(&option.options)?.channel;
+            context
+                .data
+                .write()
+                .await
+                .entry::<CaseLog>()
+                .or_insert_with(CaseLog::default)
+                .set_log_channel(guild_id, channel_id)?;
+
+            let mut embed = CreateEmbed::default();
+            embed.title("Mod-log").description(match channel_id {
+                Some(channel_id) => format!("Cases will be posted to <#{}>.", channel_id),
+                None => String::from("Cases will no longer be posted."),
+            });
+            Ok(embed)
+        }
+    }
+}
+
+#[async_trait]
+impl AppCmd for CaseCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en)
+            .dm_permission(false)
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(EDIT.en)
+                    .description(EDIT_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Integer)
+                            .name(CASE_ID.en)
+                            .description(CASE_ID_DESC.en)
+                            .min_int_value(1)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                            .required(true)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(DELETE.en)
+                    .description(DELETE_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Integer)
+                            .name(CASE_ID.en)
+                            .description(CASE_ID_DESC.en)
+                            .min_int_value(1)
+                            .required(true)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(MODLOG.en)
+                    .description(MODLOG_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Channel)
+                            .name(CUSTOMCHANNEL.en)
+                            .description(CUSTOMCHANNEL_DESC.en)
+                    })
+            });
+        cmd
+    }
+
+    #[instrument(skip(cmd, _handler, context))]
+    async fn handle(
+        cmd: &Invocation,
+        _handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+        let response_type = cmd.data.options.first().ok_or(HandlerError::EmptyCommand)?;
+        let embed_type = CasePropertyTypes::from_str(&response_type.name)
+            .map_err(|_| HandlerError::UnrecognizedCommand(response_type.name.to_string()))?;
+
+        require_permissions(
+            cmd,
+            match embed_type {
+                CasePropertyTypes::Log => Permissions::MANAGE_GUILD,
+                _ => Permissions::MODERATE_MEMBERS,
+            },
+        )?;
+
+        let embed = create_embed_case(embed_type, response_type, context, guild_id).await?;
+        Ok(CommandResponse::embed(embed).ephemeral(true))
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::permissions::Permissions;
+use serenity::model::user::User;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::command::{CommandOptionType, CommandType},
+    prelude::Context,
+};
+use tracing::*;
+
+use crate::builders::cases::cases_to_field;
+use crate::handler::cases::CaseLog;
+use crate::handler::command_details::require_permissions;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::FromCommandOptions;
+use crate::handler::response::CommandResponse;
+use crate::{
+    commands::{option_data::*, AppCmd},
+    util::LocalizedString,
+    Handler, HandlerError,
+};
+
+pub const NAME: LocalizedString = LocalizedString { en: "cases" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "List the moderation cases of a member!",
+};
+
+const MAX_LISTED: usize = 10;
+
+pub struct CasesCmd;
+
+command_options! {
+    struct CasesArgs {
+        user: User = TARGET,
+    }
+}
+
+#[async_trait]
+impl AppCmd for CasesCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en)
+            .dm_permission(false)
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::User)
+                    .name(TARGET.en)
+                    .description(TARGET_DESC.en)
+                    .required(true)
+            });
+        cmd
+    }
+
+    #[instrument(skip(cmd, _handler, context))]
+    async fn handle(
+        cmd: &Invocation,
+        _handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+        require_permissions(cmd, Permissions::MODERATE_MEMBERS)?;
+
+        let user = CasesArgs::from_options(&cmd.data.options)?.user;
+
+        let mut embed = CreateEmbed::default();
+        embed.title(format!("{}#{:04}", user.name, user.discriminator));
+        embed.thumbnail(user.face());
+        {
+            let data = context.data.read().await;
+            let log = data.get::<CaseLog>().ok_or(HandlerError::TypeMapNotFound)?;
+            cases_to_field(&log.cases_for(guild_id, user.id), MAX_LISTED, &mut embed);
+        }
+
+        Ok(CommandResponse::embed(embed).ephemeral(true))
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serenity::model::id::{GuildId, UserId};
+
+    use super::*;
+    use crate::handler::cases::CaseAction;
+    use crate::testing::{embeds, fixtures, MockDiscord};
+
+    #[tokio::test]
+    async fn lists_only_the_members_cases() {
+        let discord = MockDiscord::start().await;
+        let context = discord.context();
+        let guild_id = GuildId(fixtures::GUILD_ID);
+        let moderator = UserId(fixtures::INVOKER_ID);
+        let mut log = CaseLog::temporary();
+        for (target, reason) in [
+            (fixtures::OTHER_ID, "spam"),
+            (fixtures::OWNER_ID, "unrelated"),
+            (fixtures::OTHER_ID, "more spam"),
+        ] {
+            log.open(
+                guild_id,
+                CaseAction::Warn,
+                UserId(target),
+                moderator,
+                reason,
+            )
+            .unwrap();
+        }
+        log.open(
+            GuildId(2),
+            CaseAction::Ban,
+            UserId(fixtures::OTHER_ID),
+            moderator,
+            "elsewhere",
+        )
+        .unwrap();
+        context.data.write().await.insert::<CaseLog>(log);
+
+        let target = fixtures::OTHER_ID.to_string();
+        let cmd = fixtures::command(
+            NAME.en,
+            json!([{ "name": TARGET.en, "type": 6, "value": target }]),
+            json!({ "users": { &target: fixtures::user(fixtures::OTHER_ID, "other") } }),
+        );
+        let response = CasesCmd::handle(&cmd, &Handler::default(), &context)
+            .await
+            .unwrap();
+
+        let embeds = embeds(&response);
+        let field = &embeds[0]["fields"][0];
+        assert_eq!(field["name"], "**Cases (``2``)**");
+        let lines: Vec<&str> = field["value"].as_str().unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("``#3``") && lines[0].ends_with("more spam"));
+        assert!(lines[1].starts_with("``#1``") && lines[1].ends_with(": spam"));
+    }
+}
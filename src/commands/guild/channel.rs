@@ -0,0 +1,141 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::channel::Channel;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+use tracing::*;
+
+use crate::builders::channels::{category_to_embed, channel_to_embed};
+use crate::handler::command_details::{as_guild_channel, permissions_in};
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
+
+pub const NAME: LocalizedString = LocalizedString { en: "channel" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands accessing channels!",
+};
+
+pub struct ChannelCmd;
+
+app_command! {
+    command: ChannelCmd,
+    name: NAME,
+    desc: DESC,
+    dm_permission: false,
+    handler: handle_channel,
+    subcommands: enum ChannelPropertyTypes {
+        Info => INFO, INFO_DESC [opt(Channel, CUSTOMCHANNEL, CUSTOMCHANNEL_DESC)],
+    }
+}
+
+command_options! {
+    struct InfoArgs {
+        channel: Option<ChannelId> = CUSTOMCHANNEL,
+    }
+}
+
+/// Details of a channel in `guild_id` that `invoker` can see, shared with
+/// `/server channel custom`.
+pub(super) async fn channel_info(
+    context: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    invoker: &Member,
+) -> Result<CreateEmbed, HandlerError> {
+    let channel = channel_id.to_channel(context).await?;
+    if let Some(visible) = as_guild_channel(channel.clone()).filter(|c| c.guild_id == guild_id) {
+        let permissions = permissions_in(context, &visible, invoker).await?;
+        if !permissions.contains(Permissions::VIEW_CHANNEL) {
+            return Err(HandlerError::MissingPermissions);
+        }
+    }
+    match channel {
+        Channel::Guild(channel) if channel.guild_id == guild_id => Ok(channel_to_embed(&channel)),
+        Channel::Category(category) if category.guild_id == guild_id => {
+            Ok(category_to_embed(&category))
+        }
+        _ => Err(HandlerError::InvalidArgument(format!(
+            "<#{}> is not a channel of this server",
+            channel_id
+        ))),
+    }
+}
+
+#[instrument(skip(cmd, _handler, context))]
+async fn handle_channel(
+    cmd: &Invocation,
+    _handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let (sub, options) = parse_subcommand(&cmd.data.options)?;
+    let embed = match sub {
+        ChannelPropertyTypes::Info => {
+            let channel_id = InfoArgs::from_options(options)?
+                .channel
+                .unwrap_or(cmd.channel_id);
+            let invoker = cmd.member.as_ref().ok_or(HandlerError::NotGuild)?;
+            channel_info(context, guild_id, channel_id, invoker).await?
+        }
+    };
+    Ok(CommandResponse::embed(embed))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{fixtures, MockDiscord};
+
+    fn category(denied: u64) -> Value {
+        json!({
+            "id": fixtures::CHANNEL_ID.to_string(),
+            "guild_id": fixtures::GUILD_ID.to_string(),
+            "type": 4,
+            "name": "staff",
+            "position": 0,
+            "nsfw": false,
+            "permission_overwrites": [{
+                "id": fixtures::GUILD_ID.to_string(),
+                "type": 0,
+                "allow": "0",
+                "deny": denied.to_string(),
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn hides_channels_the_invoker_cant_see() {
+        let discord = MockDiscord::start().await;
+        let mut guild = fixtures::guild();
+        let mut everyone = fixtures::role(fixtures::GUILD_ID, "@everyone", 0);
+        everyone["permissions"] = json!(Permissions::VIEW_CHANNEL.bits().to_string());
+        guild["roles"] = json!([everyone]);
+        discord.route("GET", &format!("/guilds/{}", fixtures::GUILD_ID), guild);
+        let path = format!("/channels/{}", fixtures::CHANNEL_ID);
+        let context = discord.context();
+        let invoker: Member =
+            serde_json::from_value(fixtures::member(fixtures::INVOKER_ID, "invoker", None))
+                .unwrap();
+        let info = || {
+            channel_info(
+                &context,
+                GuildId(fixtures::GUILD_ID),
+                ChannelId(fixtures::CHANNEL_ID),
+                &invoker,
+            )
+        };
+
+        discord.route("GET", &path, category(0));
+        assert!(info().await.is_ok());
+        discord.route("GET", &path, category(Permissions::VIEW_CHANNEL.bits()));
+        assert!(matches!(
+            info().await,
+            Err(HandlerError::MissingPermissions)
+        ));
+    }
+}
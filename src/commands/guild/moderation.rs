@@ -0,0 +1,705 @@
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::model::Timestamp;
+use serenity::utils::Color;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::command::{CommandOptionType, CommandType},
+    prelude::Context,
+};
+use std::str::FromStr;
+use tracing::*;
+
+use crate::handler::cases::{record_case, CaseAction};
+use crate::handler::command_details::{channel_permissions, require_permissions};
+use crate::handler::confirm::ask_confirmation;
+use crate::handler::hierarchy::check_hierarchy;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::FromCommandOptions;
+use crate::handler::response::CommandResponse;
+use crate::{
+    commands::{option_data::*, AppCmd},
+    util::LocalizedString,
+    Handler, HandlerError,
+};
+
+pub const NAME: LocalizedString = LocalizedString { en: "mod" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands moderating the server!",
+};
+
+const NO_REASON: &str = "No reason provided";
+const MAX_TIMEOUT_MINUTES: i64 = 28 * 24 * 60;
+const MAX_SLOWMODE_SECONDS: i64 = 6 * 60 * 60;
+const BULK_DELETE_MAX_AGE: i64 = 14 * 24 * 60 * 60;
+
+pub struct ModCmd;
+
+command_options! {
+    struct ReasonArgs {
+        reason: Option<String> = REASON,
+    }
+}
+
+command_options! {
+    struct MemberArgs {
+        user: UserId = TARGET,
+        delete_days: Option<i64> = DELETE_DAYS,
+    }
+}
+
+command_options! {
+    struct PurgeArgs {
+        count: i64 = COUNT,
+    }
+}
+
+command_options! {
+    struct UnbanArgs {
+        user: UserId = TARGET_USER,
+    }
+}
+
+command_options! {
+    struct TimeoutArgs {
+        user: UserId = TARGET,
+        minutes: i64 = MINUTES,
+    }
+}
+
+command_options! {
+    struct SlowmodeArgs {
+        seconds: i64 = SECONDS,
+        channel: Option<ChannelId> = CUSTOMCHANNEL,
+    }
+}
+
+enum ModPropertyTypes {
+    Kick,
+    Ban,
+    Unban,
+    Timeout,
+    Purge,
+    Slowmode,
+}
+
+impl FromStr for ModPropertyTypes {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ModPropertyTypes, Self::Err> {
+        match input {
+            "kick" => Ok(ModPropertyTypes::Kick),
+            "ban" => Ok(ModPropertyTypes::Ban),
+            "unban" => Ok(ModPropertyTypes::Unban),
+            "timeout" => Ok(ModPropertyTypes::Timeout),
+            "purge" => Ok(ModPropertyTypes::Purge),
+            "slowmode" => Ok(ModPropertyTypes::Slowmode),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ModPropertyTypes {
+    fn required_permissions(&self) -> Permissions {
+        match self {
+            ModPropertyTypes::Kick => Permissions::KICK_MEMBERS,
+            ModPropertyTypes::Ban | ModPropertyTypes::Unban => Permissions::BAN_MEMBERS,
+            ModPropertyTypes::Timeout => Permissions::MODERATE_MEMBERS,
+            ModPropertyTypes::Purge => Permissions::MANAGE_MESSAGES,
+            ModPropertyTypes::Slowmode => Permissions::MANAGE_CHANNELS,
+        }
+    }
+}
+
+fn create_embed_action(title: &str, target: Option<UserId>, reason: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title(title).color(Color::ORANGE);
+    if let Some(target) = target {
+        embed.field("Member", format!("<@{}>", target), true);
+    }
+    embed.field("Reason", reason, true);
+    embed
+}
+
+fn timeout_until(minutes: i64) -> Result<Option<Timestamp>, HandlerError> {
+    if minutes == 0 {
+        return Ok(None);
+    }
+    Timestamp::from_unix_timestamp(Timestamp::now().unix_timestamp() + minutes * 60)
+        .map(Some)
+        .map_err(|_| HandlerError::UnexpectedData)
+}
+
+/// Deletes up to `count` of the messages sent before `before`.
+async fn purge_messages(
+    context: &Context,
+    channel_id: ChannelId,
+    before: MessageId,
+    count: u64,
+) -> Result<usize, HandlerError> {
+    let oldest_allowed = Timestamp::now().unix_timestamp() - BULK_DELETE_MAX_AGE;
+    let messages: Vec<_> = channel_id
+        .messages(context, |r| r.before(before).limit(count))
+        .await?
+        .into_iter()
+        .filter(|m| m.timestamp.unix_timestamp() > oldest_allowed)
+        .map(|m| m.id)
+        .collect();
+
+    match messages.as_slice() {
+        [] => {}
+        [single] => channel_id.delete_message(context, *single).await?,
+        ids => channel_id.delete_messages(context, ids).await?,
+    }
+    Ok(messages.len())
+}
+
+async fn handle_subcommand(
+    embed_type: ModPropertyTypes,
+    option: &CommandDataOption,
+    cmd: &Invocation,
+    context: &Context,
+    guild_id: GuildId,
+) -> Result<CommandResponse, HandlerError> {
+    let invoker = cmd.member.as_ref().ok_or(HandlerError::NotGuild)?;
+    let options = &option.options;
+    let reason = ReasonArgs::from_options(options)?
+        .reason
+        .unwrap_or_else(|| String::from(NO_REASON));
+
+    match embed_type {
+        ModPropertyTypes::Kick | ModPropertyTypes::Ban => {
+            let args = MemberArgs::from_options(options)?;
+            check_hierarchy(context, guild_id, invoker, args.user).await?;
+
+            let (prompt, done) = if matches!(embed_type, ModPropertyTypes::Kick) {
+                ("Kick member?", "Member kicked")
+            } else {
+                ("Ban member?", "Member banned")
+            };
+            let confirmation = ask_confirmation(
+                cmd,
+                context,
+                create_embed_action(prompt, Some(args.user), &reason),
+            )
+            .await?;
+            if !confirmation.is_confirmed() {
+                return confirmation
+                    .resolve(cmd, context, confirmation.aborted_embed())
+                    .await;
+            }
+
+            let action = if matches!(embed_type, ModPropertyTypes::Kick) {
+                guild_id
+                    .kick_with_reason(context, args.user, &reason)
+                    .await?;
+                CaseAction::Kick
+            } else {
+                let delete_days = args.delete_days.unwrap_or(0).clamp(0, 7);
+                guild_id
+                    .ban_with_reason(context, args.user, delete_days as u8, &reason)
+                    .await?;
+                CaseAction::Ban
+            };
+            // The member is gone either way, so the prompt says so before
+            // a failure to record the case is reported.
+            let recorded =
+                record_case(context, guild_id, action, args.user, cmd.user.id, &reason).await;
+            let response = confirmation
+                .resolve(
+                    cmd,
+                    context,
+                    create_embed_action(done, Some(args.user), &reason),
+                )
+                .await?;
+            if let Err(err) = recorded {
+                warn!(?err, "could not record case");
+                cmd.followup(
+                    context,
+                    CommandResponse::content(format!("The case could not be recorded: {}", err))
+                        .ephemeral(true),
+                )
+                .await?;
+            }
+            Ok(response)
+        }
+        ModPropertyTypes::Purge => {
+            let count = PurgeArgs::from_options(options)?.count.clamp(1, 100) as u64;
+
+            let mut prompt = create_embed_action("Purge messages?", None, &reason);
+            prompt.field("Messages", count, true);
+            let confirmation = ask_confirmation(cmd, context, prompt).await?;
+            if !confirmation.is_confirmed() {
+                return confirmation
+                    .resolve(cmd, context, confirmation.aborted_embed())
+                    .await;
+            }
+
+            let deleted = purge_messages(context, cmd.channel_id, cmd.invoked_id(), count).await?;
+            let mut embed = create_embed_action("Messages purged", None, &reason);
+            embed.field("Messages", deleted, true);
+            if (deleted as u64) < count {
+                embed.footer(|f| f.text("Messages older than 14 days can't be purged."));
+            }
+            confirmation.resolve(cmd, context, embed).await
+        }
+        ModPropertyTypes::Unban => {
+            let user = UnbanArgs::from_options(options)?.user;
+            context
+                .http
+                .remove_ban(guild_id.0, user.0, Some(&reason))
+                .await?;
+            record_case(
+                context,
+                guild_id,
+                CaseAction::Unban,
+                user,
+                cmd.user.id,
+                &reason,
+            )
+            .await?;
+
+            let embed = create_embed_action("User unbanned", Some(user), &reason);
+            Ok(CommandResponse::embed(embed).ephemeral(true))
+        }
+        ModPropertyTypes::Timeout => {
+            let TimeoutArgs { user, minutes } = TimeoutArgs::from_options(options)?;
+            let minutes = minutes.clamp(0, MAX_TIMEOUT_MINUTES);
+            check_hierarchy(context, guild_id, invoker, user).await?;
+
+            let until = timeout_until(minutes)?;
+            let mut map = serde_json::Map::new();
+            map.insert(
+                String::from("communication_disabled_until"),
+                serde_json::to_value(until).map_err(|_| HandlerError::UnexpectedData)?,
+            );
+            context
+                .http
+                .edit_member(guild_id.0, user.0, &map, Some(&reason))
+                .await?;
+            let action = if until.is_some() {
+                CaseAction::Timeout
+            } else {
+                CaseAction::TimeoutRemoved
+            };
+            record_case(context, guild_id, action, user, cmd.user.id, &reason).await?;
+
+            let embed = match until {
+                Some(until) => {
+                    let mut embed = create_embed_action("Member timed out", Some(user), &reason);
+                    embed.field("Until", format!("<t:{}:f>", until.unix_timestamp()), true);
+                    embed
+                }
+                None => create_embed_action("Timeout removed", Some(user), &reason),
+            };
+            Ok(CommandResponse::embed(embed).ephemeral(true))
+        }
+        ModPropertyTypes::Slowmode => {
+            let args = SlowmodeArgs::from_options(options)?;
+            let seconds = args.seconds.clamp(0, MAX_SLOWMODE_SECONDS);
+            let channel_id = args.channel.unwrap_or(cmd.channel_id);
+            // The interaction's permissions only cover the channel it was
+            // sent in.
+            if channel_id != cmd.channel_id
+                && !channel_permissions(context, guild_id, channel_id, invoker)
+                    .await?
+                    .contains(Permissions::MANAGE_CHANNELS)
+            {
+                return Err(HandlerError::MissingPermissions);
+            }
+
+            let mut map = serde_json::Map::new();
+            map.insert(String::from("rate_limit_per_user"), seconds.into());
+            context
+                .http
+                .edit_channel(channel_id.0, &map, Some(&reason))
+                .await?;
+
+            let mut embed = create_embed_action("Slowmode updated", None, &reason);
+            embed
+                .field("Channel", format!("<#{}>", channel_id), true)
+                .field(
+                    "Delay",
+                    if seconds == 0 {
+                        String::from("Disabled")
+                    } else {
+                        format!("{}s", seconds)
+                    },
+                    true,
+                );
+            Ok(CommandResponse::embed(embed).ephemeral(true))
+        }
+    }
+}
+
+#[async_trait]
+impl AppCmd for ModCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en)
+            .dm_permission(false)
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(KICK.en)
+                    .description(KICK_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::User)
+                            .name(TARGET.en)
+                            .description(TARGET_DESC.en)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(BAN.en)
+                    .description(BAN_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::User)
+                            .name(TARGET.en)
+                            .description(TARGET_DESC.en)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Integer)
+                            .name(DELETE_DAYS.en)
+                            .description(DELETE_DAYS_DESC.en)
+                            .min_int_value(0)
+                            .max_int_value(7)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(UNBAN.en)
+                    .description(UNBAN_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::User)
+                            .name(TARGET_USER.en)
+                            .description(TARGET_USER_DESC.en)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(TIMEOUT.en)
+                    .description(TIMEOUT_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::User)
+                            .name(TARGET.en)
+                            .description(TARGET_DESC.en)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Integer)
+                            .name(MINUTES.en)
+                            .description(MINUTES_DESC.en)
+                            .min_int_value(0)
+                            .max_int_value(MAX_TIMEOUT_MINUTES)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(PURGE.en)
+                    .description(PURGE_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Integer)
+                            .name(COUNT.en)
+                            .description(COUNT_DESC.en)
+                            .min_int_value(1)
+                            .max_int_value(100)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                    })
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommand)
+                    .name(SLOWMODE.en)
+                    .description(SLOWMODE_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Integer)
+                            .name(SECONDS.en)
+                            .description(SECONDS_DESC.en)
+                            .min_int_value(0)
+                            .max_int_value(MAX_SLOWMODE_SECONDS)
+                            .required(true)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::Channel)
+                            .name(CUSTOMCHANNEL.en)
+                            .description(CUSTOMCHANNEL_DESC.en)
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::String)
+                            .name(REASON.en)
+                            .description(REASON_DESC.en)
+                    })
+            });
+        cmd
+    }
+
+    #[instrument(skip(cmd, _handler, context))]
+    async fn handle(
+        cmd: &Invocation,
+        _handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+        let response_type = cmd.data.options.first().ok_or(HandlerError::EmptyCommand)?;
+        let embed_type = ModPropertyTypes::from_str(&response_type.name)
+            .map_err(|_| HandlerError::UnrecognizedCommand(response_type.name.to_string()))?;
+
+        require_permissions(cmd, embed_type.required_permissions())?;
+        handle_subcommand(embed_type, response_type, cmd, context, guild_id).await
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::handler::cases::CaseLog;
+    use crate::handler::response::ResponseMode;
+    use crate::testing::{embeds, fixtures, MockDiscord};
+
+    const MODERATOR: u64 = 11;
+    const ADMIN: u64 = 12;
+    const OTHER_CHANNEL: u64 = 201;
+
+    fn roles(roles: &[u64]) -> Value {
+        Value::from(roles.iter().map(|r| r.to_string()).collect::<Vec<_>>())
+    }
+
+    fn member_path(id: u64) -> String {
+        format!("/guilds/{}/members/{}", fixtures::GUILD_ID, id)
+    }
+
+    /// A guild where the invoker is an admin and the target and bot have
+    /// the given roles.
+    async fn guild(target_roles: &[u64], bot_roles: &[u64]) -> (MockDiscord, Context) {
+        let discord = MockDiscord::start().await;
+        let mut guild = fixtures::guild();
+        guild["roles"] = json!([
+            fixtures::role(fixtures::GUILD_ID, "@everyone", 0),
+            fixtures::role(MODERATOR, "moderator", 1),
+            fixtures::role(ADMIN, "admin", 2),
+        ]);
+        discord.route("GET", &format!("/guilds/{}", fixtures::GUILD_ID), guild);
+        let mut target = fixtures::member(fixtures::OTHER_ID, "other", None);
+        target["roles"] = roles(target_roles);
+        discord.route("GET", &member_path(fixtures::OTHER_ID), target.clone());
+        discord.route("PATCH", &member_path(fixtures::OTHER_ID), target);
+        let mut bot = fixtures::member(fixtures::APPLICATION_ID, "bot", None);
+        bot["roles"] = roles(bot_roles);
+        discord.route("GET", &member_path(fixtures::APPLICATION_ID), bot);
+        let context = discord.context();
+        context
+            .data
+            .write()
+            .await
+            .insert::<CaseLog>(CaseLog::temporary());
+        (discord, context)
+    }
+
+    async fn moderate(
+        context: &Context,
+        sub: &str,
+        options: Value,
+    ) -> Result<CommandResponse, HandlerError> {
+        let mut invoker = fixtures::member(fixtures::INVOKER_ID, "invoker", None);
+        invoker["roles"] = roles(&[ADMIN]);
+        invoker["permissions"] = json!("8");
+        let cmd = fixtures::command_by(
+            invoker,
+            NAME.en,
+            json!([{ "name": sub, "type": 1, "options": options }]),
+            json!({}),
+        );
+        ModCmd::handle(&cmd, &Handler::default(), context).await
+    }
+
+    fn target() -> Value {
+        json!({ "name": TARGET.en, "type": 6, "value": fixtures::OTHER_ID.to_string() })
+    }
+
+    #[tokio::test]
+    async fn unanswered_kicks_do_nothing() {
+        let (discord, context) = guild(&[MODERATOR], &[ADMIN]).await;
+        let original = format!(
+            "/webhooks/{}/interaction-token/messages/@original",
+            fixtures::APPLICATION_ID
+        );
+        discord.route("GET", &original, fixtures::message(2000, ""));
+        discord.route("PATCH", &original, fixtures::message(2000, ""));
+
+        let result = moderate(&context, KICK.en, json!([target()])).await;
+
+        assert_eq!(result.unwrap().mode, ResponseMode::Handled);
+        let prompt = &discord.responses()[0];
+        assert_eq!(prompt["embeds"][0]["title"], "Kick member?");
+        assert_eq!(prompt["flags"], 64);
+        let requests = discord.requests();
+        let edit = requests.last().unwrap();
+        assert_eq!(edit.path, original);
+        assert_eq!(
+            edit.body["embeds"][0]["description"],
+            "No answer, nothing was done."
+        );
+        assert!(requests.iter().all(|r| r.method != "DELETE"));
+    }
+
+    #[tokio::test]
+    async fn only_kicks_and_bans_lower_members() {
+        let (discord, context) = guild(&[ADMIN], &[ADMIN]).await;
+        for sub in [KICK.en, BAN.en] {
+            let result = moderate(&context, sub, json!([target()])).await;
+            assert!(matches!(result, Err(HandlerError::InvokerHierarchy)));
+        }
+        assert!(discord.responses().is_empty());
+
+        let (discord, context) = guild(&[MODERATOR], &[MODERATOR]).await;
+        let result = moderate(&context, BAN.en, json!([target()])).await;
+        assert!(matches!(result, Err(HandlerError::BotHierarchy)));
+        assert!(discord.responses().is_empty());
+    }
+
+    #[tokio::test]
+    async fn times_out_lower_members() {
+        let (discord, context) = guild(&[MODERATOR], &[ADMIN]).await;
+        let minutes = json!({ "name": MINUTES.en, "type": 4, "value": 10 });
+
+        let response = moderate(&context, TIMEOUT.en, json!([target(), minutes]))
+            .await
+            .unwrap();
+
+        assert_eq!(embeds(&response)[0]["title"], "Member timed out");
+        let requests = discord.requests();
+        let edit = requests
+            .iter()
+            .find(|r| r.method == "PATCH" && r.path == member_path(fixtures::OTHER_ID))
+            .unwrap();
+        assert!(edit.body["communication_disabled_until"].is_string());
+        let data = context.data.read().await;
+        let cases = data.get::<CaseLog>().unwrap();
+        let cases = cases.cases_for(GuildId(fixtures::GUILD_ID), UserId(fixtures::OTHER_ID));
+        assert!(matches!(cases[..], [case] if case.action == CaseAction::Timeout));
+    }
+
+    #[tokio::test]
+    async fn only_times_out_lower_members() {
+        let (discord, context) = guild(&[ADMIN], &[ADMIN]).await;
+        let minutes = json!({ "name": MINUTES.en, "type": 4, "value": 10 });
+
+        let result = moderate(&context, TIMEOUT.en, json!([target(), minutes])).await;
+
+        assert!(matches!(result, Err(HandlerError::InvokerHierarchy)));
+        assert!(discord.requests().iter().all(|r| r.method != "PATCH"));
+    }
+
+    #[tokio::test]
+    async fn slowmode_needs_manage_channels_in_the_target_channel() {
+        let (discord, context) = guild(&[], &[ADMIN]).await;
+        let mut guild = fixtures::guild();
+        let mut admin = fixtures::role(ADMIN, "admin", 2);
+        admin["permissions"] = json!(Permissions::MANAGE_CHANNELS.bits().to_string());
+        guild["roles"] = json!([fixtures::role(fixtures::GUILD_ID, "@everyone", 0), admin]);
+        discord.route("GET", &format!("/guilds/{}", fixtures::GUILD_ID), guild);
+        let channel = |denied: u64| {
+            json!({
+                "id": OTHER_CHANNEL.to_string(),
+                "guild_id": fixtures::GUILD_ID.to_string(),
+                "type": 0,
+                "name": "staff",
+                "position": 1,
+                "nsfw": false,
+                "permission_overwrites": [{
+                    "id": ADMIN.to_string(),
+                    "type": 0,
+                    "allow": "0",
+                    "deny": denied.to_string(),
+                }],
+            })
+        };
+        let path = format!("/channels/{}", OTHER_CHANNEL);
+        let options = json!([
+            { "name": SECONDS.en, "type": 4, "value": 30 },
+            { "name": CUSTOMCHANNEL.en, "type": 7, "value": OTHER_CHANNEL.to_string() },
+        ]);
+
+        discord.route("GET", &path, channel(Permissions::MANAGE_CHANNELS.bits()));
+        let result = moderate(&context, SLOWMODE.en, options.clone()).await;
+        assert!(matches!(result, Err(HandlerError::MissingPermissions)));
+        assert!(discord.requests().iter().all(|r| r.method != "PATCH"));
+
+        discord.route("GET", &path, channel(0));
+        discord.route("PATCH", &path, channel(0));
+        moderate(&context, SLOWMODE.en, options).await.unwrap();
+        let requests = discord.requests();
+        let edit = requests.last().unwrap();
+        assert_eq!(edit.path, path);
+        assert_eq!(edit.body["rate_limit_per_user"], 30);
+    }
+
+    #[tokio::test]
+    async fn purges_messages_before_the_command() {
+        let discord = MockDiscord::start().await;
+        let path = format!("/channels/{}/messages", fixtures::CHANNEL_ID);
+        let mut recent = fixtures::message(10, "recent");
+        recent["timestamp"] = json!(Timestamp::now().to_string());
+        discord.route(
+            "GET",
+            &path,
+            json!([recent, fixtures::message(9, "too old to bulk delete")]),
+        );
+        let context = discord.context();
+
+        let deleted = purge_messages(&context, ChannelId(fixtures::CHANNEL_ID), MessageId(50), 5)
+            .await
+            .unwrap();
+
+        assert_eq!(deleted, 1);
+        let requests = discord.requests();
+        assert_eq!(requests[0].query.as_deref(), Some("limit=5&before=50"));
+        assert_eq!(requests[1].method, "DELETE");
+        assert_eq!(requests[1].path, format!("{}/10", path));
+    }
+}
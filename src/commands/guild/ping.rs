@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::{
+    builder::CreateApplicationCommand, model::prelude::command::CommandType, prelude::Context,
+};
+use tracing::*;
+
+use super::botinfo::{format_latency, gateway_latency, rest_latency};
+use crate::handler::invocation::Invocation;
+use crate::handler::response::CommandResponse;
+use crate::{commands::AppCmd, util::LocalizedString, Handler, HandlerError};
+
+pub const NAME: LocalizedString = LocalizedString { en: "ping" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Checks how fast the bot can reach Discord!",
+};
+
+pub struct PingCmd;
+
+#[async_trait]
+impl AppCmd for PingCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en);
+        cmd
+    }
+
+    #[instrument(skip(_cmd, _handler, context))]
+    async fn handle(
+        _cmd: &Invocation,
+        _handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        let rest = rest_latency(context).await?;
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Pong!")
+            .field(
+                "Gateway",
+                format_latency(gateway_latency(context).await),
+                true,
+            )
+            .field("REST", format!("{} ms", rest.as_millis()), true);
+        Ok(CommandResponse::embed(embed))
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
@@ -0,0 +1,179 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::guild::Role;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use tracing::*;
+
+use crate::builders::roles::{members_to_pages, role_to_embed};
+use crate::handler::command_details::{fetch_members, parse_user_ids, require_permissions};
+use crate::handler::hierarchy::check_role_hierarchy;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::paginate::paginate;
+use crate::handler::response::CommandResponse;
+use crate::{
+    commands::option_data::*,
+    util::{truncate, LocalizedString},
+    Handler, HandlerError,
+};
+
+pub const NAME: LocalizedString = LocalizedString { en: "role" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands accessing and managing roles!",
+};
+
+const MAX_BULK_MEMBERS: usize = 50;
+const FIELD_LIMIT: usize = 1024;
+
+pub struct RoleCmd;
+
+app_command! {
+    command: RoleCmd,
+    name: NAME,
+    desc: DESC,
+    dm_permission: false,
+    handler: handle_role,
+    subcommands: enum RolePropertyTypes {
+        Info => INFO, INFO_DESC [req(Role, ROLE_TARGET, ROLE_TARGET_DESC)],
+        Members => ROLE_MEMBERS, ROLE_MEMBERS_DESC [req(Role, ROLE_TARGET, ROLE_TARGET_DESC)],
+        Give => GIVE, GIVE_DESC [
+            req(Role, ROLE_TARGET, ROLE_TARGET_DESC),
+            req(String, BULK_MEMBERS, BULK_MEMBERS_DESC),
+        ],
+        Take => TAKE, TAKE_DESC [
+            req(Role, ROLE_TARGET, ROLE_TARGET_DESC),
+            req(String, BULK_MEMBERS, BULK_MEMBERS_DESC),
+        ],
+    }
+}
+
+command_options! {
+    struct RoleArgs {
+        role: Role = ROLE_TARGET,
+    }
+}
+
+command_options! {
+    struct BulkArgs {
+        members: String = BULK_MEMBERS,
+    }
+}
+
+/// Members holding `role`. Everyone holds the @everyone role.
+async fn role_holders(
+    context: &Context,
+    guild_id: GuildId,
+    role: &Role,
+) -> Result<Vec<UserId>, HandlerError> {
+    let everyone = role.id.0 == guild_id.0;
+    Ok(fetch_members(context, guild_id)
+        .await?
+        .into_iter()
+        .filter(|m| everyone || m.roles.contains(&role.id))
+        .map(|m| m.user.id)
+        .collect())
+}
+
+async fn change_members(
+    cmd: &Invocation,
+    context: &Context,
+    guild_id: GuildId,
+    role: &Role,
+    options: &[CommandDataOption],
+    give: bool,
+) -> Result<CreateEmbed, HandlerError> {
+    let targets = parse_user_ids(&BulkArgs::from_options(options)?.members);
+    if targets.is_empty() || targets.len() > MAX_BULK_MEMBERS {
+        return Err(HandlerError::InvalidArgument(format!(
+            "between 1 and {} members",
+            MAX_BULK_MEMBERS
+        )));
+    }
+    let invoker = cmd.member.as_ref().ok_or(HandlerError::UserNotFound)?;
+    check_role_hierarchy(context, guild_id, Some(invoker), &[role.id]).await?;
+
+    let reason = format!("Requested by {}", cmd.user.tag());
+    let mut changed = vec![];
+    let mut failed = vec![];
+    for target in targets {
+        let result = if give {
+            context
+                .http
+                .add_member_role(guild_id.0, target.0, role.id.0, Some(&reason))
+                .await
+        } else {
+            context
+                .http
+                .remove_member_role(guild_id.0, target.0, role.id.0, Some(&reason))
+                .await
+        };
+        match result {
+            Ok(()) => changed.push(format!("<@{}>", target)),
+            Err(err) => {
+                debug!(?err, ?target, "could not change member role");
+                failed.push(format!("<@{}>", target));
+            }
+        }
+    }
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(if give { "Role given" } else { "Role taken" })
+        .color(if failed.is_empty() {
+            Color::DARK_GREEN
+        } else {
+            Color::ORANGE
+        })
+        .description(format!("<@&{}>", role.id));
+    if !changed.is_empty() {
+        embed.field(
+            format!("Updated ({})", changed.len()),
+            truncate(&changed.join(" "), FIELD_LIMIT),
+            false,
+        );
+    }
+    if !failed.is_empty() {
+        embed.field(
+            format!("Failed ({})", failed.len()),
+            truncate(&failed.join(" "), FIELD_LIMIT),
+            false,
+        );
+    }
+    Ok(embed)
+}
+
+#[instrument(skip(cmd, _handler, context))]
+async fn handle_role(
+    cmd: &Invocation,
+    _handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+    let role = &RoleArgs::from_options(options)?.role;
+
+    match embed_type {
+        RolePropertyTypes::Info => {
+            let holders = role_holders(context, guild_id, role).await?;
+            Ok(CommandResponse::embed(role_to_embed(role, holders.len())))
+        }
+        RolePropertyTypes::Members => {
+            let holders: Vec<String> = role_holders(context, guild_id, role)
+                .await?
+                .iter()
+                .map(|id| format!("<@{}>", id))
+                .collect();
+            let pages = members_to_pages(&format!("Members of {}", role.name), &holders);
+            paginate(cmd, context, pages, false).await
+        }
+        RolePropertyTypes::Give | RolePropertyTypes::Take => {
+            require_permissions(cmd, Permissions::MANAGE_ROLES)?;
+            let give = matches!(embed_type, RolePropertyTypes::Give);
+            let embed = change_members(cmd, context, guild_id, role, options, give).await?;
+            Ok(CommandResponse::embed(embed).ephemeral(true))
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::model::permissions::Permissions;
+use serenity::utils::Color;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::command::{CommandOptionType, CommandType},
+    prelude::Context,
+};
+use std::str::FromStr;
+use tracing::*;
+
+use crate::builders::roles::{role_menu_components, role_menu_embed};
+use crate::handler::command_details::require_permissions;
+use crate::handler::hierarchy::check_role_hierarchy;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::FromCommandOptions;
+use crate::handler::response::CommandResponse;
+use crate::handler::role_menus::{refresh_published, MenuStyle, RoleMenus, MAX_MENU_ROLES};
+use crate::{
+    commands::{option_data::*, AppCmd},
+    util::LocalizedString,
+    Handler, HandlerError,
+};
+
+pub const NAME: LocalizedString = LocalizedString { en: "roles" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands managing self-assignable roles!",
+};
+
+pub struct RolesCmd;
+
+command_options! {
+    struct CreateArgs {
+        title: String = MENU_TITLE,
+        style: Option<String> = MENU_STYLE,
+        max: Option<i64> = MENU_MAX,
+        exclusive: Option<bool> = MENU_EXCLUSIVE,
+        required: Option<RoleId> = MENU_REQUIRED,
+    }
+}
+
+command_options! {
+    struct MenuRoleArgs {
+        id: i64 = MENU_ID,
+        role: RoleId = MENU_ROLE,
+    }
+}
+
+command_options! {
+    struct PublishArgs {
+        id: i64 = MENU_ID,
+        channel: Option<ChannelId> = CUSTOMCHANNEL,
+    }
+}
+
+enum RolesPropertyTypes {
+    Create,
+    Add,
+    Remove,
+    Publish,
+}
+
+impl FromStr for RolesPropertyTypes {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<RolesPropertyTypes, Self::Err> {
+        match input {
+            "create" => Ok(RolesPropertyTypes::Create),
+            "add" => Ok(RolesPropertyTypes::Add),
+            "remove" => Ok(RolesPropertyTypes::Remove),
+            "publish" => Ok(RolesPropertyTypes::Publish),
+            _ => Err(()),
+        }
+    }
+}
+
+fn menu_id(id: i64) -> u64 {
+    id.max(0) as u64
+}
+
+async fn create_embed_roles(
+    embed_type: RolesPropertyTypes,
+    option: &CommandDataOption,
+    cmd: &Invocation,
+    context: &Context,
+    guild_id: GuildId,
+) -> Result<CreateEmbed, HandlerError> {
+    match embed_type {
+        RolesPropertyTypes::Create => {
+            let args = CreateArgs::from_options(&option.options)?;
+            let style = match args.style.as_deref() {
+                Some("select") => MenuStyle::Select,
+                _ => MenuStyle::Buttons,
+            };
+            let menu = context
+                .data
+                .write()
+                .await
+                .entry::<RoleMenus>()
+                .or_insert_with(RoleMenus::default)
+                .create(
+                    guild_id,
+                    &args.title,
+                    style,
+                    args.max.map(|max| max.max(1) as u64),
+                    args.exclusive.unwrap_or(false),
+                    args.required,
+                )?;
+
+            let mut embed = role_menu_embed(&menu);
+            embed.color(Color::DARK_GREEN).description(format!(
+                "Created role menu #{}, add roles with `/roles menu add`.",
+                menu.id
+            ));
+            Ok(embed)
+        }
+        RolesPropertyTypes::Add | RolesPropertyTypes::Remove => {
+            let args = MenuRoleArgs::from_options(&option.options)?;
+            let (id, role) = (menu_id(args.id), args.role);
+            let adding = matches!(embed_type, RolesPropertyTypes::Add);
+            if adding {
+                // Members get menu roles through the bot, so only roles the
+                // invoker could hand out themselves may be added.
+                let invoker = cmd.member.as_ref().ok_or(HandlerError::UserNotFound)?;
+                check_role_hierarchy(context, guild_id, Some(invoker), &[role]).await?;
+            }
+            let (menu, fits) = context
+                .data
+                .write()
+                .await
+                .entry::<RoleMenus>()
+                .or_insert_with(RoleMenus::default)
+                .update(guild_id, id, |menu| {
+                    if !adding {
+                        menu.roles.retain(|r| *r != role);
+                    } else if !menu.roles.contains(&role) {
+                        if menu.roles.len() >= MAX_MENU_ROLES {
+                            return false;
+                        }
+                        menu.roles.push(role);
+                    }
+                    true
+                })?
+                .ok_or(HandlerError::MenuNotFound(id))?;
+            if !fits {
+                return Err(HandlerError::MenuFull);
+            }
+            refresh_published(context, guild_id, &menu).await;
+            Ok(role_menu_embed(&menu))
+        }
+        RolesPropertyTypes::Publish => {
+            let args = PublishArgs::from_options(&option.options)?;
+            let id = menu_id(args.id);
+            let menu = context
+                .data
+                .read()
+                .await
+                .get::<RoleMenus>()
+                .and_then(|menus| menus.menu(guild_id, id).cloned())
+                .ok_or(HandlerError::MenuNotFound(id))?;
+            let channel_id = args.channel.unwrap_or(cmd.channel_id);
+
+            let roles = guild_id.roles(context).await?;
+            let components = role_menu_components(&menu, &roles);
+            let message = channel_id
+                .send_message(context, |m| {
+                    m.set_embed(role_menu_embed(&menu)).components(|c| {
+                        *c = components;
+                        c
+                    })
+                })
+                .await?;
+            context
+                .data
+                .write()
+                .await
+                .entry::<RoleMenus>()
+                .or_insert_with(RoleMenus::default)
+                .update(guild_id, id, |menu| {
+                    menu.published = Some((channel_id, message.id))
+                })?;
+
+            let mut embed = CreateEmbed::default();
+            embed
+                .title(format!("Role menu #{} published", id))
+                .description(format!("Posted in <#{}>.", channel_id));
+            Ok(embed)
+        }
+    }
+}
+
+#[async_trait]
+impl AppCmd for RolesCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en)
+            .dm_permission(false)
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::SubCommandGroup)
+                    .name(MENU.en)
+                    .description(MENU_DESC.en)
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::SubCommand)
+                            .name(MENU_CREATE.en)
+                            .description(MENU_CREATE_DESC.en)
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::String)
+                                    .name(MENU_STYLE.en)
+                                    .description(MENU_STYLE_DESC.en)
+                                    .required(true)
+                                    .add_string_choice("Buttons", "buttons")
+                                    .add_string_choice("Select menu", "select")
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::String)
+                                    .name(MENU_TITLE.en)
+                                    .description(MENU_TITLE_DESC.en)
+                                    .required(true)
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Integer)
+                                    .name(MENU_MAX.en)
+                                    .description(MENU_MAX_DESC.en)
+                                    .min_int_value(1)
+                                    .max_int_value(MAX_MENU_ROLES as i64)
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Boolean)
+                                    .name(MENU_EXCLUSIVE.en)
+                                    .description(MENU_EXCLUSIVE_DESC.en)
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Role)
+                                    .name(MENU_REQUIRED.en)
+                                    .description(MENU_REQUIRED_DESC.en)
+                            })
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::SubCommand)
+                            .name(MENU_ADD.en)
+                            .description(MENU_ADD_DESC.en)
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Integer)
+                                    .name(MENU_ID.en)
+                                    .description(MENU_ID_DESC.en)
+                                    .min_int_value(1)
+                                    .required(true)
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Role)
+                                    .name(MENU_ROLE.en)
+                                    .description(MENU_ROLE_DESC.en)
+                                    .required(true)
+                            })
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::SubCommand)
+                            .name(MENU_REMOVE.en)
+                            .description(MENU_REMOVE_DESC.en)
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Integer)
+                                    .name(MENU_ID.en)
+                                    .description(MENU_ID_DESC.en)
+                                    .min_int_value(1)
+                                    .required(true)
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Role)
+                                    .name(MENU_ROLE.en)
+                                    .description(MENU_ROLE_DESC.en)
+                                    .required(true)
+                            })
+                    })
+                    .create_sub_option(|sub| {
+                        sub.kind(CommandOptionType::SubCommand)
+                            .name(MENU_PUBLISH.en)
+                            .description(MENU_PUBLISH_DESC.en)
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Integer)
+                                    .name(MENU_ID.en)
+                                    .description(MENU_ID_DESC.en)
+                                    .min_int_value(1)
+                                    .required(true)
+                            })
+                            .create_sub_option(|opt| {
+                                opt.kind(CommandOptionType::Channel)
+                                    .name(CUSTOMCHANNEL.en)
+                                    .description(CUSTOMCHANNEL_DESC.en)
+                            })
+                    })
+            });
+        cmd
+    }
+
+    #[instrument(skip(cmd, _handler, context))]
+    async fn handle(
+        cmd: &Invocation,
+        _handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        require_permissions(cmd, Permissions::MANAGE_ROLES)?;
+        let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+        let group = cmd.data.options.first().ok_or(HandlerError::EmptyCommand)?;
+        let response_type = group.options.first().ok_or(HandlerError::EmptyCommand)?;
+        let embed_type = RolesPropertyTypes::from_str(&response_type.name)
+            .map_err(|_| HandlerError::UnrecognizedCommand(response_type.name.to_string()))?;
+
+        let embed = create_embed_roles(embed_type, response_type, cmd, context, guild_id).await?;
+        Ok(CommandResponse::embed(embed).ephemeral(true))
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
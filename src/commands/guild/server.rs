@@ -1,22 +1,32 @@
-use async_trait::async_trait;
 use serenity::builder::CreateEmbed;
 use serenity::model::application::interaction::application_command::CommandDataOption;
-use serenity::model::guild::PartialGuild;
-use serenity::{
-    builder::CreateApplicationCommand,
-    model::prelude::{
-        command::{CommandOptionType, CommandType},
-        interaction::application_command::ApplicationCommandInteraction,
-    },
-    prelude::Context,
-};
-use std::str::FromStr;
+use serenity::model::channel::{Channel, ChannelType};
+use serenity::model::guild::{Guild, PartialGuild};
+use serenity::model::id::{ChannelId, RoleId};
+use serenity::model::permissions::Permissions;
+use serenity::model::user::OnlineStatus;
+use serenity::model::Timestamp;
+use serenity::prelude::Context;
 use tracing::*;
 
-use crate::builders::roles::roles_to_field;
+use super::channel::channel_info;
+use crate::builders::activity::top_games_to_embed;
+use crate::builders::roles::{roles_to_field, roles_to_pages};
+use crate::builders::server::growth_to_embed;
+use crate::handler::command_details::{fetch_members, require_permissions, role_member_counts};
+use crate::handler::hierarchy::check_role_hierarchy;
+use crate::handler::invocation::Invocation;
+use crate::handler::member_growth::{today, MemberGrowth};
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::paginate::paginate;
+use crate::handler::presence::{presence_game, PresenceTracker};
+use crate::handler::response::CommandResponse;
+use crate::handler::server_log::ServerLog;
+use crate::handler::text_commands::TextPrefixes;
+use crate::handler::welcome::{render_template, TemplateValues, Welcome, WelcomeSettings};
 use crate::{
-    commands::{option_data::*, AppCmd},
-    util::LocalizedString,
+    commands::option_data::*,
+    util::{truncate, LocalizedString},
     Handler, HandlerError,
 };
 
@@ -27,7 +37,57 @@ pub const DESC: LocalizedString = LocalizedString {
 
 pub struct GuildServerCmd;
 
-enum GuildServerPropertyTypes {
+app_command! {
+    command: GuildServerCmd,
+    name: NAME,
+    desc: DESC,
+    handler: handle_server,
+    subcommands: enum GuildServerPropertyTypes {
+        Info => INFO, INFO_DESC [],
+        Avatar => AVATAR, AVATAR_DESC [],
+        Roles => ROLES, ROLES_DESC [],
+        Id => ID, ID_DESC [],
+        Created => CREATED, CREATED_DESC [],
+        Stats => SERVER_STATS, SERVER_STATS_DESC [],
+        NsfwLevel => NSFWLEVEL, NSFWLEVEL_DESC [],
+        Description => DESCRIPTION, DESCRIPTION_DESC [],
+        Owner => OWNER, OWNER_DESC [],
+        Name => USERNAME, USERNAME_DESC [],
+        Prefix => PREFIX, PREFIX_DESC [opt(String, PREFIX_VALUE, PREFIX_VALUE_DESC)],
+        Activity => ACTIVITY, ACTIVITY_DESC [],
+        Tracking => TRACKING, TRACKING_DESC [
+            req(Boolean, TRACKING_ENABLED, TRACKING_ENABLED_DESC),
+        ],
+    }
+    groups: {
+        CHANNEL, CHANNEL_DESC {
+            AfkChannel => AFKCHANNEL, AFKCHANNEL_DESC [],
+            WidgetChannel => WIDGETCHANNEL, WIDGETCHANNEL_DESC [],
+            SystemChannel => SYSTEMCHANNEL, SYSTEMCHANNEL_DESC [],
+            RulesChannel => RULESCHANNEL, RULESCHANNEL_DESC [],
+            CustomChannel => CUSTOM, CUSTOM_DESC [opt(Channel, CUSTOMCHANNEL, CUSTOMCHANNEL_DESC)],
+        },
+        SERVERLOG, SERVERLOG_DESC {
+            LogSet => SET, SET_DESC [req(Channel, CUSTOMCHANNEL, CUSTOMCHANNEL_DESC)],
+            LogDisable => DISABLE, DISABLE_DESC [],
+        },
+        WELCOME, WELCOME_DESC {
+            WelcomeSet => SET, WELCOME_SET_DESC [
+                req(Channel, CUSTOMCHANNEL, CUSTOMCHANNEL_DESC),
+                opt(Role, AUTO_ROLE, AUTO_ROLE_DESC),
+                opt(String, GOODBYE_MESSAGE, GOODBYE_MESSAGE_DESC),
+                opt(String, WELCOME_DM, WELCOME_DM_DESC),
+                opt(String, WELCOME_MESSAGE, WELCOME_MESSAGE_DESC),
+            ],
+            WelcomePreview => PREVIEW, PREVIEW_DESC [],
+            WelcomeDisable => DISABLE, DISABLE_DESC [],
+        },
+    }
+}
+
+/// What an embed shows of the server. `info` shows a few of them at once.
+#[derive(Clone, Copy, Debug)]
+enum ServerField {
     Roles,
     Avatar,
     Name,
@@ -35,58 +95,165 @@ enum GuildServerPropertyTypes {
     Created,
     Owner,
     Description,
-    NSFWLevel,
-    Channel,
+    NsfwLevel,
+    Members,
+    Channels,
+    Boosts,
+    Emojis,
+    Verification,
+    Features,
+    AfkChannel,
+    RulesChannel,
+    WidgetChannel,
+    SystemChannel,
+}
+
+/// The fields a subcommand shows, none for those not describing the server.
+fn server_fields(embed_type: GuildServerPropertyTypes) -> Vec<ServerField> {
+    match embed_type {
+        GuildServerPropertyTypes::Info => vec![
+            ServerField::Owner,
+            ServerField::Id,
+            ServerField::Created,
+            ServerField::Members,
+            ServerField::Channels,
+            ServerField::Boosts,
+            ServerField::Emojis,
+            ServerField::Verification,
+            ServerField::Features,
+            ServerField::Roles,
+        ],
+        GuildServerPropertyTypes::Avatar => vec![ServerField::Avatar],
+        GuildServerPropertyTypes::Roles => vec![ServerField::Roles],
+        GuildServerPropertyTypes::Id => vec![ServerField::Id],
+        GuildServerPropertyTypes::Created => vec![ServerField::Created],
+        GuildServerPropertyTypes::NsfwLevel => vec![ServerField::NsfwLevel],
+        GuildServerPropertyTypes::Description => vec![ServerField::Description],
+        GuildServerPropertyTypes::Owner => vec![ServerField::Owner],
+        GuildServerPropertyTypes::Name => vec![ServerField::Name],
+        GuildServerPropertyTypes::AfkChannel => vec![ServerField::AfkChannel],
+        GuildServerPropertyTypes::WidgetChannel => vec![ServerField::WidgetChannel],
+        GuildServerPropertyTypes::SystemChannel => vec![ServerField::SystemChannel],
+        GuildServerPropertyTypes::RulesChannel => vec![ServerField::RulesChannel],
+        _ => vec![],
+    }
+}
+
+command_options! {
+    struct ChannelArgs {
+        channel: ChannelId = CUSTOMCHANNEL,
+    }
+}
+
+command_options! {
+    struct CustomChannelArgs {
+        channel: Option<ChannelId> = CUSTOMCHANNEL,
+    }
+}
+
+command_options! {
+    struct PrefixArgs {
+        prefix: Option<String> = PREFIX_VALUE,
+    }
+}
+
+command_options! {
+    struct WelcomeArgs {
+        channel: ChannelId = CUSTOMCHANNEL,
+        auto_role: Option<RoleId> = AUTO_ROLE,
+        join_message: Option<String> = WELCOME_MESSAGE,
+        leave_message: Option<String> = GOODBYE_MESSAGE,
+        dm_message: Option<String> = WELCOME_DM,
+    }
+}
+
+command_options! {
+    struct TrackingArgs {
+        enabled: bool = TRACKING_ENABLED,
+    }
 }
 
-impl FromStr for GuildServerPropertyTypes {
-    type Err = ();
-
-    fn from_str(input: &str) -> Result<GuildServerPropertyTypes, Self::Err> {
-        match input {
-            "roles" => Ok(GuildServerPropertyTypes::Roles),
-            "avatar" => Ok(GuildServerPropertyTypes::Avatar),
-            "id" => Ok(GuildServerPropertyTypes::Id),
-            "created" => Ok(GuildServerPropertyTypes::Created),
-            "name" => Ok(GuildServerPropertyTypes::Name),
-            "owner" => Ok(GuildServerPropertyTypes::Owner),
-            "description" => Ok(GuildServerPropertyTypes::Description),
-            "nsfwlevel" => Ok(GuildServerPropertyTypes::NSFWLevel),
-            "channel" => Ok(GuildServerPropertyTypes::Channel),
-            _ => Err(()),
+/// Counts only the cache knows about. Falls back to the approximate counts
+/// Discord sends with the guild when it isn't cached. Bots are counted from
+/// the full member list, which the cache of a large guild doesn't hold.
+#[derive(Debug, Default)]
+struct ServerCounts {
+    members: u64,
+    bots: Option<usize>,
+    online: u64,
+    text: usize,
+    voice: usize,
+    categories: usize,
+    other: usize,
+}
+
+impl ServerCounts {
+    fn new(server: &PartialGuild, cached: Option<&Guild>, bots: Option<usize>) -> ServerCounts {
+        let Some(guild) = cached else {
+            return ServerCounts {
+                members: server.approximate_member_count.unwrap_or_default(),
+                bots,
+                online: server.approximate_presence_count.unwrap_or_default(),
+                ..Default::default()
+            };
+        };
+        let mut counts = ServerCounts {
+            members: guild.member_count,
+            bots,
+            online: guild
+                .presences
+                .values()
+                .filter(|p| !matches!(p.status, OnlineStatus::Offline | OnlineStatus::Invisible))
+                .count() as u64,
+            ..Default::default()
+        };
+        for channel in guild.channels.values() {
+            match channel {
+                Channel::Guild(channel) => match channel.kind {
+                    ChannelType::Text | ChannelType::News => counts.text += 1,
+                    ChannelType::Voice | ChannelType::Stage => counts.voice += 1,
+                    ChannelType::Category => counts.categories += 1,
+                    _ => counts.other += 1,
+                },
+                Channel::Category(_) => counts.categories += 1,
+                _ => {}
+            }
         }
+        counts
     }
 }
 
 fn create_field_from_embed_types<'b>(
-    embed_types: &Vec<GuildServerPropertyTypes>,
+    embed_types: &Vec<ServerField>,
     server: &PartialGuild,
+    counts: &ServerCounts,
     mut embed: &'b mut CreateEmbed,
-    command_data_option: &CommandDataOption,
 ) -> &'b CreateEmbed {
     for i in embed_types {
         match i {
-            GuildServerPropertyTypes::Roles => {
-                embed = roles_to_field(&server.roles.keys().cloned().collect(), None, embed);
+            ServerField::Roles => {
+                let roles: Vec<RoleId> = server.roles.keys().cloned().collect();
+                embed = roles_to_field(&roles, None, embed);
             }
-            GuildServerPropertyTypes::Avatar => {
+            ServerField::Avatar => {
                 if let Some(avatar_url) = &server.icon_url() {
                     embed.image(avatar_url);
                 }
             }
-            GuildServerPropertyTypes::Id => {
+            ServerField::Id => {
                 embed.field("Id", server.id, true);
             }
-            GuildServerPropertyTypes::Created => {
-                embed.field("Created", "", true);
+            ServerField::Created => {
+                let created = server.id.created_at().unix_timestamp();
+                embed.field("Created", format!("<t:{0}:f> (<t:{0}:R>)", created), true);
             }
-            GuildServerPropertyTypes::Name => {
+            ServerField::Name => {
                 embed.field("Name", &server.name, true);
             }
-            GuildServerPropertyTypes::Owner => {
+            ServerField::Owner => {
                 embed.field("Owner", format!("<@{}>", &server.owner_id), true);
             }
-            GuildServerPropertyTypes::Description => {
+            ServerField::Description => {
                 embed.field(
                     "Description",
                     server
@@ -96,51 +263,95 @@ fn create_field_from_embed_types<'b>(
                     true,
                 );
             }
-            GuildServerPropertyTypes::NSFWLevel => {
+            ServerField::NsfwLevel => {
                 embed.field("NSFW Level", format!("<{:#?}>", &server.nsfw_level), true);
             }
-            GuildServerPropertyTypes::Channel => {
-                if command_data_option.options.first().is_none() {
-                    return embed;
+            ServerField::Members => {
+                let mut members = format!("{} total\n{} online", counts.members, counts.online);
+                if let Some(bots) = counts.bots {
+                    members += &format!("\n{} bots", bots);
                 }
-
-                let option = command_data_option.options.first().unwrap();
-
-                match option.name.as_str() {
-                    "afk" => {
-                        let channel = if server.afk_channel_id.is_some() {
-                            format!("<#{}>", &server.afk_channel_id.unwrap())
-                        } else {
-                            String::from("No channel defined!")
-                        };
-                        embed.field("AFK Channel", channel, true);
-                    }
-                    "rules" => {
-                        let channel = if server.rules_channel_id.is_some() {
-                            format!("<#{}>", &server.rules_channel_id.unwrap())
-                        } else {
-                            String::from("No channel defined!")
-                        };
-                        embed.field("Rules Channel", channel, true);
-                    }
-                    "widget" => {
-                        let channel = if server.widget_channel_id.is_some() {
-                            format!("<#{}>", &server.widget_channel_id.unwrap())
-                        } else {
-                            String::from("No channel defined!")
-                        };
-                        embed.field("Widget Channel", channel, true);
-                    }
-                    "system" => {
-                        let channel = if server.system_channel_id.is_some() {
-                            format!("<#{}>", &server.system_channel_id.unwrap())
-                        } else {
-                            String::from("No channel defined!")
-                        };
-                        embed.field("System Channel", channel, true);
-                    }
-                    _ => {}
+                embed.field("Members", members, true);
+            }
+            ServerField::Channels => {
+                let mut channels = format!(
+                    "{} text\n{} voice\n{} categories",
+                    counts.text, counts.voice, counts.categories
+                );
+                if counts.other > 0 {
+                    channels += &format!("\n{} other", counts.other);
                 }
+                embed.field("Channels", channels, true);
+            }
+            ServerField::Boosts => {
+                embed.field(
+                    "Boosts",
+                    format!(
+                        "{:?}\n{} boosts",
+                        server.premium_tier, server.premium_subscription_count
+                    ),
+                    true,
+                );
+            }
+            ServerField::Emojis => {
+                embed.field(
+                    "Emojis",
+                    format!(
+                        "{} emojis\n{} stickers",
+                        server.emojis.len(),
+                        server.stickers.len()
+                    ),
+                    true,
+                );
+            }
+            ServerField::Verification => {
+                embed.field(
+                    "Verification",
+                    format!("{:?}", server.verification_level),
+                    true,
+                );
+            }
+            ServerField::Features => {
+                let features = if server.features.is_empty() {
+                    String::from("None")
+                } else {
+                    let mut features: Vec<String> = server
+                        .features
+                        .iter()
+                        .map(|f| f.to_lowercase().replace('_', " "))
+                        .collect();
+                    features.sort();
+                    truncate(&features.join(", "), FIELD_LIMIT)
+                };
+                embed.field("Features", features, false);
+            }
+            ServerField::AfkChannel => {
+                let channel = match server.afk_channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => String::from("No channel defined!"),
+                };
+                embed.field("AFK Channel", channel, true);
+            }
+            ServerField::RulesChannel => {
+                let channel = match server.rules_channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => String::from("No channel defined!"),
+                };
+                embed.field("Rules Channel", channel, true);
+            }
+            ServerField::WidgetChannel => {
+                let channel = match server.widget_channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => String::from("No channel defined!"),
+                };
+                embed.field("Widget Channel", channel, true);
+            }
+            ServerField::SystemChannel => {
+                let channel = match server.system_channel_id {
+                    Some(channel_id) => format!("<#{}>", channel_id),
+                    None => String::from("No channel defined!"),
+                };
+                embed.field("System Channel", channel, true);
             }
         };
     }
@@ -148,178 +359,476 @@ fn create_field_from_embed_types<'b>(
 }
 
 fn create_embed_single(
-    command_data_option: &CommandDataOption,
+    embed_type: GuildServerPropertyTypes,
     server: PartialGuild,
+    counts: &ServerCounts,
 ) -> CreateEmbed {
     let mut embed = CreateEmbed::default();
     embed
         .title(server.name.to_string())
         .description(server.description.as_ref().unwrap_or(&String::from("")));
 
-    let embed_type = &command_data_option.name;
-
-    if embed_type != "avatar" {
+    if embed_type != GuildServerPropertyTypes::Avatar {
         if let Some(avatar_url) = &server.icon_url() {
             embed.thumbnail(avatar_url);
         }
     }
-    match embed_type.as_str() {
-        "info" => {
-            let embed_types = vec![
-                GuildServerPropertyTypes::Owner,
-                GuildServerPropertyTypes::Id,
-                GuildServerPropertyTypes::Roles,
-            ];
-            embed = create_field_from_embed_types(
-                &embed_types,
-                &server,
-                &mut embed,
-                command_data_option,
-            )
-            .to_owned();
-        }
-        value => {
-            if let Ok(guild_user_embed_type) = GuildServerPropertyTypes::from_str(value) {
-                let embed_types = vec![guild_user_embed_type];
-                embed = create_field_from_embed_types(
-                    &embed_types,
-                    &server,
-                    &mut embed,
-                    command_data_option,
-                )
-                .to_owned();
-            }
-        }
-    }
+    create_field_from_embed_types(&server_fields(embed_type), &server, counts, &mut embed);
     embed
 }
 
 fn create_response_server(
-    embed_type: &CommandDataOption,
+    embed_type: GuildServerPropertyTypes,
     server: PartialGuild,
+    counts: &ServerCounts,
 ) -> Vec<CreateEmbed> {
-    vec![(create_embed_single(embed_type, server))]
+    vec![(create_embed_single(embed_type, server, counts))]
 }
 
-#[async_trait]
-impl AppCmd for GuildServerCmd {
-    fn to_application_command() -> CreateApplicationCommand
-    where
-        Self: Sized,
-    {
-        let mut cmd = CreateApplicationCommand::default();
-        cmd.name(NAME.en)
-            .kind(CommandType::ChatInput)
-            .description(DESC.en)
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(INFO.en)
-                    .description(INFO_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(AVATAR.en)
-                    .description(AVATAR_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(ROLES.en)
-                    .description(ROLES_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(ID.en)
-                    .description(ID_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(CREATED.en)
-                    .description(CREATED_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommandGroup)
-                    .name(CHANNEL.en)
-                    .description(CHANNEL_DESC.en)
-                    .create_sub_option(|opt| {
-                        opt.kind(CommandOptionType::SubCommand)
-                            .name(AFKCHANNEL.en)
-                            .description(AFKCHANNEL_DESC.en)
-                    })
-                    .create_sub_option(|opt| {
-                        opt.kind(CommandOptionType::SubCommand)
-                            .name(WIDGETCHANNEL.en)
-                            .description(WIDGETCHANNEL_DESC.en)
-                    })
-                    .create_sub_option(|opt| {
-                        opt.kind(CommandOptionType::SubCommand)
-                            .name(SYSTEMCHANNEL.en)
-                            .description(SYSTEMCHANNEL_DESC.en)
-                    })
-                    .create_sub_option(|opt| {
-                        opt.kind(CommandOptionType::SubCommand)
-                            .name(RULESCHANNEL.en)
-                            .description(RULESCHANNEL_DESC.en)
-                    })
-                    .create_sub_option(|opt| {
-                        opt.kind(CommandOptionType::SubCommand)
-                            .name(CUSTOM.en)
-                            .description(CUSTOM_DESC.en)
-                            .create_sub_option(|opt| {
-                                opt.kind(CommandOptionType::Channel)
-                                    .name(CUSTOMCHANNEL.en)
-                                    .description(CUSTOMCHANNEL_DESC.en)
-                            })
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(NSFWLEVEL.en)
-                    .description(NSFWLEVEL_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(DESCRIPTION.en)
-                    .description(DESCRIPTION_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(OWNER.en)
-                    .description(OWNER_DESC.en)
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(USERNAME.en)
-                    .description(USERNAME_DESC.en)
-            });
-        cmd
+async fn configure_server_log(
+    embed_type: GuildServerPropertyTypes,
+    options: &[CommandDataOption],
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CreateEmbed, HandlerError> {
+    require_permissions(cmd, Permissions::MANAGE_GUILD)?;
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+
+    let channel_id = match embed_type {
+        GuildServerPropertyTypes::LogSet => Some(ChannelArgs::from_options(options)?.channel),
+        _ => None,
+    };
+    context
+        .data
+        .write()
+        .await
+        .entry::<ServerLog>()
+        .or_insert_with(ServerLog::default)
+        .set_channel(guild_id, channel_id)?;
+
+    let mut embed = CreateEmbed::default();
+    embed.title("Server log").description(match channel_id {
+        Some(channel_id) => format!("Server events will be posted to <#{}>.", channel_id),
+        None => String::from("Server events will no longer be posted."),
+    });
+    Ok(embed)
+}
+
+const MAX_PREFIX_LEN: usize = 8;
+const FIELD_LIMIT: usize = 1024;
+
+async fn configure_prefix(
+    options: &[CommandDataOption],
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CreateEmbed, HandlerError> {
+    require_permissions(cmd, Permissions::MANAGE_GUILD)?;
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let prefix = PrefixArgs::from_options(options)?.prefix;
+    if let Some(prefix) = &prefix {
+        if prefix.chars().count() > MAX_PREFIX_LEN || prefix.contains(char::is_whitespace) {
+            return Err(HandlerError::InvalidArgument(format!(
+                "prefix must be at most {} characters without spaces",
+                MAX_PREFIX_LEN
+            )));
+        }
     }
+    context
+        .data
+        .write()
+        .await
+        .entry::<TextPrefixes>()
+        .or_insert_with(TextPrefixes::default)
+        .set(guild_id, prefix.clone())?;
 
-    #[instrument(skip(cmd, _handler, context))]
-    async fn handle(
-        cmd: &ApplicationCommandInteraction,
-        _handler: &Handler,
-        context: &Context,
-    ) -> Result<(), HandlerError>
-    where
-        Self: Sized,
-    {
-        let mut embeds = vec![];
-
-        if let Some(response_type) = cmd.data.options.first() {
-            if let Some(guild_id) = cmd.guild_id {
-                let guild = context.http.get_guild(u64::from(guild_id)).await.unwrap();
-                embeds = create_response_server(response_type, guild);
-            }
+    let mut embed = CreateEmbed::default();
+    embed.title("Text commands").description(match prefix {
+        Some(prefix) => format!("Commands can now be used as `{}command`.", prefix),
+        None => String::from("Text commands are disabled."),
+    });
+    Ok(embed)
+}
+
+async fn configure_welcome(
+    embed_type: GuildServerPropertyTypes,
+    options: &[CommandDataOption],
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CreateEmbed, HandlerError> {
+    require_permissions(cmd, Permissions::MANAGE_GUILD)?;
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+
+    let mut embed = CreateEmbed::default();
+    if embed_type == GuildServerPropertyTypes::WelcomeDisable {
+        context
+            .data
+            .write()
+            .await
+            .entry::<Welcome>()
+            .or_insert_with(Welcome::default)
+            .set(guild_id, None)?;
+        embed
+            .title("Welcome messages")
+            .description("Welcome and goodbye messages are disabled.");
+        return Ok(embed);
+    }
+
+    let settings = if embed_type == GuildServerPropertyTypes::WelcomeSet {
+        let args = WelcomeArgs::from_options(options)?;
+        // Every new member gets the auto-role, so only someone who could
+        // hand it out themselves may pick it.
+        if let Some(role) = args.auto_role {
+            require_permissions(cmd, Permissions::MANAGE_ROLES)?;
+            let invoker = cmd.member.as_ref().ok_or(HandlerError::NotGuild)?;
+            check_role_hierarchy(context, guild_id, Some(invoker), &[role]).await?;
         }
+        let mut data = context.data.write().await;
+        let welcome = data.entry::<Welcome>().or_insert_with(Welcome::default);
+        let mut settings = welcome.settings(guild_id).cloned().unwrap_or_default();
+        settings.channel = Some(args.channel);
+        if args.join_message.is_some() {
+            settings.join_message = args.join_message;
+        }
+        if args.leave_message.is_some() {
+            settings.leave_message = args.leave_message;
+        }
+        if args.dm_message.is_some() {
+            settings.dm_message = args.dm_message;
+        }
+        if args.auto_role.is_some() {
+            settings.auto_role = args.auto_role;
+        }
+        welcome.set(guild_id, Some(settings.clone()))?;
+        settings
+    } else {
+        context
+            .data
+            .read()
+            .await
+            .get::<Welcome>()
+            .and_then(|w| w.settings(guild_id).cloned())
+            .unwrap_or_else(WelcomeSettings::default)
+    };
 
-        cmd.create_interaction_response(context, |res| {
-            res.interaction_response_data(|d| d.add_embeds(embeds))
+    let values = TemplateValues::new(context, guild_id, &cmd.user);
+    embed
+        .title("Welcome messages")
+        .description(match settings.channel {
+            Some(channel_id) => format!("Posting to <#{}>", channel_id),
+            None => String::from("Not set up, showing the defaults"),
         })
+        .field(
+            "Join",
+            render_template(settings.join_message(), &values),
+            false,
+        )
+        .field(
+            "Goodbye",
+            render_template(settings.leave_message(), &values),
+            false,
+        );
+    if let Some(dm) = &settings.dm_message {
+        embed.field("Direct message", render_template(dm, &values), false);
+    }
+    if let Some(role) = settings.auto_role {
+        embed.field("Auto-role", format!("<@&{}>", role), true);
+    }
+    Ok(embed)
+}
+
+async fn configure_tracking(
+    options: &[CommandDataOption],
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CreateEmbed, HandlerError> {
+    require_permissions(cmd, Permissions::MANAGE_GUILD)?;
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let enabled = TrackingArgs::from_options(options)?.enabled;
+    let presences = context
+        .cache
+        .guild_field(guild_id, |g| g.presences.clone())
+        .unwrap_or_default();
+
+    let mut data = context.data.write().await;
+    let tracker = data
+        .entry::<PresenceTracker>()
+        .or_insert_with(PresenceTracker::default);
+    tracker.set_enabled(guild_id, enabled)?;
+    if enabled {
+        // Start from what the cache already knows instead of waiting for
+        // every member's next presence update.
+        let now = Timestamp::now().unix_timestamp();
+        for (user_id, presence) in presences {
+            tracker.record(
+                guild_id,
+                user_id,
+                now,
+                presence.status,
+                presence_game(&presence),
+            );
+        }
+    }
+
+    let mut embed = CreateEmbed::default();
+    embed.title("Activity tracking").description(if enabled {
+        "Presences and activities of members are now recorded."
+    } else {
+        "Activity tracking is off and recorded activity was deleted."
+    });
+    Ok(embed)
+}
+
+async fn respond_activity(
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let name = match context.cache.guild_field(guild_id, |g| g.name.clone()) {
+        Some(name) => name,
+        None => guild_id.to_partial_guild(context).await?.name,
+    };
+    let embed = {
+        let data = context.data.read().await;
+        let tracker = data
+            .get::<PresenceTracker>()
+            .filter(|t| t.enabled(guild_id))
+            .ok_or(HandlerError::TrackingDisabled)?;
+        top_games_to_embed(&name, &tracker.top_games(guild_id))
+    };
+    Ok(CommandResponse::embed(embed))
+}
+
+async fn respond_roles(
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let roles = guild_id.roles(context).await?;
+    let members = fetch_members(context, guild_id).await?;
+    let mut counts = role_member_counts(&members);
+    counts.insert(RoleId(guild_id.0), members.len());
+    let roles: Vec<_> = roles.values().collect();
+    paginate(cmd, context, roles_to_pages(&roles, &counts), false).await
+}
+
+async fn respond_stats(
+    cmd: &Invocation,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let name = match context.cache.guild_field(guild_id, |g| g.name.clone()) {
+        Some(name) => name,
+        None => guild_id.to_partial_guild(context).await?.name,
+    };
+    let embed = match context.data.read().await.get::<MemberGrowth>() {
+        Some(growth) => growth_to_embed(&name, growth.history(guild_id), today()),
+        None => growth_to_embed(&name, &[], today()),
+    };
+    Ok(CommandResponse::embed(embed))
+}
+
+#[instrument(skip(cmd, _handler, context))]
+async fn handle_server(
+    cmd: &Invocation,
+    _handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+    let configured = match embed_type {
+        GuildServerPropertyTypes::Roles => return respond_roles(cmd, context).await,
+        GuildServerPropertyTypes::Stats => return respond_stats(cmd, context).await,
+        GuildServerPropertyTypes::Activity => return respond_activity(cmd, context).await,
+        GuildServerPropertyTypes::CustomChannel => {
+            let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+            let channel_id = CustomChannelArgs::from_options(options)?
+                .channel
+                .unwrap_or(cmd.channel_id);
+            let invoker = cmd.member.as_ref().ok_or(HandlerError::NotGuild)?;
+            let embed = channel_info(context, guild_id, channel_id, invoker).await?;
+            return Ok(CommandResponse::embed(embed));
+        }
+        GuildServerPropertyTypes::LogSet | GuildServerPropertyTypes::LogDisable => {
+            Some(configure_server_log(embed_type, options, cmd, context).await?)
+        }
+        GuildServerPropertyTypes::WelcomeSet
+        | GuildServerPropertyTypes::WelcomePreview
+        | GuildServerPropertyTypes::WelcomeDisable => {
+            Some(configure_welcome(embed_type, options, cmd, context).await?)
+        }
+        GuildServerPropertyTypes::Prefix => Some(configure_prefix(options, cmd, context).await?),
+        GuildServerPropertyTypes::Tracking => {
+            Some(configure_tracking(options, cmd, context).await?)
+        }
+        _ => None,
+    };
+    if let Some(embed) = configured {
+        return Ok(CommandResponse::embed(embed).ephemeral(true));
+    }
+
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let guild = context
+        .http
+        .get_guild_with_counts(u64::from(guild_id))
         .await?;
-        Ok(())
+    let bots = if embed_type == GuildServerPropertyTypes::Info {
+        match fetch_members(context, guild_id).await {
+            Ok(members) => Some(members.iter().filter(|m| m.user.bot).count()),
+            Err(err) => {
+                warn!(?err, ?guild_id, "could not count the bots");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let counts = ServerCounts::new(&guild, context.cache.guild(guild_id).as_ref(), bots);
+    Ok(CommandResponse::embeds(create_response_server(
+        embed_type, guild, &counts,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serenity::model::id::GuildId;
+
+    use super::*;
+    use crate::commands::AppCmd;
+    use crate::testing::{embeds, field, fixtures, MockDiscord};
+
+    fn guild_path() -> String {
+        format!("/guilds/{}", fixtures::GUILD_ID)
     }
 
-    fn name() -> LocalizedString {
-        NAME
+    #[tokio::test]
+    async fn info_describes_the_guild() {
+        let discord = MockDiscord::start().await;
+        discord.route("GET", &guild_path(), fixtures::guild());
+        let cmd = fixtures::subcommand(NAME.en, INFO.en, json!([]));
+        let response = GuildServerCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+
+        let request = discord
+            .requests()
+            .into_iter()
+            .find(|r| r.path == guild_path())
+            .unwrap();
+        assert_eq!(request.query.as_deref(), Some("with_counts=true"));
+
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        let embed = &embeds[0];
+        assert_eq!(embed["title"], "Test Server");
+        assert_eq!(embed["description"], "A server for tests");
+        let owner = format!("<@{}>", fixtures::OWNER_ID);
+        assert_eq!(field(embed, "Owner"), Some(owner.as_str()));
+        assert_eq!(field(embed, "Members"), Some("3 total\n2 online"));
+    }
+
+    #[tokio::test]
+    async fn counts_bots_among_all_members() {
+        let discord = MockDiscord::start().await;
+        discord.route("GET", &guild_path(), fixtures::guild());
+        let mut members = fixtures::members();
+        members[2]["user"]["bot"] = json!(true);
+        discord.route("GET", &format!("{}/members", guild_path()), members);
+        let cmd = fixtures::subcommand(NAME.en, INFO.en, json!([]));
+        let response = GuildServerCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+
+        let embeds = embeds(&response);
+        assert_eq!(
+            field(&embeds[0], "Members"),
+            Some("3 total\n2 online\n1 bots")
+        );
+    }
+
+    #[tokio::test]
+    async fn single_property() {
+        let discord = MockDiscord::start().await;
+        discord.route("GET", &guild_path(), fixtures::guild());
+        let cmd = fixtures::subcommand(NAME.en, "name", json!([]));
+        let response = GuildServerCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(field(&embeds[0], "Name"), Some("Test Server"));
+        assert_eq!(field(&embeds[0], "Owner"), None);
+    }
+
+    async fn set_auto_role(permissions: &str) -> (Context, Result<CommandResponse, HandlerError>) {
+        const MODERATOR: u64 = 11;
+        const ADMIN: u64 = 12;
+        let discord = MockDiscord::start().await;
+        let mut guild = fixtures::guild();
+        guild["roles"] = json!([
+            fixtures::role(fixtures::GUILD_ID, "@everyone", 0),
+            fixtures::role(MODERATOR, "moderator", 1),
+            fixtures::role(ADMIN, "admin", 2),
+        ]);
+        discord.route("GET", &guild_path(), guild);
+        let mut bot = fixtures::member(fixtures::APPLICATION_ID, "bot", None);
+        bot["roles"] = json!([ADMIN.to_string()]);
+        discord.route(
+            "GET",
+            &format!("{}/members/{}", guild_path(), fixtures::APPLICATION_ID),
+            bot,
+        );
+        let context = discord.context();
+        context
+            .data
+            .write()
+            .await
+            .insert::<Welcome>(Welcome::temporary());
+
+        let mut invoker = fixtures::member(fixtures::INVOKER_ID, "invoker", None);
+        invoker["roles"] = json!([MODERATOR.to_string()]);
+        invoker["permissions"] = json!(permissions);
+        let set = json!([
+            { "name": CUSTOMCHANNEL.en, "type": 7, "value": fixtures::CHANNEL_ID.to_string() },
+            { "name": AUTO_ROLE.en, "type": 8, "value": ADMIN.to_string() },
+        ]);
+        let cmd = fixtures::command_by(
+            invoker,
+            NAME.en,
+            json!([{
+                "name": WELCOME.en,
+                "type": 2,
+                "options": [{ "name": SET.en, "type": 1, "options": set }],
+            }]),
+            json!({ "roles": { ADMIN.to_string(): fixtures::role(ADMIN, "admin", 2) } }),
+        );
+        let result = GuildServerCmd::handle(&cmd, &Handler::default(), &context).await;
+        (context, result)
+    }
+
+    #[tokio::test]
+    async fn auto_roles_rank_below_the_invoker() {
+        let guild_id = GuildId(fixtures::GUILD_ID);
+        // Managing the server isn't enough to hand out roles.
+        let (context, result) = set_auto_role("32").await;
+        assert!(matches!(result, Err(HandlerError::MissingPermissions)));
+        let (context_admin, result_admin) = set_auto_role("268435488").await;
+        assert!(matches!(
+            result_admin,
+            Err(HandlerError::InvokerRoleHierarchy)
+        ));
+        for context in [context, context_admin] {
+            let data = context.data.read().await;
+            assert!(data.get::<Welcome>().unwrap().settings(guild_id).is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_without_the_guild() {
+        let discord = MockDiscord::start().await;
+        let cmd = fixtures::subcommand(NAME.en, INFO.en, json!([]));
+        let result = GuildServerCmd::handle(&cmd, &Handler::default(), &discord.context()).await;
+
+        assert!(matches!(result, Err(HandlerError::Send(_))));
     }
 }
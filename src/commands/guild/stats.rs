@@ -0,0 +1,209 @@
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+use std::str::FromStr;
+use tracing::*;
+
+use crate::handler::analytics::{Analytics, StatsWindow, UsageRow};
+use crate::handler::command_details::require_permissions;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
+
+use ascii_table::AsciiTable;
+
+pub const NAME: LocalizedString = LocalizedString { en: "stats" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Commands accessing bot usage statistics!",
+};
+
+const MAX_ROWS: usize = 15;
+
+pub struct StatsCmd;
+
+command_options! {
+    struct CollectionArgs {
+        enabled: Option<bool> = ENABLED,
+    }
+}
+
+command_options! {
+    struct WindowArgs {
+        window: Option<String> = WINDOW,
+    }
+}
+
+app_command! {
+    command: StatsCmd,
+    name: NAME,
+    desc: DESC,
+    handler: handle_stats,
+    subcommands: enum StatsPropertyTypes {
+        Commands => COMMANDS, COMMANDS_DESC [
+            opt(String, WINDOW, WINDOW_DESC)
+                .add_string_choice("Last hour", "hour")
+                .add_string_choice("Last 24 hours", "day")
+                .add_string_choice("Last 7 days", "week")
+                .add_string_choice("All time", "all"),
+        ],
+        Users => USERS, USERS_DESC [
+            opt(String, WINDOW, WINDOW_DESC)
+                .add_string_choice("Last hour", "hour")
+                .add_string_choice("Last 24 hours", "day")
+                .add_string_choice("Last 7 days", "week")
+                .add_string_choice("All time", "all"),
+        ],
+        Errors => ERRORS, ERRORS_DESC [
+            opt(String, WINDOW, WINDOW_DESC)
+                .add_string_choice("Last hour", "hour")
+                .add_string_choice("Last 24 hours", "day")
+                .add_string_choice("Last 7 days", "week")
+                .add_string_choice("All time", "all"),
+        ],
+        Collection => COLLECTION, COLLECTION_DESC [req(Boolean, ENABLED, ENABLED_DESC)],
+    }
+}
+
+fn create_table(headers: &[&str], rows: Vec<Vec<String>>, width: usize) -> String {
+    if rows.is_empty() {
+        return String::from("No usage recorded.");
+    }
+
+    let mut ascii_table = AsciiTable::default();
+    ascii_table.set_max_width(width);
+    for (i, header) in headers.iter().enumerate() {
+        ascii_table.column(i).set_header(*header);
+    }
+
+    let text = ascii_table.format(&rows);
+    String::from("```\n") + &text + &*String::from("\n```")
+}
+
+fn create_embed_usage(
+    embed_type: &StatsPropertyTypes,
+    analytics: &Analytics,
+    guild_id: GuildId,
+    window: StatsWindow,
+    width: usize,
+) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    if !analytics.is_enabled(guild_id) {
+        embed
+            .title("Statistics")
+            .description("Usage collection is disabled for this server.");
+        return embed;
+    }
+
+    let take = |rows: Vec<UsageRow>| rows.into_iter().take(MAX_ROWS);
+    match embed_type {
+        StatsPropertyTypes::Commands => {
+            let rows = take(analytics.commands(guild_id, window))
+                .map(|row| {
+                    vec![
+                        row.key,
+                        row.count.to_string(),
+                        row.errors.to_string(),
+                        format!("{}ms", row.avg_latency.as_millis()),
+                    ]
+                })
+                .collect();
+            embed.title(format!("Command usage ({})", window.label()));
+            embed.description(create_table(
+                &["Command", "Uses", "Errors", "Avg. latency"],
+                rows,
+                width,
+            ));
+        }
+        StatsPropertyTypes::Users => {
+            let lines: Vec<String> = take(analytics.users(guild_id, window))
+                .enumerate()
+                .map(|(i, row)| format!("``{}.`` <@{}> ``{}``", i + 1, row.key, row.count))
+                .collect();
+            embed.title(format!("Most active users ({})", window.label()));
+            embed.description(if lines.is_empty() {
+                String::from("No usage recorded.")
+            } else {
+                lines.join("\n")
+            });
+        }
+        StatsPropertyTypes::Errors => {
+            let rows = take(analytics.errors(guild_id, window))
+                .map(|row| vec![row.key, row.count.to_string()])
+                .collect();
+            embed.title(format!("Errors ({})", window.label()));
+            embed.description(create_table(&["Error", "Count"], rows, width));
+        }
+        StatsPropertyTypes::Collection => {}
+    }
+    embed
+}
+
+async fn set_collection(
+    options: &[CommandDataOption],
+    cmd: &Invocation,
+    context: &Context,
+    guild_id: GuildId,
+) -> Result<CreateEmbed, HandlerError> {
+    require_permissions(cmd, Permissions::MANAGE_GUILD)?;
+
+    let enabled = CollectionArgs::from_options(options)?
+        .enabled
+        .unwrap_or(true);
+
+    context
+        .data
+        .write()
+        .await
+        .entry::<Analytics>()
+        .or_insert_with(Analytics::default)
+        .set_enabled(guild_id, enabled)?;
+
+    let mut embed = CreateEmbed::default();
+    embed.title("Statistics").description(if enabled {
+        "Usage collection is now enabled for this server."
+    } else {
+        "Usage collection is now disabled for this server and collected data was removed."
+    });
+    Ok(embed)
+}
+
+fn window_option(options: &[CommandDataOption]) -> Result<StatsWindow, HandlerError> {
+    Ok(WindowArgs::from_options(options)?
+        .window
+        .and_then(|v| StatsWindow::from_str(&v).ok())
+        .unwrap_or(StatsWindow::Day))
+}
+
+#[instrument(skip(cmd, handler, context))]
+async fn handle_stats(
+    cmd: &Invocation,
+    handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+
+    let embed = match embed_type {
+        StatsPropertyTypes::Collection => set_collection(options, cmd, context, guild_id).await?,
+        _ => {
+            let window = window_option(options)?;
+            let data = context.data.read().await;
+            let analytics = data
+                .get::<Analytics>()
+                .ok_or(HandlerError::TypeMapNotFound)?;
+            create_embed_usage(
+                &embed_type,
+                analytics,
+                guild_id,
+                window,
+                handler.config.limits.table_width,
+            )
+        }
+    };
+
+    Ok(CommandResponse::embed(embed))
+}
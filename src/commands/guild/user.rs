@@ -1,25 +1,22 @@
-use async_trait::async_trait;
 use serenity::builder::CreateEmbed;
 use serenity::model::guild::Member;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::model::Timestamp;
+use serenity::prelude::Context;
 use serenity::utils::Color;
-use serenity::{
-    builder::CreateApplicationCommand,
-    model::prelude::{
-        command::{CommandOptionType, CommandType},
-        interaction::application_command::ApplicationCommandInteraction,
-    },
-    prelude::Context,
-};
-use std::str::FromStr;
 use tracing::*;
 
+use crate::builders::activity::{format_duration, member_activity_to_embed};
+use crate::builders::cases::cases_to_field;
 use crate::builders::roles::{roles_to_field, roles_to_text};
-use crate::handler::command_details::parse_command_members;
-use crate::{
-    commands::{option_data::*, AppCmd},
-    util::LocalizedString,
-    Handler, HandlerError,
-};
+use crate::handler::cases::CaseLog;
+use crate::handler::command_details::{find_members, require_permissions};
+use crate::handler::invocation::Invocation;
+use crate::handler::options::{parse_subcommand, FromCommandOptions};
+use crate::handler::presence::PresenceTracker;
+use crate::handler::response::CommandResponse;
+use crate::{commands::option_data::*, util::LocalizedString, Handler, HandlerError};
 
 use ascii_table::AsciiTable;
 
@@ -30,32 +27,41 @@ pub const DESC: LocalizedString = LocalizedString {
 
 pub struct GuildUserCmd;
 
-enum GuildUserPropertyTypes {
-    Roles,
-    Avatar,
-    Nick,
-    Id,
-    Created,
-    Joined,
-    Discriminator,
-    Name,
+app_command! {
+    command: GuildUserCmd,
+    name: NAME,
+    desc: DESC,
+    handler: handle_user,
+    subcommands: enum GuildUserPropertyTypes {
+        Info => INFO, INFO_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Avatar => AVATAR, AVATAR_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Roles => ROLES, ROLES_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Nick => NICK, NICK_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Id => ID, ID_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Created => CREATED, CREATED_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Name => USERNAME, USERNAME_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Discriminator => DISCRIMINATOR, DISCRIMINATOR_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Joined => JOINED, JOINED_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        History => CASE_HISTORY, CASE_HISTORY_DESC [opt(String, MEMBER, MEMBER_DESC)],
+        Activity => ACTIVITY, ACTIVITY_DESC [opt(String, MEMBER, MEMBER_DESC)],
+    }
 }
 
-impl FromStr for GuildUserPropertyTypes {
-    type Err = ();
-
-    fn from_str(input: &str) -> Result<GuildUserPropertyTypes, Self::Err> {
-        match input {
-            "roles" => Ok(GuildUserPropertyTypes::Roles),
-            "avatar" => Ok(GuildUserPropertyTypes::Avatar),
-            "nick" => Ok(GuildUserPropertyTypes::Nick),
-            "id" => Ok(GuildUserPropertyTypes::Id),
-            "created" => Ok(GuildUserPropertyTypes::Created),
-            "joined" => Ok(GuildUserPropertyTypes::Joined),
-            "discriminator" => Ok(GuildUserPropertyTypes::Discriminator),
-            "name" => Ok(GuildUserPropertyTypes::Name),
-            _ => Err(()),
-        }
+command_options! {
+    struct MemberArgs {
+        search: Option<String> = MEMBER,
+    }
+}
+
+/// What a subcommand shows of each member, `info` standing for a summary.
+fn embed_types(embed_type: GuildUserPropertyTypes) -> Vec<GuildUserPropertyTypes> {
+    match embed_type {
+        GuildUserPropertyTypes::Info => vec![
+            GuildUserPropertyTypes::Nick,
+            GuildUserPropertyTypes::Id,
+            GuildUserPropertyTypes::Roles,
+        ],
+        embed_type => vec![embed_type],
     }
 }
 
@@ -90,7 +96,7 @@ fn create_field_from_embed_types<'b>(
                 embed.field("Created", member.user.created_at(), true);
             }
             GuildUserPropertyTypes::Joined => {
-                embed.field("Joined", &member.joined_at.unwrap().to_string(), true);
+                embed.field("Joined", member.joined_at.unwrap().to_string(), true);
             }
             GuildUserPropertyTypes::Discriminator => {
                 embed.field(
@@ -102,6 +108,9 @@ fn create_field_from_embed_types<'b>(
             GuildUserPropertyTypes::Name => {
                 embed.field("Name", &member.user.name, true);
             }
+            GuildUserPropertyTypes::Info
+            | GuildUserPropertyTypes::History
+            | GuildUserPropertyTypes::Activity => {}
         };
     }
     embed
@@ -110,9 +119,10 @@ fn create_field_from_embed_types<'b>(
 fn create_table_from_embed_types(
     embed_types: &Vec<GuildUserPropertyTypes>,
     members: &[Member],
+    width: usize,
 ) -> String {
     let mut ascii_table = AsciiTable::default();
-    ascii_table.set_max_width(120);
+    ascii_table.set_max_width(width);
 
     let mut data: Vec<Vec<String>> = vec![];
 
@@ -155,6 +165,9 @@ fn create_table_from_embed_types(
                 GuildUserPropertyTypes::Name => {
                     data[m].push(member.user.name.to_string());
                 }
+                GuildUserPropertyTypes::Info
+                | GuildUserPropertyTypes::History
+                | GuildUserPropertyTypes::Activity => {}
             };
         }
         m += 1
@@ -164,7 +177,7 @@ fn create_table_from_embed_types(
     String::from("```\n") + &text + &*String::from("\n```")
 }
 
-fn create_embed_single_member(embed_type: &String, member: &Member) -> CreateEmbed {
+fn create_embed_single_member(embed_type: GuildUserPropertyTypes, member: &Member) -> CreateEmbed {
     let mut embed = CreateEmbed::default();
     embed
         .title(format!(
@@ -172,57 +185,29 @@ fn create_embed_single_member(embed_type: &String, member: &Member) -> CreateEmb
             member.user.name, member.user.discriminator
         ))
         .description(format!("Created: {}", member.user.created_at()));
-    if embed_type != "avatar" {
+    if embed_type != GuildUserPropertyTypes::Avatar {
         if let Some(avatar_url) = member.user.avatar_url() {
             embed.thumbnail(avatar_url);
         } else {
             embed.thumbnail(member.user.default_avatar_url());
         }
     }
-    match embed_type.as_str() {
-        "info" => {
-            let embed_types = vec![
-                GuildUserPropertyTypes::Nick,
-                GuildUserPropertyTypes::Id,
-                GuildUserPropertyTypes::Nick,
-                GuildUserPropertyTypes::Roles,
-            ];
-            embed = create_field_from_embed_types(&embed_types, member, &mut embed).to_owned();
-        }
-        value => {
-            if let Ok(guild_user_embed_type) = GuildUserPropertyTypes::from_str(value) {
-                let embed_types = vec![guild_user_embed_type];
-                embed = create_field_from_embed_types(&embed_types, member, &mut embed).to_owned();
-            }
-        }
-    }
+    create_field_from_embed_types(&embed_types(embed_type), member, &mut embed);
     embed
 }
 
-fn create_content_multiple_members(embed_type: &str, members: &[Member]) -> String {
-    match embed_type {
-        "info" => {
-            let embed_types = vec![
-                GuildUserPropertyTypes::Nick,
-                GuildUserPropertyTypes::Id,
-                GuildUserPropertyTypes::Roles,
-            ];
-            create_table_from_embed_types(&embed_types, members).to_owned()
-        }
-        value => {
-            if let Ok(guild_user_embed_type) = GuildUserPropertyTypes::from_str(value) {
-                let embed_types = vec![guild_user_embed_type];
-                create_table_from_embed_types(&embed_types, members).to_owned()
-            } else {
-                String::from("")
-            }
-        }
-    }
+fn create_content_multiple_members(
+    embed_type: GuildUserPropertyTypes,
+    members: &[Member],
+    width: usize,
+) -> String {
+    create_table_from_embed_types(&embed_types(embed_type), members, width)
 }
 
 fn create_response_members(
-    embed_type: &String,
-    members: &Vec<Member>,
+    embed_type: GuildUserPropertyTypes,
+    members: &[Member],
+    width: usize,
 ) -> (String, Vec<CreateEmbed>) {
     let mut embeds = vec![];
     let mut content = String::from("");
@@ -243,158 +228,198 @@ fn create_response_members(
             ));
         }
         _ => {
-            content = create_content_multiple_members(embed_type, members);
+            content = create_content_multiple_members(embed_type, members, width);
         }
     }
     (content, embeds)
 }
 
-#[async_trait]
-impl AppCmd for GuildUserCmd {
-    fn to_application_command() -> CreateApplicationCommand
-    where
-        Self: Sized,
-    {
-        let mut cmd = CreateApplicationCommand::default();
-        cmd.name(NAME.en)
-            .kind(CommandType::ChatInput)
-            .description(DESC.en)
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(INFO.en)
-                    .description(INFO_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(AVATAR.en)
-                    .description(AVATAR_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(ROLES.en)
-                    .description(ROLES_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(NICK.en)
-                    .description(NICK_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(ID.en)
-                    .description(ID_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(CREATED.en)
-                    .description(CREATED_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(USERNAME.en)
-                    .description(USERNAME_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(DISCRIMINATOR.en)
-                    .description(DISCRIMINATOR_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            })
-            .create_option(|opt| {
-                opt.kind(CommandOptionType::SubCommand)
-                    .name(JOINED.en)
-                    .description(JOINED_DESC.en)
-                    .create_sub_option(|sub| {
-                        sub.kind(CommandOptionType::String)
-                            .name(MEMBER.en)
-                            .description(MEMBER_DESC.en)
-                    })
-            });
-        cmd
+const MAX_HISTORY_CASES: usize = 5;
+
+fn create_response_history(
+    members: &[Member],
+    log: &CaseLog,
+    guild_id: GuildId,
+    width: usize,
+) -> (String, Vec<CreateEmbed>) {
+    match members {
+        [member] => {
+            let mut embed = CreateEmbed::default();
+            embed.title(format!(
+                "{}#{:04}",
+                member.user.name, member.user.discriminator
+            ));
+            embed.thumbnail(member.user.face());
+            cases_to_field(
+                &log.cases_for(guild_id, member.user.id),
+                MAX_HISTORY_CASES,
+                &mut embed,
+            );
+            (String::from(""), vec![embed])
+        }
+        _ => {
+            let data: Vec<Vec<String>> = members
+                .iter()
+                .map(|member| {
+                    vec![
+                        format!("{}#{:04}", member.user.name, member.user.discriminator),
+                        log.cases_for(guild_id, member.user.id).len().to_string(),
+                    ]
+                })
+                .collect();
+            let mut ascii_table = AsciiTable::default();
+            ascii_table.set_max_width(width);
+            let text = ascii_table.format(&data);
+            (String::from("```\n") + &text + "\n```", vec![])
+        }
     }
+}
 
-    #[instrument(skip(cmd, _handler, context))]
-    async fn handle(
-        cmd: &ApplicationCommandInteraction,
-        _handler: &Handler,
-        context: &Context,
-    ) -> Result<(), HandlerError>
-    where
-        Self: Sized,
-    {
-        let user_id_options = cmd.data.resolved.users.keys();
-        let mut selected_users = Vec::new();
-        if user_id_options.len() == 0 {
-            selected_users.push(cmd.member.clone().unwrap())
+fn create_response_activity(
+    members: &[Member],
+    tracker: &PresenceTracker,
+    guild_id: GuildId,
+    width: usize,
+) -> (String, Vec<CreateEmbed>) {
+    let now = Timestamp::now().unix_timestamp();
+    match members {
+        [member] => {
+            let embed = member_activity_to_embed(
+                member,
+                tracker.live(guild_id, member.user.id),
+                tracker.member(guild_id, member.user.id),
+                now,
+            );
+            (String::from(""), vec![embed])
         }
+        _ => {
+            let data: Vec<Vec<String>> = members
+                .iter()
+                .map(|member| {
+                    let live = tracker.live(guild_id, member.user.id);
+                    vec![
+                        format!("{}#{:04}", member.user.name, member.user.discriminator),
+                        live.map(|l| format!("{:?}", l.status))
+                            .unwrap_or_else(|| String::from("Unknown")),
+                        live.and_then(|l| l.game.as_ref())
+                            .map(|(game, started)| {
+                                format!(
+                                    "{} ({})",
+                                    game,
+                                    format_duration((now - started).max(0) as u64)
+                                )
+                            })
+                            .unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            let mut ascii_table = AsciiTable::default();
+            ascii_table.set_max_width(width);
+            let text = ascii_table.format(&data);
+            (String::from("```\n") + &text + "\n```", vec![])
+        }
+    }
+}
 
-        let mut embeds = vec![];
-        let mut content = String::from("");
+#[instrument(skip(cmd, handler, context))]
+async fn handle_user(
+    cmd: &Invocation,
+    handler: &Handler,
+    context: &Context,
+) -> Result<CommandResponse, HandlerError> {
+    let (embed_type, options) = parse_subcommand(&cmd.data.options)?;
+    let selected_users = match MemberArgs::from_options(options)?.search {
+        Some(search) => find_members(context, cmd, &search).await?,
+        None => vec![cmd.member.clone().ok_or(HandlerError::NotGuild)?],
+    };
+    let width = handler.config.limits.table_width;
 
-        if let Some(response_type) = cmd.data.options.first() {
-            for j in &response_type.options {
-                if j.name == "members" {
-                    selected_users = parse_command_members(j, context, cmd).await
-                }
-            }
-            let response = create_response_members(&response_type.name, &selected_users);
-            embeds = response.1;
-            content = response.0;
+    let (content, embeds) = match embed_type {
+        GuildUserPropertyTypes::History => {
+            require_permissions(cmd, Permissions::MODERATE_MEMBERS)?;
+            let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+            let data = context.data.read().await;
+            let log = data.get::<CaseLog>().ok_or(HandlerError::TypeMapNotFound)?;
+            create_response_history(&selected_users, log, guild_id, width)
         }
+        GuildUserPropertyTypes::Activity => {
+            let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+            let data = context.data.read().await;
+            let tracker = data
+                .get::<PresenceTracker>()
+                .filter(|t| t.enabled(guild_id))
+                .ok_or(HandlerError::TrackingDisabled)?;
+            create_response_activity(&selected_users, tracker, guild_id, width)
+        }
+        embed_type => create_response_members(embed_type, &selected_users, width),
+    };
 
-        cmd.create_interaction_response(context, |res| {
-            res.interaction_response_data(|d| {
-                if !embeds.is_empty() {
-                    d.add_embeds(embeds)
-                } else {
-                    d.content(content)
-                }
-            })
-        })
-        .await?;
-        Ok(())
+    Ok(CommandResponse::embeds_or_content(embeds, content))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::commands::AppCmd;
+    use crate::handler::response::CommandResponse;
+    use crate::testing::{embeds, field, fixtures, MockDiscord};
+
+    fn members_option(search: &str) -> Value {
+        json!([{ "name": MEMBER.en, "type": 3, "value": search }])
+    }
+
+    /// `/user nick` against a guild with the fixture members.
+    async fn nick(options: Value) -> (MockDiscord, CommandResponse) {
+        let discord = MockDiscord::start().await;
+        let members_path = format!("/guilds/{}/members", fixtures::GUILD_ID);
+        discord.route("GET", &members_path, fixtures::members());
+        let cmd = fixtures::subcommand(NAME.en, NICK.en, options);
+        let response = GuildUserCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+        (discord, response)
+    }
+
+    #[tokio::test]
+    async fn defaults_to_the_invoker() {
+        let (discord, response) = nick(json!([])).await;
+
+        assert!(discord.requests().is_empty());
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(field(&embeds[0], "Nickname"), Some("No Nickname"));
     }
 
-    fn name() -> LocalizedString {
-        NAME
+    #[tokio::test]
+    async fn searches_members_by_name() {
+        let (discord, response) = nick(members_option("owner")).await;
+
+        let members_path = format!("/guilds/{}/members", fixtures::GUILD_ID);
+        assert!(discord.requests().iter().any(|r| r.path == members_path));
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(field(&embeds[0], "Nickname"), Some("The Owner"));
+    }
+
+    #[tokio::test]
+    async fn lists_several_members_in_a_table() {
+        let (_, response) = nick(members_option("o")).await;
+
+        let content = response.content.unwrap();
+        assert!(content.starts_with("```"));
+        assert!(content.contains("The Owner") && content.contains("Someone"));
+        assert!(response.embeds.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_when_no_member_matches() {
+        let (_, response) = nick(members_option("nobody")).await;
+
+        let embeds = embeds(&response);
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(embeds[0]["title"], "Error");
+        assert_eq!(embeds[0]["description"], "No user found!");
     }
 }
@@ -0,0 +1,176 @@
+use async_trait::async_trait;
+use serenity::model::id::UserId;
+use serenity::model::permissions::Permissions;
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::command::{CommandOptionType, CommandType},
+    prelude::Context,
+};
+use tracing::*;
+
+use crate::builders::cases::case_to_embed;
+use crate::handler::cases::{record_case, CaseAction};
+use crate::handler::command_details::require_permissions;
+use crate::handler::hierarchy::check_invoker_hierarchy;
+use crate::handler::invocation::Invocation;
+use crate::handler::options::FromCommandOptions;
+use crate::handler::response::CommandResponse;
+use crate::{
+    commands::{option_data::*, AppCmd},
+    util::LocalizedString,
+    Handler, HandlerError,
+};
+
+pub const NAME: LocalizedString = LocalizedString { en: "warn" };
+pub const DESC: LocalizedString = LocalizedString {
+    en: "Warn a member!",
+};
+
+pub struct WarnCmd;
+
+command_options! {
+    struct WarnArgs {
+        user: UserId = TARGET,
+        reason: String = REASON,
+    }
+}
+
+#[async_trait]
+impl AppCmd for WarnCmd {
+    fn to_application_command() -> CreateApplicationCommand
+    where
+        Self: Sized,
+    {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name(NAME.en)
+            .kind(CommandType::ChatInput)
+            .description(DESC.en)
+            .dm_permission(false)
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::User)
+                    .name(TARGET.en)
+                    .description(TARGET_DESC.en)
+                    .required(true)
+            })
+            .create_option(|opt| {
+                opt.kind(CommandOptionType::String)
+                    .name(REASON.en)
+                    .description(REASON_DESC.en)
+                    .required(true)
+            });
+        cmd
+    }
+
+    #[instrument(skip(cmd, _handler, context))]
+    async fn handle(
+        cmd: &Invocation,
+        _handler: &Handler,
+        context: &Context,
+    ) -> Result<CommandResponse, HandlerError>
+    where
+        Self: Sized,
+    {
+        let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+        require_permissions(cmd, Permissions::MODERATE_MEMBERS)?;
+
+        let args = WarnArgs::from_options(&cmd.data.options)?;
+        let invoker = cmd.member.as_ref().ok_or(HandlerError::NotGuild)?;
+        check_invoker_hierarchy(context, guild_id, invoker, args.user).await?;
+
+        let case = record_case(
+            context,
+            guild_id,
+            CaseAction::Warn,
+            args.user,
+            cmd.user.id,
+            &args.reason,
+        )
+        .await?;
+
+        Ok(CommandResponse::embed(case_to_embed(&case)))
+    }
+
+    fn name() -> LocalizedString {
+        NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::handler::cases::CaseLog;
+    use crate::testing::{embeds, fixtures, MockDiscord};
+
+    const MODERATOR: u64 = 11;
+    const ADMIN: u64 = 12;
+
+    async fn warn(target_roles: &[u64]) -> (MockDiscord, Result<CommandResponse, HandlerError>) {
+        let discord = MockDiscord::start().await;
+        let mut guild = fixtures::guild();
+        guild["roles"] = json!([
+            fixtures::role(fixtures::GUILD_ID, "@everyone", 0),
+            fixtures::role(MODERATOR, "moderator", 1),
+            fixtures::role(ADMIN, "admin", 2),
+        ]);
+        discord.route("GET", &format!("/guilds/{}", fixtures::GUILD_ID), guild);
+        let mut target = fixtures::member(fixtures::OTHER_ID, "other", None);
+        target["roles"] = Value::from(
+            target_roles
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>(),
+        );
+        discord.route(
+            "GET",
+            &format!(
+                "/guilds/{}/members/{}",
+                fixtures::GUILD_ID,
+                fixtures::OTHER_ID
+            ),
+            target,
+        );
+        let context = discord.context();
+        context
+            .data
+            .write()
+            .await
+            .insert::<CaseLog>(CaseLog::temporary());
+
+        let mut invoker = fixtures::member(fixtures::INVOKER_ID, "invoker", None);
+        invoker["roles"] = json!([ADMIN.to_string()]);
+        invoker["permissions"] = json!("8");
+        let cmd = fixtures::command_by(
+            invoker,
+            NAME.en,
+            json!([
+                { "name": TARGET.en, "type": 6, "value": fixtures::OTHER_ID.to_string() },
+                { "name": REASON.en, "type": 3, "value": "spam" },
+            ]),
+            json!({}),
+        );
+        let result = WarnCmd::handle(&cmd, &Handler::default(), &context).await;
+        (discord, result)
+    }
+
+    #[tokio::test]
+    async fn warns_members_that_outrank_the_bot() {
+        // The bot has no roles, so it ranks below the moderator.
+        let (discord, result) = warn(&[MODERATOR]).await;
+        let embeds = embeds(&result.unwrap());
+        assert_eq!(embeds[0]["title"], "Case #1 | Warn");
+        let bot = format!(
+            "/guilds/{}/members/{}",
+            fixtures::GUILD_ID,
+            fixtures::APPLICATION_ID
+        );
+        assert!(discord.requests().iter().all(|r| r.path != bot));
+    }
+
+    #[tokio::test]
+    async fn only_warns_lower_members() {
+        let (_, result) = warn(&[ADMIN]).await;
+        assert!(matches!(result, Err(HandlerError::InvokerHierarchy)));
+    }
+}
@@ -0,0 +1,399 @@
+/// Defines a command made of subcommands from a single table.
+///
+/// Generates the subcommand enum with its `Subcommand` impl, and an `AppCmd`
+/// impl whose `to_application_command` builds the option tree, whose `name`
+/// returns the command name and whose `handle` calls the given function.
+/// Each subcommand lists its options as `req(Kind, NAME, DESC)` or
+/// `opt(Kind, NAME, DESC)`, `Kind` being a `CommandOptionType` variant,
+/// optionally followed by option builder calls like `.min_int_value(0)`.
+/// Subcommands under `groups` are registered in a subcommand group each and
+/// share the enum with the top level ones. `slow: true` defers the command
+/// before it's handled.
+///
+/// ```text
+/// app_command! {
+///     command: ServerCmd,
+///     name: NAME,
+///     desc: DESC,
+///     dm_permission: false,
+///     handler: handle_server,
+///     subcommands: enum ServerSubcommand {
+///         Info => INFO, INFO_DESC [],
+///         Prefix => PREFIX, PREFIX_DESC [opt(String, PREFIX_VALUE, PREFIX_VALUE_DESC)],
+///     }
+///     groups: {
+///         SERVERLOG, SERVERLOG_DESC {
+///             LogSet => SET, SET_DESC [req(Channel, CUSTOMCHANNEL, CUSTOMCHANNEL_DESC)],
+///             LogDisable => DISABLE, DISABLE_DESC [],
+///         },
+///     }
+/// }
+/// ```
+macro_rules! app_command {
+    (
+        command: $cmd:ident,
+        name: $name:ident,
+        desc: $desc:ident,
+        $(dm_permission: $dm:expr,)?
+        $(slow: $slow:expr,)?
+        handler: $handler:path,
+        subcommands: enum $types:ident {
+            $(
+                $variant:ident => $sub:ident, $sub_desc:ident [$(
+                    $mode:ident($kind:ident, $opt:ident, $opt_desc:ident)
+                        $(.$extra:ident($($arg:expr),*))*
+                ),* $(,)?]
+            ),* $(,)?
+        }
+        $(groups: {
+            $(
+                $group:ident, $group_desc:ident {
+                    $(
+                        $g_variant:ident => $g_sub:ident, $g_sub_desc:ident [$(
+                            $g_mode:ident($g_kind:ident, $g_opt:ident, $g_opt_desc:ident)
+                                $(.$g_extra:ident($($g_arg:expr),*))*
+                        ),* $(,)?]
+                    ),* $(,)?
+                }
+            ),* $(,)?
+        })?
+    ) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        enum $types {
+            $($variant,)*
+            $($($($g_variant,)*)*)?
+        }
+
+        impl $crate::handler::options::Subcommand for $types {
+            fn from_path(path: &[&str]) -> Option<$types> {
+                $(
+                    if let [name] = path {
+                        if $sub.any_eq(name) {
+                            return Some($types::$variant);
+                        }
+                    }
+                )*
+                $($($(
+                    if let [group, name] = path {
+                        if $group.any_eq(group) && $g_sub.any_eq(name) {
+                            return Some($types::$g_variant);
+                        }
+                    }
+                )*)*)?
+                None
+            }
+
+            fn name(&self) -> $crate::util::LocalizedString {
+                match self {
+                    $($types::$variant => $sub,)*
+                    $($($($types::$g_variant => $g_sub,)*)*)?
+                }
+            }
+        }
+
+        #[::async_trait::async_trait]
+        impl $crate::commands::AppCmd for $cmd {
+            $(const SLOW: bool = $slow;)?
+
+            fn to_application_command() -> ::serenity::builder::CreateApplicationCommand
+            where
+                Self: Sized,
+            {
+                use ::serenity::model::application::command::{CommandOptionType, CommandType};
+
+                let mut cmd = ::serenity::builder::CreateApplicationCommand::default();
+                cmd.name($name.en)
+                    .kind(CommandType::ChatInput)
+                    .description($desc.en);
+                $(cmd.dm_permission($dm);)?
+                $(
+                    cmd.create_option(|opt| {
+                        opt.kind(CommandOptionType::SubCommand)
+                            .name($sub.en)
+                            .description($sub_desc.en)
+                            $(
+                                .create_sub_option(|sub| {
+                                    sub.kind(CommandOptionType::$kind)
+                                        .name($opt.en)
+                                        .description($opt_desc.en)
+                                        .required(app_command!(@required $mode))
+                                        $(.$extra($($arg),*))*
+                                })
+                            )*
+                    });
+                )*
+                $($(
+                    cmd.create_option(|group| {
+                        group
+                            .kind(CommandOptionType::SubCommandGroup)
+                            .name($group.en)
+                            .description($group_desc.en)
+                            $(
+                                .create_sub_option(|opt| {
+                                    opt.kind(CommandOptionType::SubCommand)
+                                        .name($g_sub.en)
+                                        .description($g_sub_desc.en)
+                                        $(
+                                            .create_sub_option(|sub| {
+                                                sub.kind(CommandOptionType::$g_kind)
+                                                    .name($g_opt.en)
+                                                    .description($g_opt_desc.en)
+                                                    .required(app_command!(@required $g_mode))
+                                                    $(.$g_extra($($g_arg),*))*
+                                            })
+                                        )*
+                                })
+                            )*
+                    });
+                )*)?
+                cmd
+            }
+
+            async fn handle(
+                cmd: &$crate::handler::invocation::Invocation,
+                handler: &$crate::Handler,
+                context: &::serenity::prelude::Context,
+            ) -> Result<$crate::handler::response::CommandResponse, $crate::HandlerError>
+            where
+                Self: Sized,
+            {
+                $handler(cmd, handler, context).await
+            }
+
+            fn name() -> $crate::util::LocalizedString {
+                $name
+            }
+        }
+    };
+    (@required req) => {
+        true
+    };
+    (@required opt) => {
+        false
+    };
+}
+
+/// Defines a struct holding a (sub)command's arguments and implements
+/// `FromCommandOptions` for it. Each field names the option it's read from;
+/// `Option<T>` fields may be left out, other ones are required.
+///
+/// ```text
+/// command_options! {
+///     struct TimeoutArgs {
+///         user: User = TARGET,
+///         minutes: i64 = MINUTES,
+///         reason: Option<String> = REASON,
+///     }
+/// }
+/// ```
+macro_rules! command_options {
+    (
+        $vis:vis struct $args:ident {
+            $($field:ident: $ty:ty = $opt:ident),* $(,)?
+        }
+    ) => {
+        #[derive(Clone, Debug)]
+        $vis struct $args {
+            $($field: $ty),*
+        }
+
+        impl $crate::handler::options::FromCommandOptions for $args {
+            fn from_options(
+                options: &[::serenity::model::application::interaction::application_command::CommandDataOption],
+            ) -> Result<Self, $crate::HandlerError> {
+                use $crate::handler::options::FromOption;
+
+                Ok($args {
+                    $(
+                        $field: <$ty>::from_option(
+                            $crate::handler::command_details::find_option(options, &$opt),
+                            $opt.en,
+                        )?,
+                    )*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use serenity::model::application::interaction::application_command::CommandDataOption;
+    use serenity::prelude::Context;
+
+    use crate::commands::AppCmd;
+    use crate::handler::invocation::Invocation;
+    use crate::handler::options::{parse_subcommand, FromCommandOptions, Subcommand};
+    use crate::handler::response::CommandResponse;
+    use crate::testing::{fixtures, MockDiscord};
+    use crate::util::LocalizedString;
+    use crate::{Handler, HandlerError};
+
+    const NAME: LocalizedString = LocalizedString { en: "test" };
+    const DESC: LocalizedString = LocalizedString { en: "Test command" };
+    const SHOW: LocalizedString = LocalizedString { en: "show" };
+    const SHOW_DESC: LocalizedString = LocalizedString { en: "Shows it" };
+    const CONFIG: LocalizedString = LocalizedString { en: "config" };
+    const CONFIG_DESC: LocalizedString = LocalizedString {
+        en: "Configures it",
+    };
+    const SET: LocalizedString = LocalizedString { en: "set" };
+    const SET_DESC: LocalizedString = LocalizedString { en: "Sets it" };
+    const RESET: LocalizedString = LocalizedString { en: "reset" };
+    const RESET_DESC: LocalizedString = LocalizedString { en: "Resets it" };
+    const VALUE: LocalizedString = LocalizedString { en: "value" };
+    const VALUE_DESC: LocalizedString = LocalizedString { en: "The value" };
+    const KIND: LocalizedString = LocalizedString { en: "kind" };
+    const KIND_DESC: LocalizedString = LocalizedString { en: "The kind" };
+
+    struct TestCmd;
+
+    app_command! {
+        command: TestCmd,
+        name: NAME,
+        desc: DESC,
+        dm_permission: false,
+        slow: true,
+        handler: handle_test,
+        subcommands: enum TestSubcommand {
+            Show => SHOW, SHOW_DESC [],
+        }
+        groups: {
+            CONFIG, CONFIG_DESC {
+                Set => SET, SET_DESC [
+                    req(Integer, VALUE, VALUE_DESC).min_int_value(1).max_int_value(10),
+                    opt(String, KIND, KIND_DESC).add_string_choice("Big", "big"),
+                ],
+                Reset => RESET, RESET_DESC [],
+            },
+        }
+    }
+
+    command_options! {
+        struct SetArgs {
+            value: i64 = VALUE,
+            kind: Option<String> = KIND,
+        }
+    }
+
+    async fn handle_test(
+        cmd: &Invocation,
+        _handler: &Handler,
+        _context: &Context,
+    ) -> Result<CommandResponse, HandlerError> {
+        let (sub, options) = parse_subcommand::<TestSubcommand>(&cmd.data.options)?;
+        Ok(match sub {
+            TestSubcommand::Set => {
+                let args = SetArgs::from_options(options)?;
+                CommandResponse::content(format!("{} {:?}", args.value, args.kind))
+            }
+            sub => CommandResponse::content(sub.name().en),
+        })
+    }
+
+    fn options(options: Value) -> Vec<CommandDataOption> {
+        serde_json::from_value(options).unwrap()
+    }
+
+    #[test]
+    fn builds_the_option_tree() {
+        let cmd = serde_json::to_value(&TestCmd::to_application_command().0).unwrap();
+        assert_eq!(
+            cmd,
+            json!({
+                "name": "test",
+                "description": "Test command",
+                "type": 1,
+                "dm_permission": false,
+                "options": [
+                    { "type": 1, "name": "show", "description": "Shows it" },
+                    {
+                        "type": 2,
+                        "name": "config",
+                        "description": "Configures it",
+                        "options": [
+                            {
+                                "type": 1,
+                                "name": "set",
+                                "description": "Sets it",
+                                "options": [
+                                    {
+                                        "type": 4,
+                                        "name": "value",
+                                        "description": "The value",
+                                        "required": true,
+                                        "min_value": 1,
+                                        "max_value": 10,
+                                    },
+                                    {
+                                        "type": 3,
+                                        "name": "kind",
+                                        "description": "The kind",
+                                        "required": false,
+                                        "choices": [{ "name": "Big", "value": "big" }],
+                                    },
+                                ],
+                            },
+                            { "type": 1, "name": "reset", "description": "Resets it" },
+                        ],
+                    },
+                ],
+            })
+        );
+        const { assert!(TestCmd::SLOW) };
+    }
+
+    #[test]
+    fn parses_subcommands_and_groups() {
+        let show = options(json!([{ "name": "show", "type": 1, "options": [] }]));
+        let (sub, _) = parse_subcommand::<TestSubcommand>(&show).unwrap();
+        assert_eq!(sub, TestSubcommand::Show);
+
+        let reset = options(json!([{
+            "name": "config",
+            "type": 2,
+            "options": [{ "name": "reset", "type": 1, "options": [] }],
+        }]));
+        let (sub, _) = parse_subcommand::<TestSubcommand>(&reset).unwrap();
+        assert_eq!(sub, TestSubcommand::Reset);
+        assert_eq!(sub.name().en, "reset");
+
+        // Group subcommands aren't reachable from the top level and the
+        // other way round.
+        assert_eq!(TestSubcommand::from_path(&["reset"]), None);
+        assert_eq!(TestSubcommand::from_path(&["config", "show"]), None);
+        assert!(matches!(
+            parse_subcommand::<TestSubcommand>(&options(json!([
+                { "name": "hide", "type": 1, "options": [] },
+            ]))),
+            Err(HandlerError::UnrecognizedCommand(path)) if path == "hide"
+        ));
+    }
+
+    #[tokio::test]
+    async fn hands_the_subcommand_options_to_the_handler() {
+        let discord = MockDiscord::start().await;
+        let cmd = fixtures::command(
+            NAME.en,
+            json!([{
+                "name": "config",
+                "type": 2,
+                "options": [{
+                    "name": "set",
+                    "type": 1,
+                    "options": [
+                        { "name": "value", "type": 4, "value": 3 },
+                        { "name": "kind", "type": 3, "value": "big" },
+                    ],
+                }],
+            }]),
+            json!({}),
+        );
+        let response = TestCmd::handle(&cmd, &Handler::default(), &discord.context())
+            .await
+            .unwrap();
+        assert_eq!(response.content.as_deref(), Some("3 Some(\"big\")"));
+        assert_eq!(TestCmd::name().en, "test");
+    }
+}
@@ -123,3 +123,314 @@ pub const XOR: LocalizedString = LocalizedString { en: "xor" };
 pub const XOR_DESC: LocalizedString = LocalizedString {
     en: "Train and Test XOR Gate!",
 };
+
+// Stats
+pub const COMMANDS: LocalizedString = LocalizedString { en: "commands" };
+pub const COMMANDS_DESC: LocalizedString = LocalizedString {
+    en: "Retrieve command usage!",
+};
+pub const USERS: LocalizedString = LocalizedString { en: "users" };
+pub const USERS_DESC: LocalizedString = LocalizedString {
+    en: "Retrieve most active users!",
+};
+pub const ERRORS: LocalizedString = LocalizedString { en: "errors" };
+pub const ERRORS_DESC: LocalizedString = LocalizedString {
+    en: "Retrieve command errors!",
+};
+pub const COLLECTION: LocalizedString = LocalizedString { en: "collection" };
+pub const COLLECTION_DESC: LocalizedString = LocalizedString {
+    en: "Enable or disable usage collection for this server!",
+};
+pub const WINDOW: LocalizedString = LocalizedString { en: "window" };
+pub const WINDOW_DESC: LocalizedString = LocalizedString {
+    en: "Time window to look at!",
+};
+pub const ENABLED: LocalizedString = LocalizedString { en: "enabled" };
+pub const ENABLED_DESC: LocalizedString = LocalizedString {
+    en: "Whether it should be enabled!",
+};
+
+// Moderation
+pub const KICK: LocalizedString = LocalizedString { en: "kick" };
+pub const KICK_DESC: LocalizedString = LocalizedString {
+    en: "Kick a member!",
+};
+pub const BAN: LocalizedString = LocalizedString { en: "ban" };
+pub const BAN_DESC: LocalizedString = LocalizedString { en: "Ban a user!" };
+pub const UNBAN: LocalizedString = LocalizedString { en: "unban" };
+pub const UNBAN_DESC: LocalizedString = LocalizedString {
+    en: "Unban a user!",
+};
+pub const TIMEOUT: LocalizedString = LocalizedString { en: "timeout" };
+pub const TIMEOUT_DESC: LocalizedString = LocalizedString {
+    en: "Time out a member!",
+};
+pub const PURGE: LocalizedString = LocalizedString { en: "purge" };
+pub const PURGE_DESC: LocalizedString = LocalizedString {
+    en: "Delete recent messages in this channel!",
+};
+pub const SLOWMODE: LocalizedString = LocalizedString { en: "slowmode" };
+pub const SLOWMODE_DESC: LocalizedString = LocalizedString {
+    en: "Set the slowmode of a channel!",
+};
+pub const TARGET: LocalizedString = LocalizedString { en: "member" };
+pub const TARGET_DESC: LocalizedString = LocalizedString {
+    en: "Member to moderate!",
+};
+pub const TARGET_USER: LocalizedString = LocalizedString { en: "user" };
+pub const TARGET_USER_DESC: LocalizedString = LocalizedString {
+    en: "User to moderate!",
+};
+pub const REASON: LocalizedString = LocalizedString { en: "reason" };
+pub const REASON_DESC: LocalizedString = LocalizedString {
+    en: "Reason recorded in the audit log!",
+};
+pub const DELETE_DAYS: LocalizedString = LocalizedString { en: "delete_days" };
+pub const DELETE_DAYS_DESC: LocalizedString = LocalizedString {
+    en: "Days of messages to delete!",
+};
+pub const MINUTES: LocalizedString = LocalizedString { en: "minutes" };
+pub const MINUTES_DESC: LocalizedString = LocalizedString {
+    en: "Duration in minutes, 0 removes the timeout!",
+};
+pub const COUNT: LocalizedString = LocalizedString { en: "count" };
+pub const COUNT_DESC: LocalizedString = LocalizedString {
+    en: "Number of messages!",
+};
+pub const SECONDS: LocalizedString = LocalizedString { en: "seconds" };
+pub const SECONDS_DESC: LocalizedString = LocalizedString {
+    en: "Delay in seconds, 0 disables it!",
+};
+
+// Cases
+pub const CASE_ID: LocalizedString = LocalizedString { en: "id" };
+pub const CASE_ID_DESC: LocalizedString = LocalizedString { en: "Case number!" };
+pub const EDIT: LocalizedString = LocalizedString { en: "edit" };
+pub const EDIT_DESC: LocalizedString = LocalizedString {
+    en: "Edit the reason of a case!",
+};
+pub const DELETE: LocalizedString = LocalizedString { en: "delete" };
+pub const DELETE_DESC: LocalizedString = LocalizedString {
+    en: "Delete a case!",
+};
+pub const MODLOG: LocalizedString = LocalizedString { en: "log" };
+pub const MODLOG_DESC: LocalizedString = LocalizedString {
+    en: "Set or clear the mod-log channel!",
+};
+pub const CASE_HISTORY: LocalizedString = LocalizedString { en: "history" };
+pub const CASE_HISTORY_DESC: LocalizedString = LocalizedString {
+    en: "Retrieve moderation history!",
+};
+
+// Server log
+pub const SERVERLOG: LocalizedString = LocalizedString { en: "log" };
+pub const SERVERLOG_DESC: LocalizedString = LocalizedString {
+    en: "Configure the server log!",
+};
+pub const SET: LocalizedString = LocalizedString { en: "set" };
+pub const SET_DESC: LocalizedString = LocalizedString {
+    en: "Set the channel to post to!",
+};
+pub const DISABLE: LocalizedString = LocalizedString { en: "disable" };
+pub const DISABLE_DESC: LocalizedString = LocalizedString {
+    en: "Stop posting!",
+};
+
+// Text commands
+pub const PREFIX: LocalizedString = LocalizedString { en: "prefix" };
+pub const PREFIX_DESC: LocalizedString = LocalizedString {
+    en: "Set the prefix for text commands, leave empty to disable them!",
+};
+pub const PREFIX_VALUE: LocalizedString = LocalizedString { en: "value" };
+pub const PREFIX_VALUE_DESC: LocalizedString = LocalizedString {
+    en: "The new prefix, e.g. !",
+};
+
+// Automod
+pub const RULE: LocalizedString = LocalizedString { en: "rule" };
+pub const RULE_DESC: LocalizedString = LocalizedString {
+    en: "Manage automod rules!",
+};
+pub const RULE_ADD: LocalizedString = LocalizedString { en: "add" };
+pub const RULE_ADD_DESC: LocalizedString = LocalizedString {
+    en: "Add an automod rule!",
+};
+pub const RULE_LIST: LocalizedString = LocalizedString { en: "list" };
+pub const RULE_LIST_DESC: LocalizedString = LocalizedString {
+    en: "List the automod rules!",
+};
+pub const RULE_REMOVE: LocalizedString = LocalizedString { en: "remove" };
+pub const RULE_REMOVE_DESC: LocalizedString = LocalizedString {
+    en: "Remove an automod rule!",
+};
+pub const RULE_TEST: LocalizedString = LocalizedString { en: "test" };
+pub const RULE_TEST_DESC: LocalizedString = LocalizedString {
+    en: "Check which rules a message would break!",
+};
+pub const RULE_KIND: LocalizedString = LocalizedString { en: "kind" };
+pub const RULE_KIND_DESC: LocalizedString = LocalizedString {
+    en: "What the rule looks for",
+};
+pub const RULE_ACTION: LocalizedString = LocalizedString { en: "action" };
+pub const RULE_ACTION_DESC: LocalizedString = LocalizedString {
+    en: "What happens when the rule is broken",
+};
+pub const RULE_VALUE: LocalizedString = LocalizedString { en: "value" };
+pub const RULE_VALUE_DESC: LocalizedString = LocalizedString {
+    en: "Comma separated words or file extensions, or a regex",
+};
+pub const RULE_THRESHOLD: LocalizedString = LocalizedString { en: "threshold" };
+pub const RULE_THRESHOLD_DESC: LocalizedString = LocalizedString {
+    en: "Mention or duplicate limit, or caps percentage",
+};
+pub const RULE_MINUTES_DESC: LocalizedString = LocalizedString {
+    en: "Timeout length in minutes",
+};
+pub const RULE_ID: LocalizedString = LocalizedString { en: "id" };
+pub const RULE_ID_DESC: LocalizedString = LocalizedString {
+    en: "The rule number",
+};
+pub const RULE_TEXT: LocalizedString = LocalizedString { en: "text" };
+pub const RULE_TEXT_DESC: LocalizedString = LocalizedString {
+    en: "The message to check",
+};
+
+// Welcome
+pub const WELCOME: LocalizedString = LocalizedString { en: "welcome" };
+pub const WELCOME_DESC: LocalizedString = LocalizedString {
+    en: "Configure welcome and goodbye messages!",
+};
+pub const WELCOME_SET_DESC: LocalizedString = LocalizedString {
+    en: "Set where and what to post when members join or leave!",
+};
+pub const PREVIEW: LocalizedString = LocalizedString { en: "preview" };
+pub const PREVIEW_DESC: LocalizedString = LocalizedString {
+    en: "Preview the messages with yourself as the member!",
+};
+pub const WELCOME_MESSAGE: LocalizedString = LocalizedString { en: "message" };
+pub const WELCOME_MESSAGE_DESC: LocalizedString = LocalizedString {
+    en: "Join message, supports {user} {username} {server} {member_count} {account_age}",
+};
+pub const GOODBYE_MESSAGE: LocalizedString = LocalizedString { en: "goodbye" };
+pub const GOODBYE_MESSAGE_DESC: LocalizedString = LocalizedString {
+    en: "Leave message, same placeholders as the join message",
+};
+pub const WELCOME_DM: LocalizedString = LocalizedString { en: "dm" };
+pub const WELCOME_DM_DESC: LocalizedString = LocalizedString {
+    en: "Message sent privately to new members",
+};
+pub const AUTO_ROLE: LocalizedString = LocalizedString { en: "role" };
+pub const AUTO_ROLE_DESC: LocalizedString = LocalizedString {
+    en: "Role given to new members",
+};
+
+// Role menus
+pub const MENU: LocalizedString = LocalizedString { en: "menu" };
+pub const MENU_DESC: LocalizedString = LocalizedString {
+    en: "Manage self-assignable role menus!",
+};
+pub const MENU_CREATE: LocalizedString = LocalizedString { en: "create" };
+pub const MENU_CREATE_DESC: LocalizedString = LocalizedString {
+    en: "Create a role menu!",
+};
+pub const MENU_ADD: LocalizedString = LocalizedString { en: "add" };
+pub const MENU_ADD_DESC: LocalizedString = LocalizedString {
+    en: "Add a role to a menu!",
+};
+pub const MENU_REMOVE: LocalizedString = LocalizedString { en: "remove" };
+pub const MENU_REMOVE_DESC: LocalizedString = LocalizedString {
+    en: "Remove a role from a menu!",
+};
+pub const MENU_PUBLISH: LocalizedString = LocalizedString { en: "publish" };
+pub const MENU_PUBLISH_DESC: LocalizedString = LocalizedString {
+    en: "Post a menu so members can use it!",
+};
+pub const MENU_ID: LocalizedString = LocalizedString { en: "id" };
+pub const MENU_ID_DESC: LocalizedString = LocalizedString {
+    en: "The menu number",
+};
+pub const MENU_TITLE: LocalizedString = LocalizedString { en: "title" };
+pub const MENU_TITLE_DESC: LocalizedString = LocalizedString {
+    en: "Title shown above the menu",
+};
+pub const MENU_STYLE: LocalizedString = LocalizedString { en: "style" };
+pub const MENU_STYLE_DESC: LocalizedString = LocalizedString {
+    en: "Buttons or a select menu",
+};
+pub const MENU_MAX: LocalizedString = LocalizedString { en: "max" };
+pub const MENU_MAX_DESC: LocalizedString = LocalizedString {
+    en: "How many of the roles a member may hold",
+};
+pub const MENU_EXCLUSIVE: LocalizedString = LocalizedString { en: "exclusive" };
+pub const MENU_EXCLUSIVE_DESC: LocalizedString = LocalizedString {
+    en: "Picking a role drops the others from the menu",
+};
+pub const MENU_REQUIRED: LocalizedString = LocalizedString {
+    en: "required_role",
+};
+pub const MENU_REQUIRED_DESC: LocalizedString = LocalizedString {
+    en: "Role needed to use the menu",
+};
+pub const MENU_ROLE: LocalizedString = LocalizedString { en: "role" };
+pub const MENU_ROLE_DESC: LocalizedString = LocalizedString { en: "The role" };
+
+// Role management
+pub const ROLE_MEMBERS: LocalizedString = LocalizedString { en: "members" };
+pub const ROLE_MEMBERS_DESC: LocalizedString = LocalizedString {
+    en: "List the members with a role!",
+};
+pub const GIVE: LocalizedString = LocalizedString { en: "give" };
+pub const GIVE_DESC: LocalizedString = LocalizedString {
+    en: "Give a role to members!",
+};
+pub const TAKE: LocalizedString = LocalizedString { en: "take" };
+pub const TAKE_DESC: LocalizedString = LocalizedString {
+    en: "Take a role from members!",
+};
+pub const ROLE_TARGET: LocalizedString = LocalizedString { en: "role" };
+pub const ROLE_TARGET_DESC: LocalizedString = LocalizedString { en: "The role" };
+pub const BULK_MEMBERS: LocalizedString = LocalizedString { en: "members" };
+pub const BULK_MEMBERS_DESC: LocalizedString = LocalizedString {
+    en: "Members as mentions or ids, separated by spaces",
+};
+
+// Server statistics
+pub const SERVER_STATS: LocalizedString = LocalizedString { en: "stats" };
+pub const SERVER_STATS_DESC: LocalizedString = LocalizedString {
+    en: "Retrieve member growth over time!",
+};
+
+// Activity tracking
+pub const ACTIVITY: LocalizedString = LocalizedString { en: "activity" };
+pub const ACTIVITY_DESC: LocalizedString = LocalizedString {
+    en: "Retrieve activity!",
+};
+pub const TRACKING: LocalizedString = LocalizedString { en: "tracking" };
+pub const TRACKING_DESC: LocalizedString = LocalizedString {
+    en: "Turn member activity tracking on or off!",
+};
+pub const TRACKING_ENABLED: LocalizedString = LocalizedString { en: "enabled" };
+pub const TRACKING_ENABLED_DESC: LocalizedString = LocalizedString {
+    en: "Whether to track presences and activities",
+};
+
+// Bot status
+pub const STATUS_SET_DESC: LocalizedString = LocalizedString {
+    en: "Set the statuses the bot cycles through!",
+};
+pub const STATUS_SHOW: LocalizedString = LocalizedString { en: "show" };
+pub const STATUS_SHOW_DESC: LocalizedString = LocalizedString {
+    en: "Show the statuses the bot cycles through!",
+};
+pub const STATUS_TEMPLATES: LocalizedString = LocalizedString { en: "statuses" };
+pub const STATUS_TEMPLATES_DESC: LocalizedString = LocalizedString {
+    en: "Statuses separated by |, using {guilds}, {commands} and {market}",
+};
+pub const STATUS_KIND: LocalizedString = LocalizedString { en: "kind" };
+pub const STATUS_KIND_DESC: LocalizedString = LocalizedString {
+    en: "Shown before each status",
+};
+pub const STATUS_INTERVAL: LocalizedString = LocalizedString { en: "interval" };
+pub const STATUS_INTERVAL_DESC: LocalizedString = LocalizedString {
+    en: "Seconds between statuses",
+};
@@ -0,0 +1,780 @@
+//! Bot configuration, read from a TOML file with a few settings that can be
+//! overridden from the environment.
+//!
+//! ```toml
+//! token = "..."
+//! intents = ["guilds", "guild_messages", "message_content"]
+//! disabled_commands = ["ml"]
+//! error_channel = 123456789012345678
+//!
+//! [logging]
+//! format = "compact"
+//! filter = "info,discord_bot=debug"
+//!
+//! [sharding]
+//! mode = "range"
+//! first = 0
+//! last = 1
+//! total = 4
+//!
+//! [storage]
+//! data_dir = "/var/lib/bot"
+//!
+//! [provider]
+//! timeout_secs = 5
+//!
+//! [limits]
+//! table_width = 100
+//!
+//! [cooldowns.commands.ml]
+//! per_user = { capacity = 1, period_secs = 60 }
+//!
+//! [[cooldowns.roles]]
+//! role = 234567890123456789
+//! command = "ml"
+//! capacity = 5
+//! period_secs = 60
+//! ```
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::prelude::GatewayIntents;
+use thiserror::Error;
+use tracing::*;
+use yahoo_finance_api as yahoo;
+
+use crate::commands::{global::GlobalCommands, guild::GuildCommands};
+use crate::handler::bot_status::MIN_INTERVAL_SECS;
+use crate::handler::cooldown::{BucketConfig, CommandLimit, CooldownConfig};
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Intents by the names used in the config file.
+const INTENTS: [(&str, GatewayIntents); 9] = [
+    ("guilds", GatewayIntents::GUILDS),
+    ("guild_members", GatewayIntents::GUILD_MEMBERS),
+    ("guild_bans", GatewayIntents::GUILD_BANS),
+    ("guild_messages", GatewayIntents::GUILD_MESSAGES),
+    (
+        "guild_message_reactions",
+        GatewayIntents::GUILD_MESSAGE_REACTIONS,
+    ),
+    ("guild_presences", GatewayIntents::GUILD_PRESENCES),
+    ("guild_voice_states", GatewayIntents::GUILD_VOICE_STATES),
+    ("direct_messages", GatewayIntents::DIRECT_MESSAGES),
+    ("message_content", GatewayIntents::MESSAGE_CONTENT),
+];
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Could not read {0}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("No token configured, set `token` or DISCORD_TOKEN")]
+    MissingToken,
+    #[error("Unknown intent `{0}`")]
+    UnknownIntent(String),
+    #[error("Unknown command `{0}` in disabled_commands")]
+    UnknownCommand(String),
+    #[error("Invalid {0}: {1}")]
+    Invalid(&'static str, String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Compact,
+    Full,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// A `tracing` filter directive, `RUST_LOG` is used when unset.
+    pub filter: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub data_dir: PathBuf,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            data_dir: PathBuf::from(crate::storage::DEFAULT_DATA_DIR),
+        }
+    }
+}
+
+/// Settings for the market data provider.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderConfig {
+    pub timeout_secs: u64,
+    /// Quote interval used by `/fear`, e.g. `1d`.
+    pub interval: String,
+    /// Symbol shown by the `{market}` status placeholder.
+    pub market_symbol: String,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        ProviderConfig {
+            timeout_secs: 10,
+            interval: String::from("1d"),
+            market_symbol: String::from("^VIX"),
+        }
+    }
+}
+
+impl ProviderConfig {
+    pub fn connector(&self) -> yahoo::YahooConnector {
+        yahoo::YahooConnector::builder()
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .build()
+            .unwrap_or_else(|err| {
+                warn!(?err, "could not build provider client, using defaults");
+                yahoo::YahooConnector::new()
+            })
+    }
+}
+
+/// Which shards the process runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ShardingConfig {
+    /// A single shard.
+    #[default]
+    Single,
+    /// As many shards as Discord recommends.
+    Auto,
+    /// Every shard of `total`.
+    Count { total: u64 },
+    /// The shards `first` to `last` of `total`, to split them over
+    /// processes.
+    Range { first: u64, last: u64, total: u64 },
+}
+
+impl ShardingConfig {
+    /// How many shards the process runs, unknown when autosharding.
+    pub fn shard_count(&self) -> Option<u64> {
+        match *self {
+            ShardingConfig::Single => Some(1),
+            ShardingConfig::Auto => None,
+            ShardingConfig::Count { total } => Some(total),
+            ShardingConfig::Range { first, last, .. } => Some(last - first + 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Seconds between status changes until one is set with `/botstatus`.
+    pub activity_interval_secs: u64,
+    /// Training steps `/ml` runs per network.
+    pub ml_iterations: u32,
+    /// Maximum width of text tables.
+    pub table_width: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            activity_interval_secs: 60,
+            ml_iterations: 300 * 1000,
+            table_width: 120,
+        }
+    }
+}
+
+/// `capacity` uses of a command, refilled over `period_secs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BucketSettings {
+    pub capacity: u32,
+    pub period_secs: u64,
+}
+
+impl From<BucketSettings> for BucketConfig {
+    fn from(settings: BucketSettings) -> Self {
+        BucketConfig::new(settings.capacity, settings.period_secs)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitSettings {
+    pub per_user: Option<BucketSettings>,
+    pub per_guild: Option<BucketSettings>,
+}
+
+impl From<LimitSettings> for CommandLimit {
+    fn from(settings: LimitSettings) -> Self {
+        CommandLimit {
+            per_user: settings.per_user.map(BucketConfig::from),
+            per_guild: settings.per_guild.map(BucketConfig::from),
+        }
+    }
+}
+
+/// Limits for one command in one guild.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GuildCooldown {
+    pub guild: GuildId,
+    pub command: String,
+    pub per_user: Option<BucketSettings>,
+    pub per_guild: Option<BucketSettings>,
+}
+
+/// A per-user bucket for members with `role`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoleCooldown {
+    pub role: RoleId,
+    pub command: String,
+    pub capacity: u32,
+    pub period_secs: u64,
+}
+
+/// A bucket for one user.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserCooldown {
+    pub user: UserId,
+    pub command: String,
+    pub capacity: u32,
+    pub period_secs: u64,
+}
+
+/// Command rate limits on top of the built-in ones. Commands listed here
+/// replace their built-in limits, an empty table removes them.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CooldownsConfig {
+    /// Limits for commands that have none of their own.
+    pub fallback: Option<LimitSettings>,
+    pub commands: HashMap<String, LimitSettings>,
+    pub guilds: Vec<GuildCooldown>,
+    pub roles: Vec<RoleCooldown>,
+    pub users: Vec<UserCooldown>,
+}
+
+impl CooldownsConfig {
+    pub fn cooldown_config(&self) -> CooldownConfig {
+        let mut config = CooldownConfig::default();
+        if let Some(fallback) = self.fallback {
+            config.fallback = fallback.into();
+        }
+        for (command, limit) in &self.commands {
+            config.commands.insert(command.clone(), (*limit).into());
+        }
+        for guild in &self.guilds {
+            let limit = LimitSettings {
+                per_user: guild.per_user,
+                per_guild: guild.per_guild,
+            };
+            config
+                .guilds
+                .insert((guild.guild, guild.command.clone()), limit.into());
+        }
+        for role in &self.roles {
+            config.roles.insert(
+                (role.role, role.command.clone()),
+                BucketConfig::new(role.capacity, role.period_secs),
+            );
+        }
+        for user in &self.users {
+            config.users.insert(
+                (user.user, user.command.clone()),
+                BucketConfig::new(user.capacity, user.period_secs),
+            );
+        }
+        config
+    }
+
+    /// Every command named in the section, with its buckets.
+    fn buckets(&self) -> Vec<(&str, BucketSettings)> {
+        let limits = self
+            .fallback
+            .iter()
+            .map(|limit| ("fallback", *limit))
+            .chain(self.commands.iter().map(|(c, limit)| (c.as_str(), *limit)))
+            .chain(self.guilds.iter().map(|guild| {
+                (
+                    guild.command.as_str(),
+                    LimitSettings {
+                        per_user: guild.per_user,
+                        per_guild: guild.per_guild,
+                    },
+                )
+            }));
+        let mut buckets: Vec<(&str, BucketSettings)> = limits
+            .flat_map(|(command, limit)| {
+                [limit.per_user, limit.per_guild]
+                    .into_iter()
+                    .flatten()
+                    .map(move |bucket| (command, bucket))
+            })
+            .collect();
+        buckets.extend(self.roles.iter().map(|role| {
+            (
+                role.command.as_str(),
+                BucketSettings {
+                    capacity: role.capacity,
+                    period_secs: role.period_secs,
+                },
+            )
+        }));
+        buckets.extend(self.users.iter().map(|user| {
+            (
+                user.command.as_str(),
+                BucketSettings {
+                    capacity: user.capacity,
+                    period_secs: user.period_secs,
+                },
+            )
+        }));
+        buckets
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let commands = self
+            .commands
+            .keys()
+            .map(String::as_str)
+            .chain(self.guilds.iter().map(|g| g.command.as_str()))
+            .chain(self.roles.iter().map(|r| r.command.as_str()))
+            .chain(self.users.iter().map(|u| u.command.as_str()));
+        for command in commands {
+            if !is_command(command) {
+                return Err(ConfigError::Invalid(
+                    "cooldowns",
+                    format!("unknown command `{}`", command),
+                ));
+            }
+        }
+        if let Some((command, _)) = self
+            .buckets()
+            .into_iter()
+            .find(|(_, bucket)| bucket.capacity == 0 || bucket.period_secs == 0)
+        {
+            return Err(ConfigError::Invalid(
+                "cooldowns",
+                format!(
+                    "the limits for `{}` need a positive capacity and period_secs",
+                    command
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn is_command(name: &str) -> bool {
+    GuildCommands::from_str(name).is_ok() || GlobalCommands::from_str(name).is_ok()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub token: String,
+    pub intents: Vec<String>,
+    /// Commands that are neither registered nor answered as text commands.
+    pub disabled_commands: Vec<String>,
+    /// Address of the health and metrics server, which is off when unset.
+    pub health_addr: Option<SocketAddr>,
+    /// Channel internal errors are reported in, which is off when unset.
+    pub error_channel: Option<ChannelId>,
+    pub sharding: ShardingConfig,
+    pub logging: LoggingConfig,
+    pub storage: StorageConfig,
+    pub provider: ProviderConfig,
+    pub limits: LimitsConfig,
+    pub cooldowns: CooldownsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            token: String::new(),
+            intents: [
+                "guilds",
+                "guild_messages",
+                "direct_messages",
+                "message_content",
+                "guild_members",
+                "guild_presences",
+            ]
+            .map(String::from)
+            .to_vec(),
+            disabled_commands: vec![],
+            health_addr: None,
+            error_channel: None,
+            sharding: ShardingConfig::default(),
+            logging: LoggingConfig::default(),
+            storage: StorageConfig::default(),
+            provider: ProviderConfig::default(),
+            limits: LimitsConfig::default(),
+            cooldowns: CooldownsConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the file at `BOT_CONFIG`, or `config.toml`, applies the
+    /// environment overrides and validates the result. A missing file means
+    /// the defaults.
+    pub fn load() -> Result<Config, ConfigError> {
+        let path = env::var("BOT_CONFIG").unwrap_or_else(|_| String::from(DEFAULT_CONFIG_PATH));
+        let mut config = Config::from_file(path)?;
+        config.apply_env(|name| env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text).map_err(|err| ConfigError::Parse(path.into(), err)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(ConfigError::Io(path.into(), err)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// Overrides settings from `DISCORD_TOKEN`, `DATA_DIR`, `HEALTH_ADDR`
+    /// and `LOG_FORMAT`.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(token) = var("DISCORD_TOKEN") {
+            self.token = token;
+        }
+        if let Some(data_dir) = var("DATA_DIR") {
+            self.storage.data_dir = PathBuf::from(data_dir);
+        }
+        if let Some(addr) = var("HEALTH_ADDR") {
+            self.health_addr = Some(addr.parse().map_err(|_| {
+                ConfigError::Invalid("HEALTH_ADDR", format!("`{}` is not a socket address", addr))
+            })?);
+        }
+        if let Some(format) = var("LOG_FORMAT") {
+            self.logging.format = match format.as_str() {
+                "pretty" => LogFormat::Pretty,
+                "compact" => LogFormat::Compact,
+                "full" => LogFormat::Full,
+                _ => {
+                    return Err(ConfigError::Invalid(
+                        "LOG_FORMAT",
+                        format!("`{}` is not one of pretty, compact, full", format),
+                    ))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.token.trim().is_empty() {
+            return Err(ConfigError::MissingToken);
+        }
+        self.gateway_intents()?;
+        if let Some(unknown) = self.disabled_commands.iter().find(|name| !is_command(name)) {
+            return Err(ConfigError::UnknownCommand(unknown.clone()));
+        }
+        match self.sharding {
+            ShardingConfig::Count { total: 0 } => {
+                return Err(ConfigError::Invalid(
+                    "sharding.total",
+                    String::from("must be positive"),
+                ))
+            }
+            ShardingConfig::Range { first, last, total } if first > last || last >= total => {
+                return Err(ConfigError::Invalid(
+                    "sharding",
+                    format!(
+                        "shards {} to {} are not within the {} shards",
+                        first, last, total
+                    ),
+                ))
+            }
+            _ => {}
+        }
+        if self.limits.activity_interval_secs < MIN_INTERVAL_SECS {
+            return Err(ConfigError::Invalid(
+                "limits.activity_interval_secs",
+                format!("must be at least {}", MIN_INTERVAL_SECS),
+            ));
+        }
+        if self.limits.ml_iterations == 0 {
+            return Err(ConfigError::Invalid(
+                "limits.ml_iterations",
+                String::from("must be positive"),
+            ));
+        }
+        if self.limits.table_width < 20 {
+            return Err(ConfigError::Invalid(
+                "limits.table_width",
+                String::from("must be at least 20"),
+            ));
+        }
+        if self.provider.timeout_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "provider.timeout_secs",
+                String::from("must be positive"),
+            ));
+        }
+        self.cooldowns.validate()?;
+        Ok(())
+    }
+
+    pub fn gateway_intents(&self) -> Result<GatewayIntents, ConfigError> {
+        self.intents
+            .iter()
+            .try_fold(GatewayIntents::empty(), |all, name| {
+                INTENTS
+                    .iter()
+                    .find(|(known, _)| known == name)
+                    .map(|(_, intent)| all | *intent)
+                    .ok_or_else(|| ConfigError::UnknownIntent(name.clone()))
+            })
+    }
+
+    pub fn is_enabled(&self, command: &str) -> bool {
+        !self.disabled_commands.iter().any(|name| name == command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn valid() -> Config {
+        Config {
+            token: String::from("token"),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn parses_partial_files() {
+        let config = Config::parse(
+            r#"
+            token = "abc"
+            disabled_commands = ["ml"]
+            error_channel = 42
+
+            [limits]
+            table_width = 80
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.token, "abc");
+        assert!(!config.is_enabled("ml") && config.is_enabled("fear"));
+        assert_eq!(config.error_channel, Some(ChannelId(42)));
+        assert_eq!(config.limits.table_width, 80);
+        assert_eq!(config.limits.ml_iterations, 300 * 1000);
+        assert_eq!(config.provider.interval, "1d");
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn parses_sharding_modes() {
+        let parse = |text: &str| Config::parse(text).unwrap().sharding;
+        assert_eq!(parse(""), ShardingConfig::Single);
+        assert_eq!(parse("[sharding]\nmode = \"auto\""), ShardingConfig::Auto);
+        let range = parse("[sharding]\nmode = \"range\"\nfirst = 2\nlast = 3\ntotal = 4");
+        assert_eq!(
+            range,
+            ShardingConfig::Range {
+                first: 2,
+                last: 3,
+                total: 4
+            }
+        );
+        assert_eq!(range.shard_count(), Some(2));
+
+        let mut config = valid();
+        config.sharding = ShardingConfig::Range {
+            first: 2,
+            last: 4,
+            total: 4,
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("sharding", _))
+        ));
+    }
+
+    #[test]
+    fn parses_cooldowns() {
+        let config = Config::parse(
+            r#"
+            token = "abc"
+
+            [cooldowns.commands.ml]
+            per_user = { capacity = 2, period_secs = 60 }
+
+            [[cooldowns.guilds]]
+            guild = 1
+            command = "fear"
+            per_guild = { capacity = 20, period_secs = 60 }
+
+            [[cooldowns.users]]
+            user = 3
+            command = "ml"
+            capacity = 10
+            period_secs = 60
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let cooldowns = config.cooldowns.cooldown_config();
+
+        let ml = cooldowns.commands["ml"];
+        assert_eq!(ml.per_user, Some(BucketConfig::new(2, 60)));
+        assert_eq!(ml.per_guild, None);
+        assert!(cooldowns.commands["fear"].per_user.is_some());
+        let guild = cooldowns.guilds[&(GuildId(1), String::from("fear"))];
+        assert_eq!(guild.per_guild, Some(BucketConfig::new(20, 60)));
+        assert_eq!(
+            cooldowns.users[&(UserId(3), String::from("ml"))],
+            BucketConfig::new(10, 60)
+        );
+    }
+
+    #[test]
+    fn rejects_empty_cooldowns() {
+        let mut config = valid();
+        config.cooldowns.commands.insert(
+            String::from("ml"),
+            LimitSettings {
+                per_user: Some(BucketSettings {
+                    capacity: 0,
+                    period_secs: 60,
+                }),
+                per_guild: None,
+            },
+        );
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("cooldowns", _))
+        ));
+
+        let mut config = valid();
+        config.cooldowns.roles.push(RoleCooldown {
+            role: RoleId(1),
+            command: String::from("fear"),
+            capacity: 1,
+            period_secs: 0,
+        });
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("cooldowns", _))
+        ));
+
+        let mut config = valid();
+        config
+            .cooldowns
+            .commands
+            .insert(String::from("nope"), LimitSettings::default());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("cooldowns", message)) if message.contains("nope")
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Config::parse("[limits]\ntable_widht = 80").is_err());
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let env = HashMap::from([
+            ("DISCORD_TOKEN", "from-env"),
+            ("HEALTH_ADDR", "127.0.0.1:8080"),
+            ("LOG_FORMAT", "compact"),
+        ]);
+        let mut config = valid();
+        config
+            .apply_env(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.token, "from-env");
+        assert_eq!(config.health_addr, Some("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(config.logging.format, LogFormat::Compact);
+
+        let bad = HashMap::from([("HEALTH_ADDR", "nowhere")]);
+        let err = valid()
+            .apply_env(|name| bad.get(name).map(|v| v.to_string()))
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid("HEALTH_ADDR", _)));
+    }
+
+    #[test]
+    fn validation_explains_the_problem() {
+        assert!(matches!(
+            Config::default().validate(),
+            Err(ConfigError::MissingToken)
+        ));
+
+        let mut config = valid();
+        config.intents.push(String::from("guild_everything"));
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Unknown intent `guild_everything`"
+        );
+
+        let mut config = valid();
+        config.disabled_commands.push(String::from("nope"));
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::UnknownCommand(name)) if name == "nope"
+        ));
+
+        let mut config = valid();
+        config.limits.activity_interval_secs = 1;
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            format!(
+                "Invalid limits.activity_interval_secs: must be at least {}",
+                MIN_INTERVAL_SECS
+            )
+        );
+    }
+
+    #[test]
+    fn default_intents_are_the_ones_the_bot_needs() {
+        assert_eq!(
+            valid().gateway_intents().unwrap(),
+            GatewayIntents::GUILDS
+                | GatewayIntents::GUILD_MESSAGES
+                | GatewayIntents::DIRECT_MESSAGES
+                | GatewayIntents::MESSAGE_CONTENT
+                | GatewayIntents::GUILD_MEMBERS
+                | GatewayIntents::GUILD_PRESENCES
+        );
+    }
+}
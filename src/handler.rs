@@ -1,26 +1,70 @@
+pub mod analytics;
+pub mod automod;
+pub mod bot_status;
+pub mod cases;
 pub mod command_details;
 pub mod commands;
+pub mod confirm;
+pub mod cooldown;
+pub mod errors;
+pub mod flush;
+pub mod hierarchy;
+pub mod invocation;
+pub mod member_growth;
+pub mod metrics;
+pub mod options;
+pub mod paginate;
+pub mod presence;
+pub mod response;
+pub mod role_menus;
+pub mod server_log;
+pub mod shards;
+pub mod text_commands;
+pub mod welcome;
 
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::storage::StorageError;
+use serenity::model::id::RoleId;
+use strum_macros::IntoStaticStr;
 use thiserror::Error;
 
-pub struct Handler;
+pub struct Handler {
+    pub config: Arc<Config>,
+    /// When the bot started, for its uptime.
+    pub started: Instant,
+}
 
 impl Handler {
-    pub fn new() -> Result<Handler, HandlerError> {
-        Ok(Handler)
+    pub fn new(config: Config) -> Handler {
+        Handler {
+            config: Arc::new(config),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Default for Handler {
+    fn default() -> Self {
+        Handler {
+            config: Arc::default(),
+            started: Instant::now(),
+        }
     }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, IntoStaticStr)]
 pub enum HandlerError {
     #[error("Unrecognized command ({0})")]
     UnrecognizedCommand(String),
     #[error("Command was empty")]
     EmptyCommand,
-    #[error("Internal error, could not build response")]
+    #[error("Couldn't find what the command refers to")]
     TargetNone,
     #[error("Failed to send message")]
-    Send(#[from] serenity::Error),
+    Send(#[source] Box<serenity::Error>),
     #[error("Command can only be used in a server")]
     NotGuild,
     #[error("Timed out or had too many inputs")]
@@ -31,20 +75,91 @@ pub enum HandlerError {
     UnexpectedData,
     #[error("Maximum number of commands reached")]
     ApplicationCommandCap,
-    #[error("Internal error, could not build response")]
+    #[error("Emote counts need at least one emote to count")]
     EmoteLogCountNoParams,
-    #[error("Internal error, could not build response")]
+    #[error("There was nothing to count")]
     CountNone,
     #[error("Received command info for unknown command")]
     CommandRegisterUnknown,
-    #[error("Internal error, could not build response")]
+    #[error("The bot is still starting up, try again in a moment")]
     TypeMapNotFound,
     #[error("Could not set up application commands")]
     CommandSetup,
+    #[error("You're doing that too often, try again in {0}s")]
+    Cooldown(u64),
+    #[error("You don't have permission to do that")]
+    MissingPermissions,
+    #[error("Only the bot owner can do that")]
+    NotOwner,
+    #[error("Could not save changes")]
+    Storage(#[from] StorageError),
+    #[error("Missing option ({0})")]
+    MissingOption(&'static str),
+    #[error("Option {0} must be {1}")]
+    InvalidOptionType(&'static str, &'static str),
+    #[error("You can't moderate a member whose highest role is equal to or above yours")]
+    InvokerHierarchy,
+    #[error("I can't moderate a member whose highest role is equal to or above mine")]
+    BotHierarchy,
+    #[error("You can't manage a role equal to or above your highest role")]
+    InvokerRoleHierarchy,
+    #[error("I can't manage a role equal to or above my highest role")]
+    BotRoleHierarchy,
+    #[error("Couldn't find case #{0}")]
+    CaseNotFound(u64),
+    #[error("Invalid argument ({0})")]
+    InvalidArgument(String),
+    #[error("Couldn't find automod rule #{0}")]
+    RuleNotFound(u64),
+    #[error("This server already has the maximum number of automod rules")]
+    RuleLimit,
+    #[error("Couldn't find role menu #{0}")]
+    MenuNotFound(u64),
+    #[error("This role menu is full")]
+    MenuFull,
+    #[error("You need the <@&{0}> role to use this menu")]
+    RequiredRole(RoleId),
+    #[error("You can pick at most {0} roles from this menu")]
+    TooManyRoles(u64),
+    #[error("Activity tracking is turned off in this server")]
+    TrackingDisabled,
+    #[error("Could not read or write data")]
+    Json(#[from] serde_json::Error),
+    #[error("Something went wrong while handling the command")]
+    Panic(String),
+}
+
+impl From<serenity::Error> for HandlerError {
+    fn from(err: serenity::Error) -> Self {
+        HandlerError::Send(Box::new(err))
+    }
 }
 
 impl HandlerError {
+    pub fn kind(&self) -> &'static str {
+        self.into()
+    }
+
     pub fn should_followup(&self) -> bool {
         !matches!(self, HandlerError::TimeoutOrOverLimit)
     }
+
+    /// Errors that are the bot's fault rather than the invoker's. They're
+    /// shown with an error id and reported to the error channel.
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            HandlerError::TargetNone
+                | HandlerError::Send(_)
+                | HandlerError::UnexpectedData
+                | HandlerError::EmoteLogCountNoParams
+                | HandlerError::CountNone
+                | HandlerError::CommandRegisterUnknown
+                | HandlerError::TypeMapNotFound
+                | HandlerError::CommandSetup
+                | HandlerError::Storage(_)
+                | HandlerError::Json(_)
+                | HandlerError::Panic(_)
+        )
+    }
 }
@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::TypeMapKey;
+
+use crate::storage::{JsonStore, PendingSave, StorageError};
+
+use super::options::subcommand_path;
+use super::HandlerError;
+
+const MAX_RECORDS: usize = 50_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InteractionRecord {
+    pub at: SystemTime,
+    pub command: String,
+    pub subcommand: Option<String>,
+    pub guild_id: Option<GuildId>,
+    pub user_id: UserId,
+    /// How long the invoker waited for the first answer.
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+impl InteractionRecord {
+    pub fn new(
+        cmd: &ApplicationCommandInteraction,
+        latency: Duration,
+        result: &Result<(), HandlerError>,
+    ) -> InteractionRecord {
+        InteractionRecord {
+            at: SystemTime::now(),
+            command: cmd.data.name.clone(),
+            subcommand: subcommand(cmd),
+            guild_id: cmd.guild_id,
+            user_id: cmd.user.id,
+            latency,
+            error: result.as_ref().err().map(|err| err.kind().to_string()),
+        }
+    }
+}
+
+fn subcommand(cmd: &ApplicationCommandInteraction) -> Option<String> {
+    let (path, _) = subcommand_path(&cmd.data.options);
+    (!path.is_empty()).then(|| path.join(" "))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsWindow {
+    Hour,
+    Day,
+    Week,
+    All,
+}
+
+impl FromStr for StatsWindow {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<StatsWindow, Self::Err> {
+        match input {
+            "hour" => Ok(StatsWindow::Hour),
+            "day" => Ok(StatsWindow::Day),
+            "week" => Ok(StatsWindow::Week),
+            "all" => Ok(StatsWindow::All),
+            _ => Err(()),
+        }
+    }
+}
+
+impl StatsWindow {
+    pub fn label(self) -> &'static str {
+        match self {
+            StatsWindow::Hour => "last hour",
+            StatsWindow::Day => "last 24 hours",
+            StatsWindow::Week => "last 7 days",
+            StatsWindow::All => "all time",
+        }
+    }
+
+    fn duration(self) -> Option<Duration> {
+        match self {
+            StatsWindow::Hour => Some(Duration::from_secs(60 * 60)),
+            StatsWindow::Day => Some(Duration::from_secs(24 * 60 * 60)),
+            StatsWindow::Week => Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            StatsWindow::All => None,
+        }
+    }
+
+    fn contains(self, now: SystemTime, at: SystemTime) -> bool {
+        match self.duration() {
+            Some(duration) => now.duration_since(at).map_or(true, |age| age <= duration),
+            None => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsageRow {
+    pub key: String,
+    pub count: usize,
+    pub errors: usize,
+    pub avg_latency: Duration,
+}
+
+/// Command usage per guild, kept for the last [`MAX_RECORDS`] commands.
+/// Records are written to disk by the periodic flush.
+#[derive(Debug)]
+pub struct Analytics {
+    records: JsonStore<VecDeque<InteractionRecord>>,
+    /// Commands handled since startup, including opted out guilds.
+    total: u64,
+    opted_out: JsonStore<HashSet<GuildId>>,
+}
+
+impl TypeMapKey for Analytics {
+    type Value = Analytics;
+}
+
+impl Default for Analytics {
+    fn default() -> Self {
+        Analytics {
+            records: JsonStore::open("analytics"),
+            total: 0,
+            opted_out: JsonStore::open("analytics_opt_out"),
+        }
+    }
+}
+
+impl Analytics {
+    pub fn record(&mut self, record: InteractionRecord) {
+        self.total += 1;
+        if let Some(guild_id) = record.guild_id {
+            if !self.is_enabled(guild_id) {
+                return;
+            }
+        }
+        self.records.update_later(|records| {
+            if records.len() >= MAX_RECORDS {
+                records.pop_front();
+            }
+            records.push_back(record);
+        });
+    }
+
+    pub fn take_pending(&mut self) -> Result<Option<PendingSave>, StorageError> {
+        self.records.take_pending()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_enabled(&self, guild_id: GuildId) -> bool {
+        !self.opted_out.get().contains(&guild_id)
+    }
+
+    /// Turns collection on or off for a guild. Opting out also drops the
+    /// records already collected for it; those are rewritten by the next
+    /// flush rather than here, since there can be many of them.
+    pub fn set_enabled(&mut self, guild_id: GuildId, enabled: bool) -> Result<(), StorageError> {
+        self.opted_out.update(|opted_out| {
+            if enabled {
+                opted_out.remove(&guild_id);
+            } else {
+                opted_out.insert(guild_id);
+            }
+        })?;
+        if !enabled {
+            self.records
+                .update_later(|records| records.retain(|r| r.guild_id != Some(guild_id)));
+        }
+        Ok(())
+    }
+
+    fn records_in(
+        &self,
+        guild_id: GuildId,
+        window: StatsWindow,
+    ) -> impl Iterator<Item = &InteractionRecord> + '_ {
+        let now = SystemTime::now();
+        self.records
+            .get()
+            .iter()
+            .filter(move |r| r.guild_id == Some(guild_id) && window.contains(now, r.at))
+    }
+
+    fn usage_by<F>(&self, guild_id: GuildId, window: StatsWindow, key: F) -> Vec<UsageRow>
+    where
+        F: Fn(&InteractionRecord) -> String,
+    {
+        let mut rows: HashMap<String, (usize, usize, Duration)> = HashMap::new();
+        for record in self.records_in(guild_id, window) {
+            let row = rows.entry(key(record)).or_default();
+            row.0 += 1;
+            if record.error.is_some() {
+                row.1 += 1;
+            }
+            row.2 += record.latency;
+        }
+
+        let mut rows: Vec<UsageRow> = rows
+            .into_iter()
+            .map(|(key, (count, errors, latency))| UsageRow {
+                key,
+                count,
+                errors,
+                avg_latency: latency / count as u32,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        rows
+    }
+
+    pub fn commands(&self, guild_id: GuildId, window: StatsWindow) -> Vec<UsageRow> {
+        self.usage_by(guild_id, window, |r| match &r.subcommand {
+            Some(sub) => format!("/{} {}", r.command, sub),
+            None => format!("/{}", r.command),
+        })
+    }
+
+    pub fn users(&self, guild_id: GuildId, window: StatsWindow) -> Vec<UsageRow> {
+        self.usage_by(guild_id, window, |r| r.user_id.to_string())
+    }
+
+    pub fn errors(&self, guild_id: GuildId, window: StatsWindow) -> Vec<UsageRow> {
+        let mut rows = HashMap::new();
+        for record in self.records_in(guild_id, window) {
+            if let Some(error) = &record.error {
+                *rows.entry(error.as_str()).or_insert(0) += 1;
+            }
+        }
+        let mut rows: Vec<UsageRow> = rows
+            .into_iter()
+            .map(|(key, count)| UsageRow {
+                key: key.to_string(),
+                count,
+                errors: count,
+                avg_latency: Duration::ZERO,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: GuildId = GuildId(1);
+
+    fn record(command: &str, age_secs: u64, error: Option<&str>) -> InteractionRecord {
+        InteractionRecord {
+            at: SystemTime::now() - Duration::from_secs(age_secs),
+            command: command.to_string(),
+            subcommand: None,
+            guild_id: Some(GUILD),
+            user_id: UserId(2),
+            latency: Duration::from_millis(100),
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn keeps_records_until_flushed() {
+        let mut analytics = Analytics {
+            records: JsonStore::temporary("analytics"),
+            total: 0,
+            opted_out: JsonStore::temporary("analytics_opt_out"),
+        };
+        analytics.record(record("ml", 0, None));
+        analytics.record(record("ml", 2 * 24 * 60 * 60, Some("Cooldown")));
+        analytics.record(record("fear", 10 * 24 * 60 * 60, None));
+
+        let keys = |window| -> Vec<(String, usize)> {
+            analytics
+                .commands(GUILD, window)
+                .into_iter()
+                .map(|row| (row.key, row.count))
+                .collect()
+        };
+        assert_eq!(keys(StatsWindow::Day), [(String::from("/ml"), 1)]);
+        assert_eq!(keys(StatsWindow::Week), [(String::from("/ml"), 2)]);
+        assert_eq!(keys(StatsWindow::All).len(), 2);
+        assert_eq!(analytics.errors(GUILD, StatsWindow::All)[0].key, "Cooldown");
+
+        let pending = analytics.take_pending().unwrap().unwrap();
+        pending.write().unwrap();
+        assert!(analytics.take_pending().unwrap().is_none());
+
+        analytics.set_enabled(GUILD, false).unwrap();
+        assert!(analytics.take_pending().unwrap().is_some());
+        analytics.record(record("ml", 0, None));
+        assert!(analytics.commands(GUILD, StatsWindow::All).is_empty());
+        assert_eq!(analytics.total(), 4);
+    }
+}
@@ -0,0 +1,709 @@
+use std::collections::{HashMap, VecDeque};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::model::Timestamp;
+use serenity::prelude::TypeMapKey;
+use serenity::utils::Color;
+use tracing::*;
+
+use crate::storage::{JsonStore, StorageError};
+
+use super::cases::{record_case, CaseAction};
+use super::{Handler, HandlerError};
+
+pub const MAX_RULES: usize = 25;
+const DUPLICATE_WINDOW_SECS: i64 = 30;
+const MAX_RECENT_PER_USER: usize = 10;
+const MAX_TRACKED_USERS: usize = 10_000;
+const MIN_CAPS_LETTERS: usize = 10;
+const INVITE_HOSTS: [&str; 3] = [
+    "discord.gg/",
+    "discord.com/invite/",
+    "discordapp.com/invite/",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleKind {
+    Words { words: Vec<String> },
+    Regex { pattern: String },
+    Invites,
+    Mentions { max: u64 },
+    Duplicates { max: u64 },
+    Caps { percent: u64 },
+    Attachments { extensions: Vec<String> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RuleAction {
+    Delete,
+    Warn,
+    Timeout { minutes: i64 },
+    Log,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: u64,
+    pub kind: RuleKind,
+    pub action: RuleAction,
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim().trim_start_matches('.').to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+impl RuleKind {
+    /// Builds a rule from the `kind`, `value` and `threshold` command options.
+    pub fn parse(
+        kind: &str,
+        value: Option<&str>,
+        threshold: Option<i64>,
+    ) -> Result<RuleKind, HandlerError> {
+        let required_value = || {
+            value.filter(|v| !v.trim().is_empty()).ok_or_else(|| {
+                HandlerError::InvalidArgument(format!("{} rules need a value", kind))
+            })
+        };
+        let threshold_or = |default: u64| threshold.map_or(default, |t| t.max(1) as u64);
+        Ok(match kind {
+            "words" => RuleKind::Words {
+                words: split_list(required_value()?),
+            },
+            "regex" => RuleKind::Regex {
+                pattern: required_value()?.to_string(),
+            },
+            "invites" => RuleKind::Invites,
+            "mentions" => RuleKind::Mentions {
+                max: threshold_or(5),
+            },
+            "duplicates" => RuleKind::Duplicates {
+                max: threshold_or(3),
+            },
+            "caps" => RuleKind::Caps {
+                percent: threshold_or(70).min(100),
+            },
+            "attachments" => RuleKind::Attachments {
+                extensions: split_list(required_value()?),
+            },
+            _ => {
+                return Err(HandlerError::InvalidArgument(format!(
+                    "unknown rule kind {}",
+                    kind
+                )))
+            }
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RuleKind::Words { words } => format!("Banned words: {}", words.join(", ")),
+            RuleKind::Regex { pattern } => format!("Matches `{}`", pattern),
+            RuleKind::Invites => String::from("Invite links"),
+            RuleKind::Mentions { max } => format!("More than {} mentions", max),
+            RuleKind::Duplicates { max } => format!(
+                "Same message more than {} times in {}s",
+                max, DUPLICATE_WINDOW_SECS
+            ),
+            RuleKind::Caps { percent } => format!("At least {}% capital letters", percent),
+            RuleKind::Attachments { extensions } => {
+                format!("Attachments of type {}", extensions.join(", "))
+            }
+        }
+    }
+
+    /// Why `msg` breaks this rule, if it does. `duplicates` is how many
+    /// times the author sent the same content recently, this one included.
+    /// Regex rules match with `regex`, their compiled pattern.
+    pub fn check(
+        &self,
+        msg: &AutomodMessage,
+        duplicates: usize,
+        regex: Option<&Regex>,
+    ) -> Option<String> {
+        let content = msg.content.to_lowercase();
+        match self {
+            RuleKind::Words { words } => {
+                let tokens: Vec<&str> = content
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                words
+                    .iter()
+                    .find(|word| tokens.contains(&word.as_str()))
+                    .map(|word| format!("used banned word \"{}\"", word))
+            }
+            RuleKind::Regex { pattern } => regex
+                .filter(|re| re.is_match(&msg.content))
+                .map(|_| format!("matched `{}`", pattern)),
+            RuleKind::Invites => INVITE_HOSTS
+                .iter()
+                .any(|host| content.contains(host))
+                .then(|| String::from("posted an invite link")),
+            RuleKind::Mentions { max } => (msg.mentions as u64 > *max)
+                .then(|| format!("mentioned {} users or roles", msg.mentions)),
+            RuleKind::Duplicates { max } => (duplicates as u64 > *max)
+                .then(|| format!("sent the same message {} times", duplicates)),
+            RuleKind::Caps { percent } => {
+                let letters = msg.content.chars().filter(|c| c.is_alphabetic()).count();
+                if letters < MIN_CAPS_LETTERS {
+                    return None;
+                }
+                let upper = msg.content.chars().filter(|c| c.is_uppercase()).count();
+                let ratio = (upper * 100 / letters) as u64;
+                (ratio >= *percent)
+                    .then(|| format!("{}% of the message was capital letters", ratio))
+            }
+            RuleKind::Attachments { extensions } => msg
+                .attachments
+                .iter()
+                .find(|name| {
+                    name.rsplit_once('.')
+                        .is_some_and(|(_, ext)| extensions.contains(&ext.to_lowercase()))
+                })
+                .map(|name| format!("attached blocked file {}", name)),
+        }
+    }
+}
+
+impl RuleAction {
+    pub fn parse(action: &str, minutes: Option<i64>) -> Result<RuleAction, HandlerError> {
+        Ok(match action {
+            "delete" => RuleAction::Delete,
+            "warn" => RuleAction::Warn,
+            "timeout" => RuleAction::Timeout {
+                minutes: minutes.unwrap_or(10).clamp(1, 28 * 24 * 60),
+            },
+            "log" => RuleAction::Log,
+            _ => {
+                return Err(HandlerError::InvalidArgument(format!(
+                    "unknown action {}",
+                    action
+                )))
+            }
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RuleAction::Delete => String::from("delete"),
+            RuleAction::Warn => String::from("delete and warn"),
+            RuleAction::Timeout { minutes } => format!("delete and time out for {}m", minutes),
+            RuleAction::Log => String::from("log only"),
+        }
+    }
+
+    pub fn deletes(&self) -> bool {
+        !matches!(self, RuleAction::Log)
+    }
+}
+
+/// The parts of a message rules look at.
+#[derive(Clone, Debug, Default)]
+pub struct AutomodMessage {
+    pub content: String,
+    pub mentions: usize,
+    pub attachments: Vec<String>,
+}
+
+impl From<&Message> for AutomodMessage {
+    fn from(msg: &Message) -> Self {
+        AutomodMessage {
+            content: msg.content.clone(),
+            mentions: msg.mentions.len()
+                + msg.mention_roles.len()
+                + usize::from(msg.mention_everyone),
+            attachments: msg.attachments.iter().map(|a| a.filename.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub rule: Rule,
+    pub reason: String,
+}
+
+/// Every rule `msg` breaks, in rule order. `regexes` holds the compiled
+/// patterns of regex rules by rule id.
+pub fn evaluate(
+    rules: &[Rule],
+    regexes: &HashMap<u64, Regex>,
+    msg: &AutomodMessage,
+    duplicates: usize,
+) -> Vec<Violation> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            rule.kind
+                .check(msg, duplicates, regexes.get(&rule.id))
+                .map(|reason| Violation {
+                    rule: rule.clone(),
+                    reason,
+                })
+        })
+        .collect()
+}
+
+fn compile(kind: &RuleKind) -> Result<Option<Regex>, HandlerError> {
+    match kind {
+        RuleKind::Regex { pattern } => Regex::new(pattern)
+            .map(Some)
+            .map_err(|err| HandlerError::InvalidArgument(err.to_string())),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildRules {
+    next_id: u64,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+pub struct Automod {
+    rules: JsonStore<HashMap<GuildId, GuildRules>>,
+    /// Compiled patterns of the regex rules, by guild and rule id.
+    regexes: HashMap<GuildId, HashMap<u64, Regex>>,
+    recent: HashMap<(GuildId, UserId), VecDeque<(i64, String)>>,
+}
+
+impl TypeMapKey for Automod {
+    type Value = Automod;
+}
+
+impl Default for Automod {
+    fn default() -> Self {
+        Automod::from_store(JsonStore::open("automod"))
+    }
+}
+
+impl Automod {
+    fn from_store(rules: JsonStore<HashMap<GuildId, GuildRules>>) -> Automod {
+        let mut regexes: HashMap<GuildId, HashMap<u64, Regex>> = HashMap::new();
+        for (guild_id, guild) in rules.get() {
+            for rule in &guild.rules {
+                match compile(&rule.kind) {
+                    Ok(Some(regex)) => {
+                        regexes.entry(*guild_id).or_default().insert(rule.id, regex);
+                    }
+                    Ok(None) => {}
+                    Err(err) => warn!(?err, rule = rule.id, "stored automod pattern is invalid"),
+                }
+            }
+        }
+        Automod {
+            rules,
+            regexes,
+            recent: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self, guild_id: GuildId) -> &[Rule] {
+        self.rules
+            .get()
+            .get(&guild_id)
+            .map(|g| g.rules.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Stores a new rule, failing with `InvalidArgument` for regex rules
+    /// whose pattern doesn't compile.
+    pub fn add_rule(
+        &mut self,
+        guild_id: GuildId,
+        kind: RuleKind,
+        action: RuleAction,
+    ) -> Result<Rule, HandlerError> {
+        let regex = compile(&kind)?;
+        let rule = self.rules.update(|guilds| {
+            let guild = guilds.entry(guild_id).or_default();
+            guild.next_id += 1;
+            let rule = Rule {
+                id: guild.next_id,
+                kind,
+                action,
+            };
+            guild.rules.push(rule.clone());
+            rule
+        })?;
+        if let Some(regex) = regex {
+            self.regexes
+                .entry(guild_id)
+                .or_default()
+                .insert(rule.id, regex);
+        }
+        Ok(rule)
+    }
+
+    pub fn remove_rule(
+        &mut self,
+        guild_id: GuildId,
+        id: u64,
+    ) -> Result<Option<Rule>, StorageError> {
+        if let Some(regexes) = self.regexes.get_mut(&guild_id) {
+            regexes.remove(&id);
+        }
+        self.rules.update(|guilds| {
+            let guild = guilds.get_mut(&guild_id)?;
+            let index = guild.rules.iter().position(|r| r.id == id)?;
+            Some(guild.rules.remove(index))
+        })
+    }
+
+    /// Every rule of the guild's that `msg` breaks.
+    pub fn evaluate(
+        &self,
+        guild_id: GuildId,
+        msg: &AutomodMessage,
+        duplicates: usize,
+    ) -> Vec<Violation> {
+        let no_regexes = HashMap::new();
+        let regexes = self.regexes.get(&guild_id).unwrap_or(&no_regexes);
+        evaluate(self.rules(guild_id), regexes, msg, duplicates)
+    }
+
+    /// Remembers `msg` for duplicate detection and returns the first rule it
+    /// breaks. `now` is a unix timestamp in seconds.
+    pub fn check(
+        &mut self,
+        guild_id: GuildId,
+        author: UserId,
+        now: i64,
+        msg: &AutomodMessage,
+    ) -> Option<Violation> {
+        if self.rules(guild_id).is_empty() {
+            return None;
+        }
+
+        if self.recent.len() > MAX_TRACKED_USERS {
+            self.recent.retain(|_, sent| {
+                sent.back()
+                    .is_some_and(|(at, _)| now - at <= DUPLICATE_WINDOW_SECS)
+            });
+        }
+        let sent = self.recent.entry((guild_id, author)).or_default();
+        while sent
+            .front()
+            .is_some_and(|(at, _)| now - at > DUPLICATE_WINDOW_SECS)
+        {
+            sent.pop_front();
+        }
+        let content = msg.content.trim().to_lowercase();
+        sent.push_back((now, content.clone()));
+        if sent.len() > MAX_RECENT_PER_USER {
+            sent.pop_front();
+        }
+        let duplicates = if content.is_empty() {
+            0
+        } else {
+            sent.iter().filter(|(_, c)| *c == content).count()
+        };
+
+        self.evaluate(guild_id, msg, duplicates).into_iter().next()
+    }
+}
+
+/// Moderators aren't held to automod rules.
+fn is_exempt(permissions: Permissions) -> bool {
+    permissions.intersects(Permissions::ADMINISTRATOR | Permissions::MANAGE_MESSAGES)
+}
+
+impl Handler {
+    /// Applies the guild's automod rules to `msg`. Returns whether the
+    /// message was deleted.
+    pub async fn run_automod(&self, context: &Context, msg: &Message) -> bool {
+        let Some(guild_id) = msg.guild_id.filter(|_| !msg.author.bot) else {
+            return false;
+        };
+        let violation = {
+            let mut data = context.data.write().await;
+            let Some(automod) = data.get_mut::<Automod>() else {
+                return false;
+            };
+            automod.check(
+                guild_id,
+                msg.author.id,
+                msg.timestamp.unix_timestamp(),
+                &AutomodMessage::from(msg),
+            )
+        };
+        let Some(violation) = violation else {
+            return false;
+        };
+        let exempt = match context.cache.guild(guild_id) {
+            Some(guild) => matches!(
+                guild.member_permissions(context, msg.author.id).await,
+                Ok(permissions) if is_exempt(permissions)
+            ),
+            None => false,
+        };
+        if exempt {
+            return false;
+        }
+
+        debug!(rule = violation.rule.id, reason = %violation.reason, "automod rule triggered");
+        match self
+            .apply_violation(context, guild_id, msg, &violation)
+            .await
+        {
+            Ok(deleted) => deleted,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    rule = violation.rule.id,
+                    "could not apply automod action"
+                );
+                false
+            }
+        }
+    }
+
+    async fn apply_violation(
+        &self,
+        context: &Context,
+        guild_id: GuildId,
+        msg: &Message,
+        violation: &Violation,
+    ) -> Result<bool, HandlerError> {
+        let action = violation.rule.action;
+        let reason = format!("Automod rule #{}: {}", violation.rule.id, violation.reason);
+        let bot_id = context.cache.current_user_id();
+
+        if action.deletes() {
+            // The automod entry below already reports the deletion.
+            self.delete_unlogged(context, msg).await?;
+        }
+        match action {
+            RuleAction::Warn => {
+                record_case(
+                    context,
+                    guild_id,
+                    CaseAction::Warn,
+                    msg.author.id,
+                    bot_id,
+                    &reason,
+                )
+                .await?;
+            }
+            RuleAction::Timeout { minutes } => {
+                let until = Timestamp::from_unix_timestamp(
+                    Timestamp::now().unix_timestamp() + minutes * 60,
+                )
+                .map_err(|_| HandlerError::UnexpectedData)?;
+                let mut map = serde_json::Map::new();
+                map.insert(
+                    String::from("communication_disabled_until"),
+                    serde_json::to_value(until)?,
+                );
+                context
+                    .http
+                    .edit_member(guild_id.0, msg.author.id.0, &map, Some(&reason))
+                    .await?;
+                record_case(
+                    context,
+                    guild_id,
+                    CaseAction::Timeout,
+                    msg.author.id,
+                    bot_id,
+                    &reason,
+                )
+                .await?;
+            }
+            RuleAction::Delete | RuleAction::Log => {}
+        }
+        if matches!(action, RuleAction::Warn | RuleAction::Timeout { .. }) {
+            msg.channel_id
+                .say(context, format!("<@{}>, {}", msg.author.id, reason))
+                .await?;
+        }
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Automod")
+            .color(Color::ORANGE)
+            .description(format!("<@{}> in <#{}>", msg.author.id, msg.channel_id))
+            .field(
+                "Rule",
+                format!("#{} {}", violation.rule.id, violation.rule.kind.describe()),
+                false,
+            )
+            .field("Reason", &violation.reason, false)
+            .field("Action", action.describe(), true);
+        self.post_log(context, guild_id, embed).await;
+        Ok(action.deletes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: GuildId = GuildId(1);
+    const AUTHOR: UserId = UserId(2);
+
+    fn text(content: &str) -> AutomodMessage {
+        AutomodMessage {
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn automod(kinds: Vec<RuleKind>) -> Automod {
+        let mut automod = Automod::from_store(JsonStore::temporary("automod"));
+        for kind in kinds {
+            automod.add_rule(GUILD, kind, RuleAction::Delete).unwrap();
+        }
+        automod
+    }
+
+    /// The ids of the rules `msg` breaks.
+    fn broken(automod: &Automod, msg: &AutomodMessage, duplicates: usize) -> Vec<u64> {
+        automod
+            .evaluate(GUILD, msg, duplicates)
+            .iter()
+            .map(|v| v.rule.id)
+            .collect()
+    }
+
+    #[test]
+    fn checks_each_rule_kind() {
+        let automod = automod(vec![
+            RuleKind::parse("words", Some("heck, darn"), None).unwrap(),
+            RuleKind::parse("regex", Some(r"\d{4}-\d{4}"), None).unwrap(),
+            RuleKind::Invites,
+            RuleKind::parse("attachments", Some(".exe, bat"), None).unwrap(),
+        ]);
+
+        assert!(broken(&automod, &text("hello there"), 1).is_empty());
+        assert_eq!(broken(&automod, &text("oh HECK no"), 1), [1]);
+        // Words only match whole words.
+        assert!(broken(&automod, &text("checking"), 1).is_empty());
+        assert_eq!(broken(&automod, &text("call 1234-5678"), 1), [2]);
+        assert_eq!(
+            broken(&automod, &text("join discord.gg/abc, heck"), 1),
+            [1, 3]
+        );
+        let msg = AutomodMessage {
+            attachments: vec![String::from("notes.txt"), String::from("setup.EXE")],
+            ..Default::default()
+        };
+        let violations = automod.evaluate(GUILD, &msg, 1);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].reason, "attached blocked file setup.EXE");
+    }
+
+    #[test]
+    fn applies_thresholds() {
+        let automod = automod(vec![
+            RuleKind::parse("mentions", None, Some(2)).unwrap(),
+            RuleKind::parse("caps", None, Some(80)).unwrap(),
+            RuleKind::parse("duplicates", None, Some(2)).unwrap(),
+        ]);
+        let mentions = |mentions| AutomodMessage {
+            mentions,
+            ..Default::default()
+        };
+
+        assert!(broken(&automod, &mentions(2), 1).is_empty());
+        assert_eq!(broken(&automod, &mentions(3), 1), [1]);
+        assert!(broken(&automod, &text("THIS IS quite loud"), 1).is_empty());
+        assert_eq!(broken(&automod, &text("THIS IS VERY LOUD"), 1), [2]);
+        // Short messages are too short to shout.
+        assert!(broken(&automod, &text("OK LOL"), 1).is_empty());
+        assert!(broken(&automod, &text("again"), 2).is_empty());
+        assert_eq!(broken(&automod, &text("again"), 3), [3]);
+    }
+
+    #[test]
+    fn counts_recent_duplicates() {
+        let mut automod = automod(vec![RuleKind::Duplicates { max: 2 }]);
+        let check = |automod: &mut Automod, author, now, content| {
+            automod
+                .check(GUILD, author, now, &text(content))
+                .map(|v| v.rule.id)
+        };
+
+        assert_eq!(check(&mut automod, AUTHOR, 0, "spam"), None);
+        assert_eq!(check(&mut automod, AUTHOR, 1, " SPAM "), None);
+        assert_eq!(check(&mut automod, UserId(3), 2, "spam"), None);
+        assert_eq!(check(&mut automod, AUTHOR, 2, "spam"), Some(1));
+        // Old messages drop out of the window.
+        assert_eq!(
+            check(&mut automod, AUTHOR, 3 + DUPLICATE_WINDOW_SECS, "spam"),
+            None
+        );
+        // Messages without text, like lone attachments, never count.
+        for now in 100..105 {
+            assert_eq!(check(&mut automod, AUTHOR, now, ""), None);
+        }
+        // Other guilds have their own rules.
+        assert!(automod
+            .check(GuildId(9), AUTHOR, 200, &text("spam"))
+            .is_none());
+    }
+
+    #[test]
+    fn compiles_patterns_once() {
+        let mut automod = automod(vec![]);
+        let bad = RuleKind::parse("regex", Some("(unclosed"), None).unwrap();
+        assert!(matches!(
+            automod.add_rule(GUILD, bad, RuleAction::Log),
+            Err(HandlerError::InvalidArgument(_))
+        ));
+        assert!(automod.rules(GUILD).is_empty());
+
+        let rule = automod
+            .add_rule(
+                GUILD,
+                RuleKind::Regex {
+                    pattern: String::from("^buy"),
+                },
+                RuleAction::Log,
+            )
+            .unwrap();
+        assert_eq!(broken(&automod, &text("buy now"), 1), [rule.id]);
+
+        // Patterns are compiled again when the rules are loaded.
+        let mut store = JsonStore::temporary("automod");
+        store
+            .update(|guilds: &mut HashMap<GuildId, GuildRules>| {
+                guilds.insert(
+                    GUILD,
+                    GuildRules {
+                        next_id: 1,
+                        rules: automod.rules(GUILD).to_vec(),
+                    },
+                )
+            })
+            .unwrap();
+        let reloaded = Automod::from_store(store);
+        assert_eq!(broken(&reloaded, &text("buy now"), 1), [rule.id]);
+
+        automod.remove_rule(GUILD, rule.id).unwrap();
+        assert!(broken(&automod, &text("buy now"), 1).is_empty());
+        assert!(!automod.regexes[&GUILD].contains_key(&rule.id));
+    }
+
+    #[test]
+    fn moderators_are_exempt() {
+        assert!(is_exempt(Permissions::ADMINISTRATOR));
+        assert!(is_exempt(
+            Permissions::MANAGE_MESSAGES | Permissions::SEND_MESSAGES
+        ));
+        assert!(!is_exempt(
+            Permissions::SEND_MESSAGES | Permissions::ATTACH_FILES
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::gateway::Activity;
+use serenity::prelude::TypeMapKey;
+use tracing::*;
+
+use crate::config::ProviderConfig;
+use crate::storage::{JsonStore, StorageError};
+
+use super::analytics::Analytics;
+use super::shards::ShardManagerContainer;
+use super::Handler;
+
+pub const MIN_INTERVAL_SECS: u64 = 15;
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatusKind {
+    Playing,
+    Watching,
+    Listening,
+    Competing,
+}
+
+impl StatusKind {
+    pub fn parse(kind: &str) -> Option<StatusKind> {
+        match kind {
+            "playing" => Some(StatusKind::Playing),
+            "watching" => Some(StatusKind::Watching),
+            "listening" => Some(StatusKind::Listening),
+            "competing" => Some(StatusKind::Competing),
+            _ => None,
+        }
+    }
+
+    pub fn activity(self, text: &str) -> Activity {
+        match self {
+            StatusKind::Playing => Activity::playing(text),
+            StatusKind::Watching => Activity::watching(text),
+            StatusKind::Listening => Activity::listening(text),
+            StatusKind::Competing => Activity::competing(text),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusSettings {
+    pub kind: StatusKind,
+    /// Cycled through in order. Supports `{guilds}`, `{commands}` and
+    /// `{market}`.
+    pub templates: Vec<String>,
+    pub interval_secs: u64,
+}
+
+impl Default for StatusSettings {
+    fn default() -> Self {
+        StatusSettings {
+            kind: StatusKind::Watching,
+            templates: vec![
+                String::from("{guilds} servers"),
+                String::from("{commands} commands"),
+                String::from("the fear index at {market}"),
+            ],
+            interval_secs: DEFAULT_INTERVAL_SECS,
+        }
+    }
+}
+
+/// Values substituted into status templates.
+#[derive(Clone, Debug, Default)]
+pub struct StatusValues {
+    pub guilds: usize,
+    pub commands: u64,
+    pub market: Option<String>,
+}
+
+pub fn render_status(template: &str, values: &StatusValues) -> String {
+    template
+        .replace("{guilds}", &values.guilds.to_string())
+        .replace("{commands}", &values.commands.to_string())
+        .replace("{market}", values.market.as_deref().unwrap_or("n/a"))
+}
+
+/// The latest close of the configured market symbol.
+async fn market_summary(provider: &ProviderConfig) -> Option<String> {
+    match provider
+        .connector()
+        .get_latest_quotes(&provider.market_symbol, &provider.interval)
+        .await
+    {
+        Ok(quotes) => quotes
+            .last_quote()
+            .ok()
+            .map(|quote| format!("{:.2}", quote.close)),
+        Err(err) => {
+            debug!(?err, "could not fetch market summary");
+            None
+        }
+    }
+}
+
+/// Gathers the values `template` refers to. The market is only fetched
+/// when the template asks for it.
+pub async fn status_values(
+    context: &Context,
+    provider: &ProviderConfig,
+    template: &str,
+) -> StatusValues {
+    let commands = match context.data.read().await.get::<Analytics>() {
+        Some(analytics) => analytics.total(),
+        None => 0,
+    };
+    StatusValues {
+        guilds: context.cache.guilds().len(),
+        commands,
+        market: if template.contains("{market}") {
+            market_summary(provider).await
+        } else {
+            None
+        },
+    }
+}
+
+/// Shows `activity` on every shard this process runs, or only on the shard
+/// of `context` before the shard manager is known.
+pub async fn set_activity(context: &Context, activity: Option<Activity>) {
+    let manager = context
+        .data
+        .read()
+        .await
+        .get::<ShardManagerContainer>()
+        .cloned();
+    let Some(manager) = manager else {
+        context.shard.set_activity(activity);
+        return;
+    };
+    let runners = manager.lock().await.runners.clone();
+    for runner in runners.lock().await.values() {
+        runner.runner_tx.set_activity(activity.clone());
+    }
+}
+
+#[derive(Debug)]
+pub struct BotStatus {
+    settings: JsonStore<StatusSettings>,
+    position: usize,
+    running: bool,
+}
+
+impl TypeMapKey for BotStatus {
+    type Value = BotStatus;
+}
+
+impl Default for BotStatus {
+    fn default() -> Self {
+        BotStatus::new(DEFAULT_INTERVAL_SECS)
+    }
+}
+
+impl BotStatus {
+    /// Opens the stored rotation, which until one is set is the default
+    /// one changing every `interval_secs`.
+    pub fn new(interval_secs: u64) -> BotStatus {
+        BotStatus {
+            settings: JsonStore::open_or("bot_status", || StatusSettings {
+                interval_secs,
+                ..StatusSettings::default()
+            }),
+            position: 0,
+            running: false,
+        }
+    }
+
+    pub fn settings(&self) -> &StatusSettings {
+        self.settings.get()
+    }
+
+    /// Replaces the rotation. Callers show the first status themselves, so
+    /// the rotation carries on from the second.
+    pub fn set(&mut self, settings: StatusSettings) -> Result<(), StorageError> {
+        self.position = 1;
+        self.settings.update(|s| *s = settings)
+    }
+
+    /// The template to show next along with how long to show it.
+    fn advance(&mut self) -> (Option<(StatusKind, String)>, Duration) {
+        let settings = self.settings.get();
+        let interval = Duration::from_secs(settings.interval_secs.max(MIN_INTERVAL_SECS));
+        if settings.templates.is_empty() {
+            return (None, interval);
+        }
+        let template = settings.templates[self.position % settings.templates.len()].clone();
+        self.position = (self.position + 1) % settings.templates.len();
+        (Some((settings.kind, template)), interval)
+    }
+}
+
+impl Handler {
+    /// Spawns the task cycling through the configured statuses on every
+    /// shard. Does nothing if it's already running, e.g. when another shard
+    /// or a reconnect got here first.
+    pub async fn start_status_rotation(&self, context: &Context) {
+        {
+            let mut data = context.data.write().await;
+            let status = data
+                .entry::<BotStatus>()
+                .or_insert_with(|| BotStatus::new(self.config.limits.activity_interval_secs));
+            if status.running {
+                return;
+            }
+            status.running = true;
+        }
+
+        let context = context.clone();
+        let config = self.config.clone();
+        tokio::spawn(async move {
+            loop {
+                let (next, interval) = match context.data.write().await.get_mut::<BotStatus>() {
+                    Some(status) => status.advance(),
+                    None => return,
+                };
+                match next {
+                    Some((kind, template)) => {
+                        let values = status_values(&context, &config.provider, &template).await;
+                        let activity = kind.activity(&render_status(&template, &values));
+                        set_activity(&context, Some(activity)).await;
+                    }
+                    None => set_activity(&context, None).await,
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_status_values() {
+        let values = StatusValues {
+            guilds: 12,
+            commands: 3400,
+            market: Some(String::from("4321.50")),
+        };
+        assert_eq!(
+            render_status(
+                "{guilds} servers, {commands} commands, {guilds} again",
+                &values
+            ),
+            "12 servers, 3400 commands, 12 again"
+        );
+        assert_eq!(
+            render_status("the market at {market}", &values),
+            "the market at 4321.50"
+        );
+        assert_eq!(
+            render_status("the market at {market}", &StatusValues::default()),
+            "the market at n/a"
+        );
+        assert_eq!(render_status("{unknown}", &values), "{unknown}");
+    }
+}
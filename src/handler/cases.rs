@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::Timestamp;
+use serenity::prelude::TypeMapKey;
+use strum_macros::Display;
+use tracing::*;
+
+use crate::builders::cases::case_to_embed;
+use crate::storage::{JsonStore, StorageError};
+
+use super::HandlerError;
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaseAction {
+    Warn,
+    Kick,
+    Ban,
+    Unban,
+    Timeout,
+    #[strum(serialize = "Timeout removed")]
+    TimeoutRemoved,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Case {
+    pub id: u64,
+    pub action: CaseAction,
+    pub target: UserId,
+    pub moderator: UserId,
+    pub reason: String,
+    pub created_at: i64,
+    pub edited_at: Option<i64>,
+    pub log_message: Option<(ChannelId, MessageId)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildCases {
+    pub log_channel: Option<ChannelId>,
+    next_id: u64,
+    cases: Vec<Case>,
+}
+
+#[derive(Debug)]
+pub struct CaseLog(JsonStore<HashMap<GuildId, GuildCases>>);
+
+impl TypeMapKey for CaseLog {
+    type Value = CaseLog;
+}
+
+impl Default for CaseLog {
+    fn default() -> Self {
+        CaseLog(JsonStore::open("cases"))
+    }
+}
+
+impl CaseLog {
+    #[cfg(test)]
+    pub fn temporary() -> CaseLog {
+        CaseLog(JsonStore::temporary("cases"))
+    }
+
+    pub fn log_channel(&self, guild_id: GuildId) -> Option<ChannelId> {
+        self.0.get().get(&guild_id).and_then(|g| g.log_channel)
+    }
+
+    pub fn set_log_channel(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<(), StorageError> {
+        self.0
+            .update(|guilds| guilds.entry(guild_id).or_default().log_channel = channel_id)
+    }
+
+    pub fn case(&self, guild_id: GuildId, id: u64) -> Option<&Case> {
+        self.0
+            .get()
+            .get(&guild_id)
+            .and_then(|g| g.cases.iter().find(|c| c.id == id))
+    }
+
+    /// All cases against `target`, most recent first.
+    pub fn cases_for(&self, guild_id: GuildId, target: UserId) -> Vec<&Case> {
+        self.0
+            .get()
+            .get(&guild_id)
+            .map(|g| {
+                g.cases
+                    .iter()
+                    .rev()
+                    .filter(|c| c.target == target)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn open(
+        &mut self,
+        guild_id: GuildId,
+        action: CaseAction,
+        target: UserId,
+        moderator: UserId,
+        reason: &str,
+    ) -> Result<Case, StorageError> {
+        self.0.update(|guilds| {
+            let guild = guilds.entry(guild_id).or_default();
+            guild.next_id += 1;
+            let case = Case {
+                id: guild.next_id,
+                action,
+                target,
+                moderator,
+                reason: reason.to_string(),
+                created_at: Timestamp::now().unix_timestamp(),
+                edited_at: None,
+                log_message: None,
+            };
+            guild.cases.push(case.clone());
+            case
+        })
+    }
+
+    fn update_case<R>(
+        &mut self,
+        guild_id: GuildId,
+        id: u64,
+        f: impl FnOnce(&mut Case) -> R,
+    ) -> Result<Option<R>, StorageError> {
+        self.0.update(|guilds| {
+            guilds
+                .get_mut(&guild_id)
+                .and_then(|g| g.cases.iter_mut().find(|c| c.id == id))
+                .map(f)
+        })
+    }
+
+    pub fn edit_reason(
+        &mut self,
+        guild_id: GuildId,
+        id: u64,
+        reason: &str,
+    ) -> Result<Option<Case>, StorageError> {
+        self.update_case(guild_id, id, |case| {
+            case.reason = reason.to_string();
+            case.edited_at = Some(Timestamp::now().unix_timestamp());
+            case.clone()
+        })
+    }
+
+    pub fn delete(&mut self, guild_id: GuildId, id: u64) -> Result<Option<Case>, StorageError> {
+        self.0.update(|guilds| {
+            let guild = guilds.get_mut(&guild_id)?;
+            let index = guild.cases.iter().position(|c| c.id == id)?;
+            Some(guild.cases.remove(index))
+        })
+    }
+}
+
+/// Stores a new case and posts it to the guild's mod-log channel, if one is
+/// configured.
+pub async fn record_case(
+    context: &Context,
+    guild_id: GuildId,
+    action: CaseAction,
+    target: UserId,
+    moderator: UserId,
+    reason: &str,
+) -> Result<Case, HandlerError> {
+    let (mut case, log_channel) = {
+        let mut data = context.data.write().await;
+        let log = data.entry::<CaseLog>().or_insert_with(CaseLog::default);
+        let case = log.open(guild_id, action, target, moderator, reason)?;
+        (case, log.log_channel(guild_id))
+    };
+
+    if let Some(channel_id) = log_channel {
+        let embed = case_to_embed(&case);
+        match channel_id
+            .send_message(context, |m| m.set_embed(embed))
+            .await
+        {
+            Ok(message) => {
+                case.log_message = Some((channel_id, message.id));
+                let log_message = case.log_message;
+                let mut data = context.data.write().await;
+                if let Some(log) = data.get_mut::<CaseLog>() {
+                    log.update_case(guild_id, case.id, |c| c.log_message = log_message)?;
+                }
+            }
+            Err(err) => warn!(?err, ?channel_id, "could not post case to mod-log channel"),
+        }
+    }
+    Ok(case)
+}
+
+/// Mirrors an edited case onto its mod-log entry.
+pub async fn refresh_log_entry(context: &Context, case: &Case) {
+    if let Some((channel_id, message_id)) = case.log_message {
+        let embed = case_to_embed(case);
+        if let Err(err) = channel_id
+            .edit_message(context, message_id, |m| m.set_embed(embed))
+            .await
+        {
+            warn!(?err, case = case.id, "could not update mod-log entry");
+        }
+    }
+}
+
+pub async fn remove_log_entry(context: &Context, case: &Case) {
+    if let Some((channel_id, message_id)) = case.log_message {
+        if let Err(err) = channel_id.delete_message(context, message_id).await {
+            warn!(?err, case = case.id, "could not delete mod-log entry");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{fixtures, MockDiscord};
+
+    const GUILD: GuildId = GuildId(fixtures::GUILD_ID);
+    const OTHER_GUILD: GuildId = GuildId(2);
+    const TARGET: UserId = UserId(fixtures::OTHER_ID);
+    const MODERATOR: UserId = UserId(fixtures::INVOKER_ID);
+
+    #[test]
+    fn numbers_cases_per_guild() {
+        let mut log = CaseLog::temporary();
+        let open = |log: &mut CaseLog, guild_id, target| {
+            log.open(guild_id, CaseAction::Warn, target, MODERATOR, "spam")
+                .unwrap()
+                .id
+        };
+        assert_eq!(open(&mut log, GUILD, TARGET), 1);
+        assert_eq!(open(&mut log, GUILD, UserId(5)), 2);
+        assert_eq!(open(&mut log, OTHER_GUILD, TARGET), 1);
+
+        // Deleted numbers aren't handed out again.
+        assert!(log.delete(GUILD, 2).unwrap().is_some());
+        assert!(log.delete(GUILD, 2).unwrap().is_none());
+        assert_eq!(open(&mut log, GUILD, TARGET), 3);
+
+        let ids: Vec<u64> = log.cases_for(GUILD, TARGET).iter().map(|c| c.id).collect();
+        assert_eq!(ids, [3, 1]);
+
+        let edited = log.edit_reason(GUILD, 1, "flooding").unwrap().unwrap();
+        assert_eq!(edited.reason, "flooding");
+        assert!(edited.edited_at.is_some());
+        assert!(log.edit_reason(GUILD, 9, "flooding").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn records_cases_in_the_mod_log() {
+        let discord = MockDiscord::start().await;
+        let mut posted = fixtures::message(2000, "");
+        posted["channel_id"] = json!("700");
+        discord.route("POST", "/channels/700/messages", posted);
+        let context = discord.context();
+        let mut log = CaseLog::temporary();
+        log.set_log_channel(GUILD, Some(ChannelId(700))).unwrap();
+        context.data.write().await.insert::<CaseLog>(log);
+
+        let case = record_case(&context, GUILD, CaseAction::Ban, TARGET, MODERATOR, "raid")
+            .await
+            .unwrap();
+        assert_eq!(case.id, 1);
+        assert_eq!(case.log_message, Some((ChannelId(700), MessageId(2000))));
+
+        let requests = discord.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].body["embeds"][0]["title"], "Case #1 | Ban");
+        let data = context.data.read().await;
+        let stored = data.get::<CaseLog>().unwrap().case(GUILD, 1).unwrap();
+        assert_eq!(stored.log_message, case.log_message);
+    }
+}
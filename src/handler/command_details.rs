@@ -1,73 +1,178 @@
+use std::collections::HashMap;
+
 use serenity::client::Context;
 
 use serenity::model::application::interaction::application_command::{
     ApplicationCommandInteraction, CommandDataOption,
 };
+use serenity::model::channel::{Channel, ChannelType, GuildChannel};
 use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+
+use super::HandlerError;
+use crate::util::LocalizedString;
 
-pub async fn parse_command_members(
-    option: &CommandDataOption,
+/// Members of the command's guild whose id, name or nickname matches
+/// `search`, along with the members the command mentions.
+pub async fn find_members(
     context: &Context,
     cmd: &ApplicationCommandInteraction,
-) -> Vec<Member> {
-    let mut selected_users = Vec::new();
+    search: &str,
+) -> Result<Vec<Member>, HandlerError> {
+    let guild_id = cmd.guild_id.ok_or(HandlerError::NotGuild)?;
+    let search = search.to_lowercase();
+    let matches = |name: &str| name.to_lowercase().contains(&search);
+    Ok(fetch_members(context, guild_id)
+        .await?
+        .into_iter()
+        .filter(|m| {
+            m.user.id.to_string() == search
+                || matches(&m.user.name)
+                || m.nick.as_deref().is_some_and(matches)
+                || cmd.data.resolved.members.contains_key(&m.user.id)
+        })
+        .collect())
+}
 
-    let mut string_value: String = String::from("");
-    if let Some(option_value) = option.value.clone() {
-        string_value = option_value.as_str().unwrap_or("").to_lowercase()
+/// A guild channel of any kind as a [`GuildChannel`]. Categories have a type
+/// of their own but the same fields as far as permissions go.
+pub fn as_guild_channel(channel: Channel) -> Option<GuildChannel> {
+    match channel {
+        Channel::Guild(channel) => Some(channel),
+        Channel::Category(category) => serde_json::to_value(category)
+            .and_then(serde_json::from_value)
+            .ok(),
+        _ => None,
     }
+}
 
-    let mut guild_members = vec![];
-    if let Some(guild_id) = cmd.guild_id {
-        guild_members = match context
-            .http
-            .get_guild_members(u64::from(guild_id), None, None)
-            .await
-        {
-            Ok(members) => members,
-            Err(..) => Vec::new(),
-        };
+/// What `member` may do in `channel`, from the cached guild if there is one.
+pub async fn permissions_in(
+    context: &Context,
+    channel: &GuildChannel,
+    member: &Member,
+) -> Result<Permissions, HandlerError> {
+    if let Some(guild) = context.cache.guild(channel.guild_id) {
+        return Ok(guild.user_permissions_in(channel, member)?);
+    }
+    let guild = context.http.get_guild(channel.guild_id.0).await?;
+    Ok(guild.user_permissions_in(channel, member)?)
+}
+
+/// What `member` may do in `channel_id`, like the permissions Discord sends
+/// with an interaction. Threads go by their parent channel.
+pub async fn channel_permissions(
+    context: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    member: &Member,
+) -> Result<Permissions, HandlerError> {
+    let guild_channel = |channel: Channel| {
+        as_guild_channel(channel)
+            .filter(|c| c.guild_id == guild_id)
+            .ok_or(HandlerError::NotGuild)
+    };
+    let mut channel = guild_channel(channel_id.to_channel(context).await?)?;
+    let is_thread = matches!(
+        channel.kind,
+        ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread
+    );
+    if let Some(parent) = channel.parent_id.filter(|_| is_thread) {
+        channel = guild_channel(parent.to_channel(context).await?)?;
+    }
+    permissions_in(context, &channel, member).await
+}
+
+/// Every member of the guild, from the cache when it holds all of them and
+/// paged in over REST otherwise.
+pub async fn fetch_members(
+    context: &Context,
+    guild_id: GuildId,
+) -> Result<Vec<Member>, HandlerError> {
+    const PAGE: u64 = 1000;
+    // The cache of a large guild only holds the members that showed up
+    // since the bot connected.
+    let cached = context.cache.guild_field(guild_id, |g| {
+        (g.members.len() as u64 >= g.member_count).then(|| g.members.values().cloned().collect())
+    });
+    if let Some(Some(members)) = cached {
+        return Ok(members);
     }
 
-    for i in guild_members {
-        let nickname = i.nick.clone().unwrap_or(String::from(""));
-        if i.user.id.to_string() == string_value {
-            selected_users.push(i)
-        } else if i.user.name.to_lowercase().contains(&string_value) {
-            selected_users.push(i)
-        } else if nickname.to_lowercase().contains(&string_value) {
-            selected_users.push(i)
-        } else {
-            match &cmd
-                .data
-                .resolved
-                .members
-                .iter()
-                .find(|&x| x.0 == &i.user.id)
-            {
-                Some(_) => selected_users.push(i),
-                _ => (),
-            }
+    let mut members = vec![];
+    let mut after = None;
+    loop {
+        let page = context
+            .http
+            .get_guild_members(guild_id.0, Some(PAGE), after)
+            .await?;
+        let done = (page.len() as u64) < PAGE;
+        after = page.last().map(|m| m.user.id.0);
+        members.extend(page);
+        if done {
+            return Ok(members);
         }
     }
+}
 
-    selected_users
+pub fn role_member_counts(members: &[Member]) -> HashMap<RoleId, usize> {
+    let mut counts = HashMap::new();
+    for role in members.iter().flat_map(|m| &m.roles) {
+        *counts.entry(*role).or_insert(0) += 1;
+    }
+    counts
 }
 
-pub fn parse_command_array(
-    option: &CommandDataOption,
-    _context: &Context,
-    _cmd: &ApplicationCommandInteraction,
-) -> Vec<String> {
-    let mut items = Vec::new();
+/// User ids from a list of mentions or raw ids separated by spaces or commas.
+pub fn parse_user_ids(text: &str) -> Vec<UserId> {
+    let mut ids: Vec<UserId> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|token| {
+            token
+                .trim_start_matches("<@")
+                .trim_start_matches('!')
+                .trim_end_matches('>')
+                .parse()
+                .ok()
+                .map(UserId)
+        })
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
 
-    if let Some(option_value) = option.value.clone() {
-        items = option_value
-            .as_str()
-            .unwrap_or("")
-            .split(' ')
-            .map(|s| s.to_string())
-            .collect();
+pub fn require_permissions(
+    cmd: &ApplicationCommandInteraction,
+    permissions: Permissions,
+) -> Result<(), HandlerError> {
+    match cmd.member.as_ref().and_then(|m| m.permissions) {
+        Some(granted) if granted.administrator() || granted.contains(permissions) => Ok(()),
+        _ => Err(HandlerError::MissingPermissions),
     }
-    items
+}
+
+/// Whether `user_id` owns the bot's application or is on its team.
+pub async fn is_bot_owner(context: &Context, user_id: UserId) -> Result<bool, HandlerError> {
+    let info = context.http.get_current_application_info().await?;
+    Ok(info.owner.id == user_id
+        || info
+            .team
+            .is_some_and(|team| team.members.iter().any(|m| m.user.id == user_id)))
+}
+
+pub async fn require_bot_owner(context: &Context, user_id: UserId) -> Result<(), HandlerError> {
+    if is_bot_owner(context, user_id).await? {
+        Ok(())
+    } else {
+        Err(HandlerError::NotOwner)
+    }
+}
+
+pub fn find_option<'a>(
+    options: &'a [CommandDataOption],
+    name: &LocalizedString,
+) -> Option<&'a CommandDataOption> {
+    options.iter().find(|o| name.any_eq(&o.name))
 }
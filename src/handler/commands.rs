@@ -1,18 +1,21 @@
 use std::collections::HashMap;
 
 use serenity::builder::CreateApplicationCommand;
-use serenity::futures::{future::try_join_all, stream, StreamExt, TryStreamExt};
+use serenity::futures::future::try_join_all;
 use serenity::{
-    model::prelude::{command::Command, Ready},
+    model::prelude::{command::Command, GuildId},
     prelude::Context,
 };
 use tracing::*;
 
-use crate::commands::{guild::GuildCommands, CommandsEnum};
+use crate::commands::{global::GlobalCommands, guild::GuildCommands, CommandsEnum};
 
+use super::shards::Shards;
 use super::{Handler, HandlerError};
 
 impl Handler {
+    /// Remembers which command each id is. Guild commands have an id per
+    /// guild, so the ids are added to the ones already known.
     async fn save_command_ids<T>(
         &self,
         context: &Context,
@@ -29,53 +32,106 @@ impl Handler {
                 warn!(?prev, "overwrote previous command with same id");
             }
         }
-        context.data.write().await.insert::<T>(cmd_map);
+        context
+            .data
+            .write()
+            .await
+            .entry::<T>()
+            .or_insert_with(HashMap::new)
+            .extend(cmd_map);
         Ok(())
     }
 
+    /// Registers the guild commands in the guilds no other shard registered
+    /// them in yet.
     pub async fn setup_guild_commands(
         &self,
         context: &Context,
-        ready: Ready,
+        guilds: impl IntoIterator<Item = GuildId>,
     ) -> Result<(), HandlerError> {
-        if !ready.guilds.is_empty() {
-            let guild_commands_holder = try_join_all(ready.guilds.iter().map(|g| {
-                g.id.set_application_commands(&context, |create| {
-                    let commands: Vec<CreateApplicationCommand> =
-                        GuildCommands::application_commands().collect();
-                    create.set_application_commands(commands);
-                    create
-                })
-            }))
-            .await;
+        let guilds = match context.data.write().await.get_mut::<Shards>() {
+            Some(shards) => shards.claim_guilds(guilds),
+            None => guilds.into_iter().collect(),
+        };
+        if guilds.is_empty() {
+            return Ok(());
+        }
 
-            match guild_commands_holder {
-                Ok(guild_commands) => {
-                    if let Some(first) = guild_commands.first() {
-                        info!(commands = ?first.iter().map(|c| &c.name).collect::<Vec<_>>(),"registered guild commands");
-                    } else {
-                        error!("guilds list is not empty, but no guild commands were registered");
-                        return Err(HandlerError::CommandSetup);
-                    }
-                    if let Err(err) = stream::iter(guild_commands.into_iter())
-                        .map(Ok)
-                        .try_for_each(|cmds| async {
-                            self.save_command_ids::<GuildCommands>(context, cmds.into_iter())
-                                .await
-                        })
-                        .await
-                    {
-                        error!(?err, "error saving guild application command data");
-                        return Err(HandlerError::CommandSetup);
-                    }
-                }
-                Err(err) => {
-                    error!(?err, "error registering guild application commands");
-                    return Err(HandlerError::CommandSetup);
-                }
+        let res = self.register_guild_commands(context, &guilds).await;
+        if res.is_err() {
+            if let Some(shards) = context.data.write().await.get_mut::<Shards>() {
+                shards.release_guilds(&guilds);
             }
         }
+        res
+    }
 
-        Ok(())
+    async fn register_guild_commands(
+        &self,
+        context: &Context,
+        guilds: &[GuildId],
+    ) -> Result<(), HandlerError> {
+        let guild_commands = try_join_all(guilds.iter().map(|guild| {
+            guild.set_application_commands(&context, |create| {
+                let commands: Vec<CreateApplicationCommand> =
+                    GuildCommands::application_commands(&self.config).collect();
+                create.set_application_commands(commands);
+                create
+            })
+        }))
+        .await
+        .map_err(|err| {
+            error!(?err, "error registering guild application commands");
+            HandlerError::CommandSetup
+        })?;
+
+        if let Some(first) = guild_commands.first() {
+            info!(commands = ?first.iter().map(|c| &c.name).collect::<Vec<_>>(), guilds = guilds.len(), "registered guild commands");
+        }
+        self.save_command_ids::<GuildCommands>(context, guild_commands.into_iter().flatten())
+            .await
+            .map_err(|err| {
+                error!(?err, "error saving guild application command data");
+                HandlerError::CommandSetup
+            })
+    }
+
+    /// Registers the global commands unless another shard already did.
+    pub async fn setup_global_commands(&self, context: &Context) -> Result<(), HandlerError> {
+        if let Some(shards) = context.data.write().await.get_mut::<Shards>() {
+            if !shards.claim_global_commands() {
+                return Ok(());
+            }
+        }
+
+        let res = self.register_global_commands(context).await;
+        if res.is_err() {
+            if let Some(shards) = context.data.write().await.get_mut::<Shards>() {
+                shards.release_global_commands();
+            }
+        }
+        res
+    }
+
+    async fn register_global_commands(&self, context: &Context) -> Result<(), HandlerError> {
+        let commands = Command::set_global_application_commands(&context, |create| {
+            let commands: Vec<CreateApplicationCommand> =
+                GlobalCommands::application_commands(&self.config).collect();
+            create.set_application_commands(commands);
+            create
+        })
+        .await
+        .map_err(|err| {
+            error!(?err, "error registering global application commands");
+            HandlerError::CommandSetup
+        })?;
+
+        info!(commands = ?commands.iter().map(|c| &c.name).collect::<Vec<_>>(), "registered global commands");
+        self.save_command_ids::<GlobalCommands>(context, commands.into_iter())
+            .await
+            .map_err(|err| {
+                error!(?err, "error saving global application command data");
+                HandlerError::CommandSetup
+            })
     }
 }
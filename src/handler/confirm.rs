@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::utils::Color;
+
+use super::invocation::Invocation;
+use super::response::CommandResponse;
+use super::HandlerError;
+
+const CONFIRM_ID: &str = "confirm";
+const CANCEL_ID: &str = "cancel";
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub enum Confirmation {
+    Confirmed(Arc<MessageComponentInteraction>),
+    Cancelled(Arc<MessageComponentInteraction>),
+    TimedOut,
+}
+
+/// Answers the command with an ephemeral prompt and waits for the invoker to
+/// press one of its buttons.
+pub async fn ask_confirmation(
+    cmd: &Invocation,
+    context: &Context,
+    prompt: CreateEmbed,
+) -> Result<Confirmation, HandlerError> {
+    let mut buttons = CreateComponents::default();
+    buttons.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id(CONFIRM_ID)
+                .label("Confirm")
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|b| {
+            b.custom_id(CANCEL_ID)
+                .label("Cancel")
+                .style(ButtonStyle::Secondary)
+        })
+    });
+    cmd.reply(
+        context,
+        CommandResponse::embed(prompt)
+            .ephemeral(true)
+            .components(buttons),
+    )
+    .await?;
+
+    let message = cmd.reply_message(context).await?;
+    let pressed = message
+        .await_component_interaction(&context.shard)
+        .author_id(cmd.user.id)
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+
+    Ok(match pressed {
+        Some(press) if press.data.custom_id == CONFIRM_ID => Confirmation::Confirmed(press),
+        Some(press) => Confirmation::Cancelled(press),
+        None => Confirmation::TimedOut,
+    })
+}
+
+impl Confirmation {
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, Confirmation::Confirmed(_))
+    }
+
+    /// Replaces the prompt with `embed` and removes its buttons. The command
+    /// has been answered afterwards.
+    pub async fn resolve(
+        &self,
+        cmd: &Invocation,
+        context: &Context,
+        embed: CreateEmbed,
+    ) -> Result<CommandResponse, HandlerError> {
+        match self {
+            Confirmation::Confirmed(press) | Confirmation::Cancelled(press) => {
+                press
+                    .create_interaction_response(context, |res| {
+                        res.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|d| {
+                                d.set_embeds(vec![embed]).components(|c| c)
+                            })
+                    })
+                    .await?
+            }
+            Confirmation::TimedOut => {
+                cmd.edit_reply(context, CommandResponse::embed(embed))
+                    .await?;
+            }
+        }
+        Ok(CommandResponse::handled())
+    }
+
+    /// The embed shown when the action was not confirmed.
+    pub fn aborted_embed(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::default();
+        embed.color(Color::DARK_GREY).description(match self {
+            Confirmation::TimedOut => "No answer, nothing was done.",
+            _ => "Cancelled, nothing was done.",
+        });
+        embed
+    }
+}
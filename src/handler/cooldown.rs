@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::prelude::TypeMapKey;
+
+/// A bucket holding `capacity` uses, refilled over `period`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BucketConfig {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl BucketConfig {
+    pub const fn new(capacity: u32, period_secs: u64) -> BucketConfig {
+        BucketConfig {
+            capacity,
+            period: Duration::from_secs(period_secs),
+        }
+    }
+
+    fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CommandLimit {
+    pub per_user: Option<BucketConfig>,
+    pub per_guild: Option<BucketConfig>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CooldownConfig {
+    pub fallback: CommandLimit,
+    pub commands: HashMap<String, CommandLimit>,
+    pub guilds: HashMap<(GuildId, String), CommandLimit>,
+    pub roles: HashMap<(RoleId, String), BucketConfig>,
+    pub users: HashMap<(UserId, String), BucketConfig>,
+}
+
+impl Default for CooldownConfig {
+    fn default() -> Self {
+        let mut commands = HashMap::new();
+        commands.insert(
+            String::from("ml"),
+            CommandLimit {
+                per_user: Some(BucketConfig::new(1, 30)),
+                per_guild: Some(BucketConfig::new(3, 60)),
+            },
+        );
+        commands.insert(
+            String::from("fear"),
+            CommandLimit {
+                per_user: Some(BucketConfig::new(3, 30)),
+                per_guild: Some(BucketConfig::new(10, 60)),
+            },
+        );
+
+        CooldownConfig {
+            fallback: CommandLimit {
+                per_user: Some(BucketConfig::new(5, 10)),
+                per_guild: None,
+            },
+            commands,
+            guilds: HashMap::new(),
+            roles: HashMap::new(),
+            users: HashMap::new(),
+        }
+    }
+}
+
+impl CooldownConfig {
+    fn command_limit(&self, command: &str, guild_id: Option<GuildId>) -> CommandLimit {
+        guild_id
+            .and_then(|g| self.guilds.get(&(g, command.to_string())))
+            .or_else(|| self.commands.get(command))
+            .copied()
+            .unwrap_or(self.fallback)
+    }
+
+    /// Resolves the per-user bucket: user overrides win, then the most generous
+    /// role override, then the guild/command limit.
+    fn user_bucket(
+        &self,
+        command: &str,
+        user_id: UserId,
+        roles: &[RoleId],
+        limit: &CommandLimit,
+    ) -> Option<BucketConfig> {
+        if let Some(bucket) = self.users.get(&(user_id, command.to_string())) {
+            return Some(*bucket);
+        }
+        let role_bucket = roles
+            .iter()
+            .filter_map(|role| self.roles.get(&(*role, command.to_string())))
+            .max_by(|a, b| a.refill_rate().total_cmp(&b.refill_rate()));
+        role_bucket.copied().or(limit.per_user)
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum BucketKey {
+    User(UserId, Option<GuildId>, String),
+    Guild(GuildId, String),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(config: &BucketConfig, now: Instant) -> Bucket {
+        Bucket {
+            tokens: config.capacity as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, config: &BucketConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * config.refill_rate()).min(config.capacity as f64);
+        self.updated = now;
+    }
+
+    /// A bucket that never refills, from a zero capacity or period, makes
+    /// the caller wait forever rather than panic.
+    fn wait_time(&self, config: &BucketConfig) -> Option<Duration> {
+        if self.tokens >= 1.0 {
+            None
+        } else {
+            let secs = (1.0 - self.tokens) / config.refill_rate();
+            Some(Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX))
+        }
+    }
+}
+
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+#[derive(Debug, Default)]
+pub struct Cooldowns {
+    pub config: CooldownConfig,
+    buckets: HashMap<BucketKey, Bucket>,
+}
+
+impl TypeMapKey for Cooldowns {
+    type Value = Cooldowns;
+}
+
+impl Cooldowns {
+    pub fn new(config: CooldownConfig) -> Cooldowns {
+        Cooldowns {
+            config,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token from every bucket that applies to the interaction, or
+    /// returns how long the caller has to wait. Nothing is consumed when any
+    /// bucket is empty.
+    pub fn check(&mut self, cmd: &ApplicationCommandInteraction) -> Result<(), Duration> {
+        let roles = cmd
+            .member
+            .as_ref()
+            .map(|m| m.roles.clone())
+            .unwrap_or_default();
+        self.check_at(
+            &cmd.data.name,
+            cmd.user.id,
+            cmd.guild_id,
+            &roles,
+            Instant::now(),
+        )
+    }
+
+    pub fn check_at(
+        &mut self,
+        command: &str,
+        user_id: UserId,
+        guild_id: Option<GuildId>,
+        roles: &[RoleId],
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let limit = self.config.command_limit(command, guild_id);
+
+        let mut applicable = vec![];
+        if let Some(bucket) = self.config.user_bucket(command, user_id, roles, &limit) {
+            applicable.push((
+                BucketKey::User(user_id, guild_id, command.to_string()),
+                bucket,
+            ));
+        }
+        if let (Some(guild_id), Some(bucket)) = (guild_id, limit.per_guild) {
+            applicable.push((BucketKey::Guild(guild_id, command.to_string()), bucket));
+        }
+
+        let mut wait = None;
+        for (key, config) in &applicable {
+            let bucket = self
+                .buckets
+                .entry(key.clone())
+                .or_insert_with(|| Bucket::full(config, now));
+            bucket.refill(config, now);
+            if let Some(w) = bucket.wait_time(config) {
+                wait = Some(wait.map_or(w, |prev: Duration| prev.max(w)));
+            }
+        }
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+
+        if self.buckets.len() > MAX_TRACKED_BUCKETS {
+            self.prune(now);
+        }
+
+        for (key, _) in &applicable {
+            if let Some(bucket) = self.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops buckets that have been idle long enough to be full again.
+    fn prune(&mut self, now: Instant) {
+        let max_period = self
+            .config
+            .commands
+            .values()
+            .chain(self.config.guilds.values())
+            .chain(std::iter::once(&self.config.fallback))
+            .flat_map(|limit| [limit.per_user, limit.per_guild])
+            .flatten()
+            .chain(self.config.roles.values().copied())
+            .chain(self.config.users.values().copied())
+            .map(|bucket| bucket.period)
+            .max()
+            .unwrap_or_default();
+        self.buckets
+            .retain(|_, bucket| now.saturating_duration_since(bucket.updated) < max_period);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMAND: &str = "ml";
+    const USER: UserId = UserId(1);
+    const GUILD: GuildId = GuildId(2);
+    const ROLE: RoleId = RoleId(3);
+
+    fn cooldowns(limit: CommandLimit) -> Cooldowns {
+        Cooldowns::new(CooldownConfig {
+            fallback: CommandLimit::default(),
+            commands: HashMap::from([(String::from(COMMAND), limit)]),
+            guilds: HashMap::new(),
+            roles: HashMap::new(),
+            users: HashMap::new(),
+        })
+    }
+
+    fn per_user(capacity: u32, period_secs: u64) -> CommandLimit {
+        CommandLimit {
+            per_user: Some(BucketConfig::new(capacity, period_secs)),
+            per_guild: None,
+        }
+    }
+
+    /// How many times `user` can run the command at `now` before the
+    /// cooldown kicks in.
+    fn uses(cooldowns: &mut Cooldowns, user: UserId, roles: &[RoleId], now: Instant) -> u32 {
+        let mut uses = 0;
+        while cooldowns
+            .check_at(COMMAND, user, Some(GUILD), roles, now)
+            .is_ok()
+        {
+            uses += 1;
+            assert!(uses < 100, "the bucket never ran out");
+        }
+        uses
+    }
+
+    #[test]
+    fn exhausts_and_waits_for_a_refill() {
+        let mut cooldowns = cooldowns(per_user(2, 10));
+        let start = Instant::now();
+        assert_eq!(uses(&mut cooldowns, USER, &[], start), 2);
+
+        let wait = cooldowns
+            .check_at(COMMAND, USER, Some(GUILD), &[], start)
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_secs(5));
+        let wait = cooldowns
+            .check_at(
+                COMMAND,
+                USER,
+                Some(GUILD),
+                &[],
+                start + Duration::from_secs(4),
+            )
+            .unwrap_err();
+        assert_eq!(wait.as_secs_f64().round(), 1.0);
+
+        // One token came back after 5s, both after 10s.
+        assert_eq!(
+            uses(&mut cooldowns, USER, &[], start + Duration::from_secs(5)),
+            1
+        );
+        assert_eq!(
+            uses(&mut cooldowns, USER, &[], start + Duration::from_secs(20)),
+            2
+        );
+        // Other users have buckets of their own.
+        assert_eq!(uses(&mut cooldowns, UserId(9), &[], start), 2);
+    }
+
+    #[test]
+    fn guild_buckets_are_shared() {
+        let mut cooldowns = cooldowns(CommandLimit {
+            per_user: Some(BucketConfig::new(2, 10)),
+            per_guild: Some(BucketConfig::new(3, 10)),
+        });
+        let now = Instant::now();
+        assert_eq!(uses(&mut cooldowns, USER, &[], now), 2);
+        assert_eq!(uses(&mut cooldowns, UserId(9), &[], now), 1);
+        // The guild bucket refills for whoever comes next.
+        assert_eq!(
+            uses(
+                &mut cooldowns,
+                UserId(10),
+                &[],
+                now + Duration::from_millis(3400)
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let mut cooldowns = cooldowns(per_user(1, 10));
+        cooldowns
+            .config
+            .guilds
+            .insert((GUILD, String::from(COMMAND)), per_user(2, 10));
+        let now = Instant::now();
+        assert_eq!(uses(&mut cooldowns, USER, &[], now), 2);
+
+        cooldowns
+            .config
+            .roles
+            .insert((ROLE, String::from(COMMAND)), BucketConfig::new(3, 10));
+        cooldowns
+            .config
+            .roles
+            .insert((RoleId(4), String::from(COMMAND)), BucketConfig::new(4, 10));
+        assert_eq!(uses(&mut cooldowns, UserId(10), &[ROLE], now), 3);
+        // The most generous role wins.
+        assert_eq!(uses(&mut cooldowns, UserId(11), &[ROLE, RoleId(4)], now), 4);
+
+        cooldowns.config.users.insert(
+            (UserId(12), String::from(COMMAND)),
+            BucketConfig::new(5, 10),
+        );
+        assert_eq!(uses(&mut cooldowns, UserId(12), &[RoleId(4)], now), 5);
+
+        // Other guilds still get the command's own limit.
+        assert!(cooldowns
+            .check_at(COMMAND, UserId(13), Some(GuildId(7)), &[], now)
+            .is_ok());
+        assert!(cooldowns
+            .check_at(COMMAND, UserId(13), Some(GuildId(7)), &[], now)
+            .is_err());
+    }
+
+    #[test]
+    fn empty_buckets_wait_forever() {
+        let mut cooldowns = cooldowns(per_user(0, 10));
+        let wait = cooldowns
+            .check_at(COMMAND, USER, None, &[], Instant::now())
+            .unwrap_err();
+        assert_eq!(wait, Duration::MAX);
+    }
+}
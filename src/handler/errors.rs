@@ -0,0 +1,200 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{self, Write};
+
+use rand::Rng;
+use serenity::model::application::command::CommandOptionType;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use tracing::*;
+
+use super::invocation::Invocation;
+use super::{Handler, HandlerError};
+use crate::util::truncate;
+
+const MAX_REPORT_LEN: usize = 4000;
+
+/// Identifies one failed command, so a user's report can be matched with
+/// the logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorId(u32);
+
+impl ErrorId {
+    pub fn new() -> ErrorId {
+        ErrorId(rand::thread_rng().gen())
+    }
+}
+
+impl Default for ErrorId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ErrorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+/// The error and everything that caused it, outermost first.
+pub fn error_chain(err: &HandlerError) -> String {
+    let mut chain = match err {
+        HandlerError::Panic(message) => format!("{}: panicked at '{}'", err, message),
+        _ => err.to_string(),
+    };
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let _ = write!(chain, ": {}", cause);
+        source = cause.source();
+    }
+    chain
+}
+
+/// What the invoker is told. Internal errors come with their id, since
+/// there's nothing the invoker can do about them but report them.
+pub fn user_message(err: &HandlerError, id: ErrorId) -> String {
+    if err.is_internal() {
+        format!("{} (error id ``{}``)", err, id)
+    } else {
+        err.to_string()
+    }
+}
+
+/// The message a panic was started with, when it has one.
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+impl Handler {
+    /// Posts an internal error to the configured error channel, if any.
+    pub async fn report_error(
+        &self,
+        context: &Context,
+        cmd: &Invocation,
+        err: &HandlerError,
+        id: ErrorId,
+    ) {
+        let Some(channel) = self.config.error_channel else {
+            return;
+        };
+        if !err.is_internal() {
+            return;
+        }
+
+        let command = match cmd.data.options.first() {
+            Some(sub) if sub.kind == CommandOptionType::SubCommand => {
+                format!("/{} {}", cmd.data.name, sub.name)
+            }
+            _ => format!("/{}", cmd.data.name),
+        };
+        let res = channel
+            .send_message(context, |msg| {
+                msg.embed(|embed| {
+                    embed
+                        .title(format!("Error {}", id))
+                        .color(Color::RED)
+                        .description(format!(
+                            "```\n{}\n```",
+                            truncate(&error_chain(err), MAX_REPORT_LEN)
+                        ))
+                        .field("Command", command, true)
+                        .field("User", format!("<@{}>", cmd.user.id), true)
+                        .field(
+                            "Server",
+                            cmd.guild_id
+                                .map_or_else(|| String::from("DM"), |guild| guild.to_string()),
+                            true,
+                        )
+                })
+            })
+            .await;
+        if let Err(err) = res {
+            warn!(?err, %id, "could not report error");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use serenity::model::id::ChannelId;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::storage::StorageError;
+    use crate::testing::{fixtures, MockDiscord};
+
+    #[test]
+    fn internal_errors_carry_their_id() {
+        let id = ErrorId(0xbeef);
+        assert_eq!(
+            user_message(&HandlerError::NotGuild, id),
+            "Command can only be used in a server"
+        );
+        assert_eq!(
+            user_message(&HandlerError::Panic(String::from("oops")), id),
+            "Something went wrong while handling the command (error id ``0000beef``)"
+        );
+    }
+
+    #[test]
+    fn chains_every_cause() {
+        let io = std::io::Error::other("disk full");
+        let err = HandlerError::Storage(StorageError::Io(io));
+        assert_eq!(
+            error_chain(&err),
+            "Could not save changes: Could not access storage file: disk full"
+        );
+        assert_eq!(
+            error_chain(&HandlerError::Panic(String::from("oops"))),
+            "Something went wrong while handling the command: panicked at 'oops'"
+        );
+    }
+
+    #[test]
+    fn reads_panic_messages() {
+        let panic = std::panic::catch_unwind(|| panic!("at {}", 42)).unwrap_err();
+        assert_eq!(panic_message(&*panic), "at 42");
+        let panic = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(&*panic), "static");
+    }
+
+    #[tokio::test]
+    async fn reports_internal_errors_only() {
+        let discord = MockDiscord::start().await;
+        discord.route("POST", "/channels/77/messages", fixtures::message(1, ""));
+        let handler = Handler {
+            config: Arc::new(Config {
+                error_channel: Some(ChannelId(77)),
+                ..Config::default()
+            }),
+            ..Handler::default()
+        };
+        let context = discord.context();
+        let cmd = fixtures::subcommand("server", "info", json!([]));
+
+        handler
+            .report_error(&context, &cmd, &HandlerError::NotGuild, ErrorId(1))
+            .await;
+        assert!(discord.requests().is_empty());
+
+        let err = HandlerError::Panic(String::from("oops"));
+        handler.report_error(&context, &cmd, &err, ErrorId(2)).await;
+        let requests = discord.requests();
+        assert_eq!(requests.len(), 1);
+        let embed = &requests[0].body["embeds"][0];
+        assert_eq!(embed["title"], "Error 00000002");
+        assert!(embed["description"].as_str().unwrap().contains("oops"));
+        assert_eq!(
+            crate::testing::field(embed, "Command"),
+            Some("/server info")
+        );
+    }
+}
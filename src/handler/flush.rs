@@ -0,0 +1,84 @@
+//! Stores that change too often to be written on every change are written
+//! from here instead, every [`FLUSH_INTERVAL`] and on shutdown.
+
+use std::time::Duration;
+
+use serenity::client::Context;
+use serenity::prelude::{TypeMap, TypeMapKey};
+use tracing::*;
+
+use crate::storage::PendingSave;
+
+use super::analytics::Analytics;
+use super::presence::PresenceTracker;
+use super::Handler;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Present once the flush task was started.
+struct Flushing;
+
+impl TypeMapKey for Flushing {
+    type Value = ();
+}
+
+/// Snapshots of everything that changed since the last flush.
+pub fn take_pending(data: &mut TypeMap) -> Vec<PendingSave> {
+    let pending = [
+        data.get_mut::<PresenceTracker>()
+            .map(PresenceTracker::take_pending),
+        data.get_mut::<Analytics>().map(Analytics::take_pending),
+    ];
+    pending
+        .into_iter()
+        .flatten()
+        .filter_map(|pending| {
+            pending.unwrap_or_else(|err| {
+                error!(?err, "could not encode stored data");
+                None
+            })
+        })
+        .collect()
+}
+
+pub fn write_all(pending: Vec<PendingSave>) {
+    for save in pending {
+        if let Err(err) = save.write() {
+            error!(?err, "could not save stored data");
+        }
+    }
+}
+
+/// Writes what changed since the last flush, off the runtime.
+async fn flush(context: &Context) {
+    let pending = take_pending(&mut *context.data.write().await);
+    if pending.is_empty() {
+        return;
+    }
+    if let Err(err) = tokio::task::spawn_blocking(move || write_all(pending)).await {
+        error!(?err, "could not save stored data");
+    }
+}
+
+impl Handler {
+    /// Spawns the task flushing stores every [`FLUSH_INTERVAL`]. Does
+    /// nothing if it's already running.
+    pub async fn start_flush(&self, context: &Context) {
+        {
+            let mut data = context.data.write().await;
+            if data.contains_key::<Flushing>() {
+                return;
+            }
+            data.insert::<Flushing>(());
+        }
+
+        let context = context.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                flush(&context).await;
+            }
+        });
+    }
+}
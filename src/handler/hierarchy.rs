@@ -0,0 +1,195 @@
+use serenity::model::guild::{Member, PartialGuild};
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::prelude::Context;
+
+use super::HandlerError;
+
+pub fn highest_role_position(guild: &PartialGuild, roles: &[RoleId]) -> i64 {
+    roles
+        .iter()
+        .filter_map(|role| guild.roles.get(role))
+        .map(|role| role.position)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Fetches the guild and makes sure the invoker ranks above `target`,
+/// returning both along with the target's highest role position. `None` for
+/// users that aren't members of the guild, they have no roles and always
+/// pass.
+async fn check_invoker(
+    context: &Context,
+    guild_id: GuildId,
+    invoker: &Member,
+    target: UserId,
+) -> Result<Option<(PartialGuild, i64)>, HandlerError> {
+    let guild = context.http.get_guild(u64::from(guild_id)).await?;
+    if target == guild.owner_id {
+        return Err(HandlerError::InvokerHierarchy);
+    }
+    let target_member = match guild_id.member(context, target).await {
+        Ok(member) => member,
+        Err(_) => return Ok(None),
+    };
+    let target_position = highest_role_position(&guild, &target_member.roles);
+
+    if invoker.user.id != guild.owner_id
+        && highest_role_position(&guild, &invoker.roles) <= target_position
+    {
+        return Err(HandlerError::InvokerHierarchy);
+    }
+    Ok(Some((guild, target_position)))
+}
+
+/// Makes sure the invoker ranks above `target`, for actions that don't
+/// change the member through Discord, like warnings.
+pub async fn check_invoker_hierarchy(
+    context: &Context,
+    guild_id: GuildId,
+    invoker: &Member,
+    target: UserId,
+) -> Result<(), HandlerError> {
+    check_invoker(context, guild_id, invoker, target).await?;
+    Ok(())
+}
+
+/// Makes sure both the invoker and the bot rank above `target`. Users that
+/// aren't members of the guild have no roles and always pass.
+pub async fn check_hierarchy(
+    context: &Context,
+    guild_id: GuildId,
+    invoker: &Member,
+    target: UserId,
+) -> Result<(), HandlerError> {
+    let Some((guild, target_position)) = check_invoker(context, guild_id, invoker, target).await?
+    else {
+        return Ok(());
+    };
+
+    let bot = guild_id
+        .member(context, context.cache.current_user_id())
+        .await?;
+    if highest_role_position(&guild, &bot.roles) <= target_position {
+        return Err(HandlerError::BotHierarchy);
+    }
+    Ok(())
+}
+
+/// Makes sure `role` can be handed out: it isn't managed or @everyone, and
+/// the bot ranks above it. So does the invoker, when there is one.
+pub fn check_role_position(
+    guild: &PartialGuild,
+    role: RoleId,
+    invoker: Option<&Member>,
+    bot: &Member,
+) -> Result<(), HandlerError> {
+    let role = guild
+        .roles
+        .get(&role)
+        .ok_or_else(|| HandlerError::InvalidArgument(format!("<@&{}> doesn't exist", role)))?;
+    if role.managed || role.id.0 == guild.id.0 {
+        return Err(HandlerError::InvalidArgument(format!(
+            "{} can't be assigned",
+            role.name
+        )));
+    }
+    if let Some(invoker) = invoker {
+        if invoker.user.id != guild.owner_id
+            && highest_role_position(guild, &invoker.roles) <= role.position
+        {
+            return Err(HandlerError::InvokerRoleHierarchy);
+        }
+    }
+    if highest_role_position(guild, &bot.roles) <= role.position {
+        return Err(HandlerError::BotRoleHierarchy);
+    }
+    Ok(())
+}
+
+/// [`check_role_position`] for each of `roles`, with the guild as it is now.
+pub async fn check_role_hierarchy(
+    context: &Context,
+    guild_id: GuildId,
+    invoker: Option<&Member>,
+    roles: &[RoleId],
+) -> Result<(), HandlerError> {
+    let guild = context.http.get_guild(u64::from(guild_id)).await?;
+    let bot = guild_id
+        .member(context, context.cache.current_user_id())
+        .await?;
+    roles
+        .iter()
+        .try_for_each(|role| check_role_position(&guild, *role, invoker, &bot))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::fixtures;
+
+    const ADMIN: u64 = 10;
+    const MODERATOR: u64 = 11;
+    const MEMBER: u64 = 12;
+    const INTEGRATION: u64 = 13;
+
+    fn guild() -> PartialGuild {
+        let mut guild = fixtures::guild();
+        let mut integration = fixtures::role(INTEGRATION, "integration", 1);
+        integration["managed"] = json!(true);
+        guild["roles"] = json!([
+            fixtures::role(fixtures::GUILD_ID, "@everyone", 0),
+            integration,
+            fixtures::role(MEMBER, "member", 2),
+            fixtures::role(MODERATOR, "moderator", 3),
+            fixtures::role(ADMIN, "admin", 4),
+        ]);
+        serde_json::from_value(guild).unwrap()
+    }
+
+    fn member(id: u64, roles: &[u64]) -> Member {
+        let mut member = fixtures::member(id, "member", None);
+        member["roles"] = Value::from(roles.iter().map(|r| r.to_string()).collect::<Vec<_>>());
+        member["guild_id"] = json!(fixtures::GUILD_ID.to_string());
+        serde_json::from_value(member).unwrap()
+    }
+
+    #[test]
+    fn only_hands_out_lower_roles() {
+        let guild = guild();
+        let moderator = member(fixtures::INVOKER_ID, &[MODERATOR]);
+        let bot = member(fixtures::APPLICATION_ID, &[ADMIN]);
+        let check = |role, invoker| check_role_position(&guild, RoleId(role), invoker, &bot);
+
+        assert!(check(MEMBER, Some(&moderator)).is_ok());
+        assert!(matches!(
+            check(ADMIN, Some(&moderator)),
+            Err(HandlerError::InvokerRoleHierarchy)
+        ));
+        assert!(matches!(
+            check(MODERATOR, Some(&moderator)),
+            Err(HandlerError::InvokerRoleHierarchy)
+        ));
+        assert!(matches!(
+            check(INTEGRATION, Some(&moderator)),
+            Err(HandlerError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            check(fixtures::GUILD_ID, None),
+            Err(HandlerError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            check(99, None),
+            Err(HandlerError::InvalidArgument(_))
+        ));
+
+        let owner = member(fixtures::OWNER_ID, &[]);
+        assert!(check(MODERATOR, Some(&owner)).is_ok());
+        let low_bot = member(fixtures::APPLICATION_ID, &[MEMBER]);
+        assert!(matches!(
+            check_role_position(&guild, RoleId(MODERATOR), None, &low_bot),
+            Err(HandlerError::BotRoleHierarchy)
+        ));
+    }
+}
@@ -0,0 +1,473 @@
+use std::mem;
+use std::ops::Deref;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
+
+use super::response::{CommandResponse, ResponseMode};
+use super::HandlerError;
+
+/// How far the interaction has been acknowledged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Ack {
+    Pending,
+    Deferred,
+    Answered,
+}
+
+/// A command invocation, either a slash command or a text command that was
+/// translated into one. Derefs to the interaction so handlers can read
+/// options the same way for both; answers must go through [`Invocation::respond`] or [`Invocation::reply`].
+#[derive(Debug)]
+pub struct Invocation {
+    interaction: ApplicationCommandInteraction,
+    message: Option<Message>,
+    /// Where the answer went: always for text commands, and for slash
+    /// commands whose private answer followed a public deferral.
+    sent: Mutex<Option<(ChannelId, MessageId)>>,
+    ack: Mutex<Ack>,
+    /// When the first answer was sent.
+    answered_at: Mutex<Option<Instant>>,
+}
+
+impl Deref for Invocation {
+    type Target = ApplicationCommandInteraction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.interaction
+    }
+}
+
+impl From<ApplicationCommandInteraction> for Invocation {
+    fn from(interaction: ApplicationCommandInteraction) -> Self {
+        Invocation {
+            interaction,
+            message: None,
+            sent: Mutex::new(None),
+            ack: Mutex::new(Ack::Pending),
+            answered_at: Mutex::new(None),
+        }
+    }
+}
+
+impl Invocation {
+    pub fn from_text(interaction: ApplicationCommandInteraction, message: Message) -> Invocation {
+        Invocation {
+            interaction,
+            message: Some(message),
+            sent: Mutex::new(None),
+            ack: Mutex::new(Ack::Pending),
+            answered_at: Mutex::new(None),
+        }
+    }
+
+    pub fn is_text(&self) -> bool {
+        self.message.is_some()
+    }
+
+    /// The invoking message for text commands, the interaction otherwise.
+    /// Snowflakes are ordered by time, so messages before this one leave out
+    /// the command and everything sent in answer to it.
+    pub fn invoked_id(&self) -> MessageId {
+        match &self.message {
+            Some(message) => message.id,
+            None => MessageId(self.interaction.id.0),
+        }
+    }
+
+    fn sent_id(&self) -> Option<(ChannelId, MessageId)> {
+        *self.sent.lock().unwrap()
+    }
+
+    /// When [`Invocation::reply`] first answered, which comes before any
+    /// confirmation or pagination the command waits for.
+    pub fn answered_at(&self) -> Option<Instant> {
+        *self.answered_at.lock().unwrap()
+    }
+
+    pub fn is_deferred(&self) -> bool {
+        *self.ack.lock().unwrap() == Ack::Deferred
+    }
+
+    /// Acknowledges the command without answering yet, showing that the bot
+    /// is thinking. The answer sent by [`Invocation::reply`] then replaces
+    /// the placeholder, or follows it up if it is ephemeral, since a public
+    /// placeholder can't become private. Does nothing once the command has
+    /// been acknowledged.
+    pub async fn defer(&self, context: &Context) -> Result<(), HandlerError> {
+        match &self.message {
+            None => {
+                {
+                    let mut ack = self.ack.lock().unwrap();
+                    if *ack != Ack::Pending {
+                        return Ok(());
+                    }
+                    *ack = Ack::Deferred;
+                }
+                self.interaction.defer(context).await?;
+            }
+            Some(message) => message.channel_id.broadcast_typing(context).await?,
+        }
+        Ok(())
+    }
+
+    pub async fn reply(
+        &self,
+        context: &Context,
+        reply: CommandResponse,
+    ) -> Result<(), HandlerError> {
+        let ack = mem::replace(&mut *self.ack.lock().unwrap(), Ack::Answered);
+        match &self.message {
+            None if ack == Ack::Deferred && reply.ephemeral => {
+                let sent = self
+                    .interaction
+                    .create_followup_message(context, |msg| {
+                        if let Some(content) = reply.content {
+                            msg.content(content);
+                        }
+                        if let Some(components) = reply.components {
+                            msg.components(|c| {
+                                *c = components;
+                                c
+                            });
+                        }
+                        msg.ephemeral(true)
+                            .add_embeds(reply.embeds)
+                            .add_files(reply.attachments)
+                    })
+                    .await?;
+                *self.sent.lock().unwrap() = Some((sent.channel_id, sent.id));
+                self.interaction
+                    .delete_original_interaction_response(context)
+                    .await?;
+            }
+            None if ack == Ack::Deferred => {
+                let attachments = reply.attachments.clone();
+                self.edit_reply(context, reply).await?;
+                if !attachments.is_empty() {
+                    self.followup(context, CommandResponse::default().attachments(attachments))
+                        .await?;
+                }
+            }
+            None => {
+                self.interaction
+                    .create_interaction_response(context, |res| {
+                        res.interaction_response_data(|d| {
+                            if let Some(content) = reply.content {
+                                d.content(content);
+                            }
+                            if let Some(components) = reply.components {
+                                d.components(|c| {
+                                    *c = components;
+                                    c
+                                });
+                            }
+                            d.ephemeral(reply.ephemeral)
+                                .add_embeds(reply.embeds)
+                                .add_files(reply.attachments)
+                        })
+                    })
+                    .await?;
+            }
+            Some(message) => {
+                let sent = send_text(context, message, reply).await?;
+                *self.sent.lock().unwrap() = Some((sent.channel_id, sent.id));
+            }
+        }
+        self.answered_at
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+        Ok(())
+    }
+
+    /// Replaces the answer sent by [`Invocation::reply`].
+    pub async fn edit_reply(
+        &self,
+        context: &Context,
+        reply: CommandResponse,
+    ) -> Result<(), HandlerError> {
+        let components = reply.components.unwrap_or_default();
+        match (&self.message, self.sent_id()) {
+            (None, Some((_, sent_id))) => {
+                self.interaction
+                    .edit_followup_message(context, sent_id, |res| {
+                        if let Some(content) = reply.content {
+                            res.content(content);
+                        }
+                        res.set_embeds(reply.embeds).components(|c| {
+                            *c = components;
+                            c
+                        })
+                    })
+                    .await?;
+            }
+            (None, None) => {
+                self.interaction
+                    .edit_original_interaction_response(context, |res| {
+                        if let Some(content) = reply.content {
+                            res.content(content);
+                        }
+                        res.set_embeds(reply.embeds).components(|c| {
+                            *c = components;
+                            c
+                        })
+                    })
+                    .await?;
+            }
+            (Some(_), Some((channel_id, sent_id))) => {
+                channel_id
+                    .edit_message(context, sent_id, |m| {
+                        if let Some(content) = reply.content {
+                            m.content(content);
+                        }
+                        m.set_embeds(reply.embeds).components(|c| {
+                            *c = components;
+                            c
+                        })
+                    })
+                    .await?;
+            }
+            (Some(_), None) => return Err(HandlerError::TargetNone),
+        }
+        Ok(())
+    }
+
+    /// Sends another message after the answer.
+    pub async fn followup(
+        &self,
+        context: &Context,
+        reply: CommandResponse,
+    ) -> Result<(), HandlerError> {
+        match &self.message {
+            None => {
+                self.interaction
+                    .create_followup_message(context, |msg| {
+                        if let Some(content) = reply.content {
+                            msg.content(content);
+                        }
+                        if let Some(components) = reply.components {
+                            msg.components(|c| {
+                                *c = components;
+                                c
+                            });
+                        }
+                        msg.ephemeral(reply.ephemeral)
+                            .add_embeds(reply.embeds)
+                            .add_files(reply.attachments)
+                    })
+                    .await?;
+            }
+            Some(message) => {
+                send_text(context, message, reply).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delivers what a command returned the way its mode asks for, in as
+    /// many messages as it takes.
+    pub async fn respond(
+        &self,
+        context: &Context,
+        response: CommandResponse,
+    ) -> Result<(), HandlerError> {
+        let mode = response.mode;
+        if mode == ResponseMode::Handled {
+            return Ok(());
+        }
+        for (i, message) in response.into_messages().into_iter().enumerate() {
+            match (mode, i) {
+                (ResponseMode::Reply, 0) => self.reply(context, message).await?,
+                (ResponseMode::Edit, 0) => self.edit_reply(context, message).await?,
+                _ => self.followup(context, message).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The message that was sent as the answer.
+    pub async fn reply_message(&self, context: &Context) -> Result<Message, HandlerError> {
+        match (&self.message, self.sent_id()) {
+            (None, Some((_, sent_id))) => Ok(self
+                .interaction
+                .get_followup_message(context, sent_id)
+                .await?),
+            (None, None) => Ok(self.interaction.get_interaction_response(context).await?),
+            (Some(_), Some((channel_id, sent_id))) => {
+                Ok(channel_id.message(context, sent_id).await?)
+            }
+            (Some(_), None) => Err(HandlerError::TargetNone),
+        }
+    }
+
+    /// Reports an error to the invoker, answering if nothing has been sent
+    /// yet and following up otherwise.
+    pub async fn send_error(&self, context: &Context, message: &str) -> Result<(), HandlerError> {
+        if self.is_text() {
+            return self.reply(context, CommandResponse::content(message)).await;
+        }
+        if self
+            .reply(context, CommandResponse::content(message).ephemeral(true))
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        self.interaction
+            .create_followup_message(context, |msg| msg.ephemeral(true).content(message))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Posts the answer to a text command in reply to it, or in a DM to the
+/// author when it's meant for them only.
+async fn send_text(
+    context: &Context,
+    message: &Message,
+    reply: CommandResponse,
+) -> Result<Message, HandlerError> {
+    let channel_id = if reply.ephemeral {
+        message.author.create_dm_channel(context).await?.id
+    } else {
+        message.channel_id
+    };
+    let sent = channel_id
+        .send_message(context, |m| {
+            if let Some(content) = reply.content {
+                m.content(content);
+            }
+            if let Some(components) = reply.components {
+                m.components(|c| {
+                    *c = components;
+                    c
+                });
+            }
+            if !reply.ephemeral {
+                m.reference_message(message);
+            }
+            m.add_embeds(reply.embeds).add_files(reply.attachments)
+        })
+        .await?;
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{fixtures, MockDiscord};
+
+    fn original() -> String {
+        format!(
+            "/webhooks/{}/interaction-token/messages/@original",
+            fixtures::APPLICATION_ID
+        )
+    }
+
+    #[tokio::test]
+    async fn answers_replace_the_deferral() {
+        let discord = MockDiscord::start().await;
+        discord.route("PATCH", &original(), fixtures::message(2000, ""));
+        let context = discord.context();
+        let cmd = fixtures::command("test", json!([]), json!({}));
+        cmd.defer(&context).await.unwrap();
+        cmd.defer(&context).await.unwrap();
+        assert!(cmd.is_deferred());
+        cmd.reply(&context, CommandResponse::content("done"))
+            .await
+            .unwrap();
+
+        let requests = discord.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body["type"], 5);
+        assert_eq!(requests[1].method, "PATCH");
+        assert_eq!(requests[1].path, original());
+        assert_eq!(requests[1].body["content"], "done");
+        assert!(!cmd.is_deferred());
+        assert!(cmd.answered_at().is_some());
+    }
+
+    #[tokio::test]
+    async fn errors_stay_private_after_the_deferral() {
+        let discord = MockDiscord::start().await;
+        let webhook = format!("/webhooks/{}/interaction-token", fixtures::APPLICATION_ID);
+        discord.route("POST", &webhook, fixtures::message(2000, ""));
+        discord.route(
+            "PATCH",
+            &format!("{}/messages/2000", webhook),
+            fixtures::message(2000, ""),
+        );
+        let context = discord.context();
+        let cmd = fixtures::command("test", json!([]), json!({}));
+        cmd.defer(&context).await.unwrap();
+        let message = HandlerError::NotGuild.to_string();
+        cmd.send_error(&context, &message).await.unwrap();
+        cmd.edit_reply(&context, CommandResponse::content("edited"))
+            .await
+            .unwrap();
+
+        let requests = discord.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].path, webhook);
+        assert_eq!(requests[1].body["content"], message);
+        assert_eq!(requests[1].body["flags"], 64);
+        assert_eq!(requests[2].method, "DELETE");
+        assert_eq!(requests[2].path, original());
+        assert_eq!(requests[3].path, format!("{}/messages/2000", webhook));
+        assert!(cmd.answered_at().is_some());
+    }
+
+    #[tokio::test]
+    async fn text_commands_answer_privately_in_dms() {
+        let discord = MockDiscord::start().await;
+        discord.route(
+            "POST",
+            "/users/@me/channels",
+            json!({
+                "id": "555",
+                "type": 1,
+                "recipients": [fixtures::user(fixtures::INVOKER_ID, "invoker")],
+            }),
+        );
+        let mut sent = fixtures::message(2001, "");
+        sent["channel_id"] = json!("555");
+        discord.route("POST", "/channels/555/messages", sent);
+        discord.route(
+            "POST",
+            &format!("/channels/{}/messages", fixtures::CHANNEL_ID),
+            fixtures::message(2002, ""),
+        );
+        let context = discord.context();
+        let mut message = fixtures::message(1999, "!test");
+        message["author"] = fixtures::user(fixtures::INVOKER_ID, "invoker");
+        message["guild_id"] = json!(fixtures::GUILD_ID.to_string());
+        let interaction = fixtures::command("test", json!([]), json!({}));
+        let cmd = Invocation::from_text(
+            interaction.interaction.clone(),
+            serde_json::from_value(message).unwrap(),
+        );
+
+        cmd.reply(&context, CommandResponse::content("secret").ephemeral(true))
+            .await
+            .unwrap();
+        cmd.followup(&context, CommandResponse::content("public"))
+            .await
+            .unwrap();
+
+        let requests = discord.requests();
+        assert_eq!(requests[1].path, "/channels/555/messages");
+        assert_eq!(requests[1].body["content"], "secret");
+        assert!(requests[1].body.get("message_reference").is_none());
+        assert_eq!(cmd.sent_id(), Some((ChannelId(555), MessageId(2001))));
+        assert_eq!(requests[2].body["content"], "public");
+        assert_eq!(requests[2].body["message_reference"]["message_id"], "1999");
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::model::Timestamp;
+use serenity::prelude::TypeMapKey;
+use tracing::*;
+
+use crate::storage::{JsonStore, StorageError};
+
+use super::Handler;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+/// Days of history kept per guild.
+pub const MAX_HISTORY_DAYS: usize = 90;
+
+/// Member count at the end of a day along with the joins and leaves seen
+/// during it. `day` counts days since the unix epoch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyMembers {
+    pub day: i64,
+    pub members: u64,
+    pub joins: u64,
+    pub leaves: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemberChange {
+    Join,
+    Leave,
+    /// A fresh count without a join or leave, e.g. when a guild becomes
+    /// available.
+    Snapshot,
+}
+
+pub fn day_of(unix_timestamp: i64) -> i64 {
+    unix_timestamp.div_euclid(SECONDS_PER_DAY)
+}
+
+pub fn today() -> i64 {
+    day_of(Timestamp::now().unix_timestamp())
+}
+
+/// Folds a member count into `history`, starting a new entry on a new day.
+pub fn record_day(history: &mut Vec<DailyMembers>, day: i64, members: u64, change: MemberChange) {
+    let entry = match history.last_mut() {
+        Some(last) if last.day == day => last,
+        _ => {
+            history.push(DailyMembers {
+                day,
+                ..Default::default()
+            });
+            history.last_mut().unwrap()
+        }
+    };
+    entry.members = members;
+    match change {
+        MemberChange::Join => entry.joins += 1,
+        MemberChange::Leave => entry.leaves += 1,
+        MemberChange::Snapshot => {}
+    }
+    if history.len() > MAX_HISTORY_DAYS {
+        history.drain(..history.len() - MAX_HISTORY_DAYS);
+    }
+}
+
+/// How much the member count changed over the last `days` days, if the
+/// history reaches back that far.
+pub fn change_since(history: &[DailyMembers], today: i64, days: i64) -> Option<i64> {
+    let current = history.last()?.members as i64;
+    let past = history.iter().rev().find(|d| d.day <= today - days)?;
+    Some(current - past.members as i64)
+}
+
+#[derive(Debug)]
+pub struct MemberGrowth(JsonStore<HashMap<GuildId, Vec<DailyMembers>>>);
+
+impl TypeMapKey for MemberGrowth {
+    type Value = MemberGrowth;
+}
+
+impl Default for MemberGrowth {
+    fn default() -> Self {
+        MemberGrowth(JsonStore::open("member_growth"))
+    }
+}
+
+impl MemberGrowth {
+    pub fn history(&self, guild_id: GuildId) -> &[DailyMembers] {
+        self.0
+            .get()
+            .get(&guild_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn record(
+        &mut self,
+        guild_id: GuildId,
+        day: i64,
+        members: u64,
+        change: MemberChange,
+    ) -> Result<(), StorageError> {
+        self.0
+            .update(|guilds| record_day(guilds.entry(guild_id).or_default(), day, members, change))
+    }
+}
+
+impl Handler {
+    /// Records the cached member count of `guild_id` after `change`.
+    pub async fn track_member_count(
+        &self,
+        context: &Context,
+        guild_id: GuildId,
+        change: MemberChange,
+    ) {
+        let Some(members) = context.cache.guild_field(guild_id, |g| g.member_count) else {
+            trace!(?guild_id, "guild not cached, skipping member count");
+            return;
+        };
+        if let Err(err) = context
+            .data
+            .write()
+            .await
+            .entry::<MemberGrowth>()
+            .or_insert_with(MemberGrowth::default)
+            .record(guild_id, today(), members, change)
+        {
+            error!(?err, ?guild_id, "could not record member count");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: i64, members: u64, joins: u64, leaves: u64) -> DailyMembers {
+        DailyMembers {
+            day,
+            members,
+            joins,
+            leaves,
+        }
+    }
+
+    #[test]
+    fn folds_changes_into_days() {
+        assert_eq!(day_of(SECONDS_PER_DAY * 3 + 5), 3);
+        assert_eq!(day_of(-1), -1);
+
+        let mut history = vec![];
+        record_day(&mut history, 10, 5, MemberChange::Snapshot);
+        record_day(&mut history, 10, 6, MemberChange::Join);
+        record_day(&mut history, 10, 7, MemberChange::Join);
+        record_day(&mut history, 11, 6, MemberChange::Leave);
+        assert_eq!(history, [day(10, 7, 2, 0), day(11, 6, 0, 1)]);
+    }
+
+    #[test]
+    fn keeps_a_limited_history() {
+        let mut history = vec![];
+        for d in 0..MAX_HISTORY_DAYS as i64 + 5 {
+            record_day(&mut history, d, d as u64, MemberChange::Snapshot);
+        }
+        assert_eq!(history.len(), MAX_HISTORY_DAYS);
+        assert_eq!(history[0].day, 5);
+    }
+
+    #[test]
+    fn measures_change_since_a_past_day() {
+        let history = [day(1, 10, 0, 0), day(5, 14, 0, 0), day(8, 12, 0, 0)];
+        assert_eq!(change_since(&history, 8, 1), Some(-2));
+        assert_eq!(change_since(&history, 8, 3), Some(-2));
+        assert_eq!(change_since(&history, 8, 7), Some(2));
+        assert_eq!(change_since(&history, 8, 30), None);
+        assert_eq!(change_since(&[], 8, 1), None);
+    }
+}
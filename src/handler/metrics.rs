@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use serenity::prelude::TypeMapKey;
+
+use super::analytics::InteractionRecord;
+
+/// Upper bounds of the command latency histogram, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Counters since startup, exported in the Prometheus text format. Unlike
+/// [`super::analytics::Analytics`] they cover every guild and are never
+/// trimmed.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    interactions: BTreeMap<String, u64>,
+    errors: BTreeMap<(String, String), u64>,
+    latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: Duration,
+    latency_count: u64,
+}
+
+impl TypeMapKey for Metrics {
+    type Value = Metrics;
+}
+
+impl Metrics {
+    pub fn record(&mut self, record: &InteractionRecord) {
+        *self.interactions.entry(record.command.clone()).or_default() += 1;
+        if let Some(kind) = &record.error {
+            *self
+                .errors
+                .entry((record.command.clone(), kind.clone()))
+                .or_default() += 1;
+        }
+
+        let secs = record.latency.as_secs_f64();
+        for (count, bound) in self.latency_buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= bound {
+                *count += 1;
+            }
+        }
+        self.latency_sum += record.latency;
+        self.latency_count += 1;
+    }
+
+    /// The metrics in the Prometheus text format, along with readiness and
+    /// the heartbeat latency of each shard that has one.
+    pub fn render(&self, ready: bool, gateway_latencies: &[(u64, Option<Duration>)]) -> String {
+        let mut out = String::new();
+        let header = |out: &mut String, name: &str, kind: &str, help: &str| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        };
+
+        header(
+            &mut out,
+            "bot_ready",
+            "gauge",
+            "Whether every shard is up and commands are registered.",
+        );
+        let _ = writeln!(out, "bot_ready {}", u8::from(ready));
+
+        header(
+            &mut out,
+            "bot_interactions_total",
+            "counter",
+            "Commands handled, by command.",
+        );
+        for (command, count) in &self.interactions {
+            let _ = writeln!(
+                out,
+                "bot_interactions_total{{command=\"{}\"}} {}",
+                command, count
+            );
+        }
+
+        header(
+            &mut out,
+            "bot_interaction_errors_total",
+            "counter",
+            "Commands that failed, by command and error.",
+        );
+        for ((command, kind), count) in &self.errors {
+            let _ = writeln!(
+                out,
+                "bot_interaction_errors_total{{command=\"{}\",kind=\"{}\"}} {}",
+                command, kind, count
+            );
+        }
+
+        header(
+            &mut out,
+            "bot_interaction_duration_seconds",
+            "histogram",
+            "Time taken to handle and answer a command.",
+        );
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.latency_buckets) {
+            let _ = writeln!(
+                out,
+                "bot_interaction_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "bot_interaction_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            self.latency_count
+        );
+        let _ = writeln!(
+            out,
+            "bot_interaction_duration_seconds_sum {}",
+            self.latency_sum.as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "bot_interaction_duration_seconds_count {}",
+            self.latency_count
+        );
+
+        header(
+            &mut out,
+            "bot_gateway_latency_seconds",
+            "gauge",
+            "Heartbeat latency, by shard.",
+        );
+        for (shard, latency) in gateway_latencies {
+            if let Some(latency) = latency {
+                let _ = writeln!(
+                    out,
+                    "bot_gateway_latency_seconds{{shard=\"{}\"}} {}",
+                    shard,
+                    latency.as_secs_f64()
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use serenity::model::id::UserId;
+
+    use super::*;
+
+    fn record(command: &str, millis: u64, error: Option<&'static str>) -> InteractionRecord {
+        InteractionRecord {
+            at: SystemTime::now(),
+            command: command.to_string(),
+            subcommand: None,
+            guild_id: None,
+            user_id: UserId(1),
+            latency: Duration::from_millis(millis),
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn renders_counters_and_latencies() {
+        let mut metrics = Metrics::default();
+        metrics.record(&record("user", 40, None));
+        metrics.record(&record("user", 300, Some("NotGuild")));
+        metrics.record(&record("ml", 3000, None));
+        let text = metrics.render(true, &[(0, Some(Duration::from_millis(42))), (1, None)]);
+        let lines: Vec<&str> = text.lines().filter(|l| !l.starts_with('#')).collect();
+
+        for expected in [
+            "bot_ready 1",
+            "bot_interactions_total{command=\"ml\"} 1",
+            "bot_interactions_total{command=\"user\"} 2",
+            "bot_interaction_errors_total{command=\"user\",kind=\"NotGuild\"} 1",
+            "bot_interaction_duration_seconds_bucket{le=\"0.05\"} 1",
+            "bot_interaction_duration_seconds_bucket{le=\"0.5\"} 2",
+            "bot_interaction_duration_seconds_bucket{le=\"5\"} 3",
+            "bot_interaction_duration_seconds_bucket{le=\"+Inf\"} 3",
+            "bot_interaction_duration_seconds_sum 3.34",
+            "bot_interaction_duration_seconds_count 3",
+            "bot_gateway_latency_seconds{shard=\"0\"} 0.042",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "{} missing from\n{}",
+                expected,
+                text
+            );
+        }
+        assert!(!text.contains("shard=\"1\""));
+    }
+}
@@ -0,0 +1,381 @@
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::channel::{Attachment, PartialChannel};
+use serenity::model::guild::Role;
+use serenity::model::id::{ChannelId, RoleId, UserId};
+use serenity::model::user::User;
+
+use super::HandlerError;
+use crate::util::LocalizedString;
+
+/// Arguments of a (sub)command, read from its options. Usually implemented
+/// through `command_options!`.
+pub trait FromCommandOptions: Sized {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, HandlerError>;
+}
+
+/// A single option. `Option<T>` is implemented alongside every `T` and
+/// stands for options that can be left out.
+pub trait FromOption: Sized {
+    fn from_option(
+        option: Option<&CommandDataOption>,
+        name: &'static str,
+    ) -> Result<Self, HandlerError>;
+}
+
+/// A value that can be read from a present option.
+pub trait OptionValue: Sized {
+    /// Describes the value for error messages, e.g. "a user".
+    const EXPECTED: &'static str;
+
+    fn from_value(option: &CommandDataOption) -> Option<Self>;
+}
+
+fn extract<T: OptionValue>(
+    option: &CommandDataOption,
+    name: &'static str,
+) -> Result<T, HandlerError> {
+    T::from_value(option).ok_or(HandlerError::InvalidOptionType(name, T::EXPECTED))
+}
+
+macro_rules! impl_from_option {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FromOption for $ty {
+                fn from_option(
+                    option: Option<&CommandDataOption>,
+                    name: &'static str,
+                ) -> Result<Self, HandlerError> {
+                    extract(option.ok_or(HandlerError::MissingOption(name))?, name)
+                }
+            }
+
+            impl FromOption for Option<$ty> {
+                fn from_option(
+                    option: Option<&CommandDataOption>,
+                    name: &'static str,
+                ) -> Result<Self, HandlerError> {
+                    option.map(|o| extract(o, name)).transpose()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_option!(
+    String,
+    i64,
+    f64,
+    bool,
+    User,
+    Role,
+    PartialChannel,
+    Attachment,
+    UserId,
+    RoleId,
+    ChannelId
+);
+
+impl OptionValue for String {
+    const EXPECTED: &'static str = "text";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        option.value.as_ref()?.as_str().map(str::to_string)
+    }
+}
+
+impl OptionValue for i64 {
+    const EXPECTED: &'static str = "a whole number";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        option.value.as_ref()?.as_i64()
+    }
+}
+
+impl OptionValue for f64 {
+    const EXPECTED: &'static str = "a number";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        option.value.as_ref()?.as_f64()
+    }
+}
+
+impl OptionValue for bool {
+    const EXPECTED: &'static str = "true or false";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        option.value.as_ref()?.as_bool()
+    }
+}
+
+impl OptionValue for User {
+    const EXPECTED: &'static str = "a user";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref()? {
+            CommandDataOptionValue::User(user, _) => Some(user.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for Role {
+    const EXPECTED: &'static str = "a role";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref()? {
+            CommandDataOptionValue::Role(role) => Some(role.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for PartialChannel {
+    const EXPECTED: &'static str = "a channel";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref()? {
+            CommandDataOptionValue::Channel(channel) => Some(channel.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl OptionValue for Attachment {
+    const EXPECTED: &'static str = "an attachment";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref()? {
+            CommandDataOptionValue::Attachment(attachment) => Some(attachment.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The raw snowflake of a user, role or channel option, for when the
+/// resolved entity isn't needed.
+fn snowflake(option: &CommandDataOption) -> Option<u64> {
+    option.value.as_ref()?.as_str()?.parse().ok()
+}
+
+impl OptionValue for UserId {
+    const EXPECTED: &'static str = "a user";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user.id),
+            Some(_) => None,
+            None => snowflake(option).map(UserId),
+        }
+    }
+}
+
+impl OptionValue for RoleId {
+    const EXPECTED: &'static str = "a role";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::Role(role)) => Some(role.id),
+            Some(_) => None,
+            None => snowflake(option).map(RoleId),
+        }
+    }
+}
+
+impl OptionValue for ChannelId {
+    const EXPECTED: &'static str = "a channel";
+
+    fn from_value(option: &CommandDataOption) -> Option<Self> {
+        match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::Channel(channel)) => Some(channel.id),
+            Some(_) => None,
+            None => snowflake(option).map(ChannelId),
+        }
+    }
+}
+
+/// Follows subcommand groups and subcommands down to the one that was
+/// invoked, returning the names along the way and its options.
+pub fn subcommand_path(options: &[CommandDataOption]) -> (Vec<&str>, &[CommandDataOption]) {
+    let mut path = vec![];
+    let mut options = options;
+    while let [option] = options {
+        if !matches!(
+            option.kind,
+            CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup
+        ) {
+            break;
+        }
+        path.push(option.name.as_str());
+        options = &option.options;
+    }
+    (path, options)
+}
+
+/// The subcommands of a command, usually generated by `app_command!`.
+pub trait Subcommand: Sized {
+    /// The subcommand invoked through `path`, e.g. `["welcome", "set"]`.
+    fn from_path(path: &[&str]) -> Option<Self>;
+
+    /// The subcommand's own name, without its group.
+    fn name(&self) -> LocalizedString;
+}
+
+/// The invoked subcommand along with its options.
+pub fn parse_subcommand<S: Subcommand>(
+    options: &[CommandDataOption],
+) -> Result<(S, &[CommandDataOption]), HandlerError> {
+    let (path, options) = subcommand_path(options);
+    if path.is_empty() {
+        return Err(HandlerError::EmptyCommand);
+    }
+    let sub =
+        S::from_path(&path).ok_or_else(|| HandlerError::UnrecognizedCommand(path.join(" ")))?;
+    Ok((sub, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::fixtures;
+
+    const ROLE_ID: u64 = 11;
+
+    /// The options of a command, resolved the way Discord would.
+    fn options(options: Value) -> Vec<CommandDataOption> {
+        let resolved =
+            json!({ "roles": { ROLE_ID.to_string(): fixtures::role(ROLE_ID, "mods", 1) } });
+        fixtures::command("test", options, resolved)
+            .data
+            .options
+            .clone()
+    }
+
+    fn read<T: FromOption>(
+        options: &[CommandDataOption],
+        name: &'static str,
+    ) -> Result<T, HandlerError> {
+        T::from_option(options.iter().find(|o| o.name == name), name)
+    }
+
+    #[test]
+    fn converts_option_values() {
+        let options = options(json!([
+            { "name": "text", "type": 3, "value": "hello" },
+            { "name": "count", "type": 4, "value": 5 },
+            { "name": "ratio", "type": 10, "value": 0.5 },
+            { "name": "flag", "type": 5, "value": true },
+            { "name": "user", "type": 6, "value": fixtures::OTHER_ID.to_string() },
+            { "name": "role", "type": 8, "value": ROLE_ID.to_string() },
+        ]));
+
+        assert_eq!(read::<String>(&options, "text").unwrap(), "hello");
+        assert_eq!(read::<i64>(&options, "count").unwrap(), 5);
+        assert_eq!(read::<f64>(&options, "ratio").unwrap(), 0.5);
+        assert!(read::<bool>(&options, "flag").unwrap());
+        assert_eq!(
+            read::<UserId>(&options, "user").unwrap(),
+            UserId(fixtures::OTHER_ID)
+        );
+        assert_eq!(read::<Role>(&options, "role").unwrap().name, "mods");
+        assert_eq!(read::<RoleId>(&options, "role").unwrap(), RoleId(ROLE_ID));
+        assert_eq!(read::<Option<i64>>(&options, "count").unwrap(), Some(5));
+        assert_eq!(read::<Option<String>>(&options, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn reports_missing_options() {
+        assert!(matches!(
+            read::<String>(&[], "text"),
+            Err(HandlerError::MissingOption("text"))
+        ));
+        assert!(matches!(
+            read::<UserId>(&[], "user"),
+            Err(HandlerError::MissingOption("user"))
+        ));
+    }
+
+    #[test]
+    fn reports_values_of_the_wrong_type() {
+        let options = options(json!([
+            { "name": "text", "type": 3, "value": "hello" },
+            { "name": "role", "type": 8, "value": ROLE_ID.to_string() },
+        ]));
+
+        assert!(matches!(
+            read::<i64>(&options, "text"),
+            Err(HandlerError::InvalidOptionType("text", "a whole number"))
+        ));
+        assert!(matches!(
+            read::<Option<bool>>(&options, "text"),
+            Err(HandlerError::InvalidOptionType("text", "true or false"))
+        ));
+        assert!(matches!(
+            read::<UserId>(&options, "role"),
+            Err(HandlerError::InvalidOptionType("role", "a user"))
+        ));
+        assert!(matches!(
+            read::<User>(&options, "text"),
+            Err(HandlerError::InvalidOptionType("text", "a user"))
+        ));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Sub {
+        Show,
+        WelcomeSet,
+    }
+
+    impl Subcommand for Sub {
+        fn from_path(path: &[&str]) -> Option<Sub> {
+            match path {
+                ["show"] => Some(Sub::Show),
+                ["welcome", "set"] => Some(Sub::WelcomeSet),
+                _ => None,
+            }
+        }
+
+        fn name(&self) -> LocalizedString {
+            match self {
+                Sub::Show => LocalizedString { en: "show" },
+                Sub::WelcomeSet => LocalizedString { en: "set" },
+            }
+        }
+    }
+
+    #[test]
+    fn follows_the_subcommand_path() {
+        let options = options(json!([{
+            "name": "welcome",
+            "type": 2,
+            "options": [{
+                "name": "set",
+                "type": 1,
+                "options": [{ "name": "text", "type": 3, "value": "hi" }],
+            }],
+        }]));
+        let (sub, args) = parse_subcommand::<Sub>(&options).unwrap();
+        assert_eq!(sub, Sub::WelcomeSet);
+        assert_eq!(read::<String>(args, "text").unwrap(), "hi");
+
+        let options = self::options(json!([{ "name": "show", "type": 1 }]));
+        let (sub, args) = parse_subcommand::<Sub>(&options).unwrap();
+        assert_eq!(sub, Sub::Show);
+        assert!(args.is_empty());
+
+        let options = self::options(json!([{ "name": "hide", "type": 1 }]));
+        assert!(matches!(
+            parse_subcommand::<Sub>(&options),
+            Err(HandlerError::UnrecognizedCommand(name)) if name == "hide"
+        ));
+        let options = self::options(json!([{ "name": "text", "type": 3, "value": "hi" }]));
+        assert!(matches!(
+            parse_subcommand::<Sub>(&options),
+            Err(HandlerError::EmptyCommand)
+        ));
+    }
+}
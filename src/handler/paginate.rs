@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::InteractionResponseType;
+
+use super::invocation::Invocation;
+use super::response::CommandResponse;
+use super::HandlerError;
+
+const PREV_ID: &str = "page_prev";
+const NEXT_ID: &str = "page_next";
+const PAGE_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn page_buttons(page: usize, pages: usize) -> CreateComponents {
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id(PREV_ID)
+                .label("◀")
+                .style(ButtonStyle::Secondary)
+                .disabled(page == 0)
+        })
+        .create_button(|b| {
+            b.custom_id(NEXT_ID)
+                .label("▶")
+                .style(ButtonStyle::Secondary)
+                .disabled(page + 1 == pages)
+        })
+    });
+    components
+}
+
+/// Answers with the first page and lets the invoker flip through the rest
+/// until the buttons time out. A single page is returned to be sent as is.
+pub async fn paginate(
+    cmd: &Invocation,
+    context: &Context,
+    mut pages: Vec<CreateEmbed>,
+    ephemeral: bool,
+) -> Result<CommandResponse, HandlerError> {
+    let count = pages.len();
+    if count <= 1 {
+        return Ok(CommandResponse::embeds(pages).ephemeral(ephemeral));
+    }
+    for (i, page) in pages.iter_mut().enumerate() {
+        page.footer(|f| f.text(format!("Page {}/{}", i + 1, count)));
+    }
+
+    let mut page = 0;
+    cmd.reply(
+        context,
+        CommandResponse::embed(pages[page].clone())
+            .ephemeral(ephemeral)
+            .components(page_buttons(page, count)),
+    )
+    .await?;
+    let message = cmd.reply_message(context).await?;
+
+    while let Some(press) = message
+        .await_component_interaction(&context.shard)
+        .author_id(cmd.user.id)
+        .timeout(PAGE_TIMEOUT)
+        .await
+    {
+        page = match press.data.custom_id.as_str() {
+            PREV_ID => page.saturating_sub(1),
+            NEXT_ID => (page + 1).min(count - 1),
+            _ => page,
+        };
+        let embed = pages[page].clone();
+        let buttons = page_buttons(page, count);
+        press
+            .create_interaction_response(context, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.set_embeds(vec![embed]).components(|c| {
+                            *c = buttons;
+                            c
+                        })
+                    })
+            })
+            .await?;
+    }
+
+    cmd.edit_reply(context, CommandResponse::embed(pages[page].clone()))
+        .await?;
+    Ok(CommandResponse::handled())
+}
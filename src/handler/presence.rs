@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::gateway::{ActivityType, Presence};
+use serenity::model::guild::Guild;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::user::OnlineStatus;
+use serenity::model::Timestamp;
+use serenity::prelude::TypeMapKey;
+
+use crate::storage::{JsonStore, PendingSave, StorageError};
+
+use super::Handler;
+
+/// What a member is doing right now. Kept in memory only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveActivity {
+    pub status: OnlineStatus,
+    /// The activity's name and when it started, as a unix timestamp.
+    pub game: Option<(String, i64)>,
+}
+
+impl LiveActivity {
+    pub fn online(&self) -> bool {
+        !matches!(self.status, OnlineStatus::Offline | OnlineStatus::Invisible)
+    }
+}
+
+/// What is persisted per member: when they were last online and the seconds
+/// spent per activity in finished sessions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberActivity {
+    pub last_seen: Option<i64>,
+    pub games: HashMap<String, u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildActivity {
+    pub members: HashMap<UserId, MemberActivity>,
+}
+
+/// The activity worth tracking from a presence, skipping custom statuses.
+pub fn presence_game(presence: &Presence) -> Option<String> {
+    presence
+        .activities
+        .iter()
+        .find(|a| a.kind != ActivityType::Custom)
+        .map(|a| a.name.clone())
+}
+
+/// Moves `live` to the observed status and game, folding whatever ended
+/// into `stored`. Returns whether `stored` changed.
+pub fn observe(
+    live: &mut Option<LiveActivity>,
+    stored: &mut MemberActivity,
+    now: i64,
+    status: OnlineStatus,
+    game: Option<String>,
+) -> bool {
+    let mut changed = false;
+    let previous = live.take();
+    let next_game = match (previous.as_ref().and_then(|p| p.game.as_ref()), game) {
+        (Some((old, started)), Some(new)) if *old == new => Some((new, *started)),
+        (old, new) => {
+            if let Some((name, started)) = old {
+                *stored.games.entry(name.clone()).or_insert(0) += (now - started).max(0) as u64;
+                changed = true;
+            }
+            new.map(|name| (name, now))
+        }
+    };
+    let next = LiveActivity {
+        status,
+        game: next_game,
+    };
+    if matches!(&previous, Some(p) if p.online()) && !next.online() {
+        stored.last_seen = Some(now);
+        changed = true;
+    }
+    *live = Some(next);
+    changed
+}
+
+/// Per-guild opt-in activity tracking. Only guilds present in the store are
+/// tracked; disabling tracking drops everything recorded for the guild.
+#[derive(Debug)]
+pub struct PresenceTracker {
+    guilds: JsonStore<HashMap<GuildId, GuildActivity>>,
+    live: HashMap<GuildId, HashMap<UserId, LiveActivity>>,
+}
+
+impl TypeMapKey for PresenceTracker {
+    type Value = PresenceTracker;
+}
+
+impl Default for PresenceTracker {
+    fn default() -> Self {
+        PresenceTracker {
+            guilds: JsonStore::open("presence"),
+            live: HashMap::new(),
+        }
+    }
+}
+
+impl PresenceTracker {
+    pub fn enabled(&self, guild_id: GuildId) -> bool {
+        self.guilds.get().contains_key(&guild_id)
+    }
+
+    pub fn set_enabled(&mut self, guild_id: GuildId, enabled: bool) -> Result<(), StorageError> {
+        if !enabled {
+            self.live.remove(&guild_id);
+        }
+        self.guilds.update(|guilds| {
+            if enabled {
+                guilds.entry(guild_id).or_default();
+            } else {
+                guilds.remove(&guild_id);
+            }
+        })
+    }
+
+    pub fn member(&self, guild_id: GuildId, user_id: UserId) -> Option<&MemberActivity> {
+        self.guilds.get().get(&guild_id)?.members.get(&user_id)
+    }
+
+    pub fn live(&self, guild_id: GuildId, user_id: UserId) -> Option<&LiveActivity> {
+        self.live.get(&guild_id)?.get(&user_id)
+    }
+
+    /// Activities being played in the guild right now with how many members
+    /// are playing each, most popular first.
+    pub fn top_games(&self, guild_id: GuildId) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for live in self
+            .live
+            .get(&guild_id)
+            .into_iter()
+            .flat_map(|m| m.values())
+        {
+            if let Some((game, _)) = &live.game {
+                *counts.entry(game).or_insert(0) += 1;
+            }
+        }
+        let mut games: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(game, count)| (game.to_string(), count))
+            .collect();
+        games.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        games
+    }
+
+    /// Records a presence if its guild opted in. Changes are stored when a
+    /// session ends or a member goes offline, and written to disk by the
+    /// next flush.
+    pub fn record(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        now: i64,
+        status: OnlineStatus,
+        game: Option<String>,
+    ) {
+        if !self.enabled(guild_id) {
+            return;
+        }
+        let mut stored = self.member(guild_id, user_id).cloned().unwrap_or_default();
+        let live = self.live.entry(guild_id).or_default();
+        let mut slot = live.remove(&user_id);
+        let changed = observe(&mut slot, &mut stored, now, status, game);
+        if let Some(slot) = slot {
+            live.insert(user_id, slot);
+        }
+        if !changed {
+            return;
+        }
+        self.guilds.update_later(|guilds| {
+            if let Some(guild) = guilds.get_mut(&guild_id) {
+                guild.members.insert(user_id, stored);
+            }
+        });
+    }
+
+    pub fn take_pending(&mut self) -> Result<Option<PendingSave>, StorageError> {
+        self.guilds.take_pending()
+    }
+}
+
+impl Handler {
+    pub async fn track_presence(&self, context: &Context, presence: &Presence) {
+        let Some(guild_id) = presence.guild_id else {
+            return;
+        };
+        if !tracking(context, guild_id).await {
+            return;
+        }
+        if let Some(tracker) = context.data.write().await.get_mut::<PresenceTracker>() {
+            tracker.record(
+                guild_id,
+                presence.user.id,
+                Timestamp::now().unix_timestamp(),
+                presence.status,
+                presence_game(presence),
+            );
+        }
+    }
+
+    /// Picks up where tracking left off when a guild becomes available,
+    /// e.g. after a restart.
+    pub async fn track_guild_presences(&self, context: &Context, guild: &Guild) {
+        if !tracking(context, guild.id).await {
+            return;
+        }
+        let mut data = context.data.write().await;
+        let Some(tracker) = data.get_mut::<PresenceTracker>() else {
+            return;
+        };
+        let now = Timestamp::now().unix_timestamp();
+        for (user_id, presence) in &guild.presences {
+            tracker.record(
+                guild.id,
+                *user_id,
+                now,
+                presence.status,
+                presence_game(presence),
+            );
+        }
+    }
+}
+
+/// Whether the guild opted in, checked under a read lock since most don't.
+async fn tracking(context: &Context, guild_id: GuildId) -> bool {
+    context
+        .data
+        .read()
+        .await
+        .get::<PresenceTracker>()
+        .is_some_and(|tracker| tracker.enabled(guild_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: GuildId = GuildId(1);
+    const USER: UserId = UserId(2);
+
+    #[test]
+    fn saves_finished_sessions_when_flushed() {
+        let mut tracker = PresenceTracker {
+            guilds: JsonStore::temporary("presence"),
+            live: HashMap::new(),
+        };
+        let game = || Some(String::from("Chess"));
+        tracker.record(GUILD, USER, 0, OnlineStatus::Online, game());
+        assert!(tracker.live(GUILD, USER).is_none());
+
+        tracker.set_enabled(GUILD, true).unwrap();
+        tracker.record(GUILD, USER, 0, OnlineStatus::Online, game());
+        tracker.record(GUILD, USER, 60, OnlineStatus::Online, game());
+        assert!(tracker.take_pending().unwrap().is_none());
+
+        tracker.record(GUILD, USER, 90, OnlineStatus::Offline, None);
+        let stored = tracker.member(GUILD, USER).unwrap();
+        assert_eq!(stored.games["Chess"], 90);
+        assert_eq!(stored.last_seen, Some(90));
+        assert!(tracker.take_pending().unwrap().is_some());
+        assert!(tracker.take_pending().unwrap().is_none());
+    }
+}
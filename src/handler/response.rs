@@ -0,0 +1,425 @@
+use std::mem;
+
+use serde_json::Value;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::channel::AttachmentType;
+
+use crate::util::truncate;
+
+pub const CONTENT_LIMIT: usize = 2000;
+pub const EMBEDS_PER_MESSAGE: usize = 10;
+pub const EMBED_TOTAL_LIMIT: usize = 6000;
+const TITLE_LIMIT: usize = 256;
+const DESCRIPTION_LIMIT: usize = 4096;
+const FIELDS_PER_EMBED: usize = 25;
+const FIELD_NAME_LIMIT: usize = 256;
+const FIELD_VALUE_LIMIT: usize = 1024;
+const FOOTER_LIMIT: usize = 2048;
+const AUTHOR_LIMIT: usize = 256;
+const CODE_FENCE: &str = "```";
+
+/// How a [`CommandResponse`] is delivered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResponseMode {
+    /// The first answer to the invocation.
+    #[default]
+    Reply,
+    /// An additional message after the first answer.
+    Followup,
+    /// Replaces the first answer.
+    Edit,
+    /// The handler already answered itself, e.g. through a confirmation
+    /// prompt, so there's nothing left to send.
+    Handled,
+}
+
+/// What a command answers with. Handlers return it and the dispatcher
+/// sends it, splitting it into several messages when it's over Discord's
+/// limits.
+#[derive(Clone, Debug, Default)]
+pub struct CommandResponse {
+    pub content: Option<String>,
+    pub embeds: Vec<CreateEmbed>,
+    pub components: Option<CreateComponents>,
+    pub attachments: Vec<AttachmentType<'static>>,
+    pub ephemeral: bool,
+    pub mode: ResponseMode,
+}
+
+impl CommandResponse {
+    pub fn content(content: impl Into<String>) -> CommandResponse {
+        CommandResponse {
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn embed(embed: CreateEmbed) -> CommandResponse {
+        CommandResponse::embeds(vec![embed])
+    }
+
+    pub fn embeds(embeds: Vec<CreateEmbed>) -> CommandResponse {
+        CommandResponse {
+            embeds,
+            ..Default::default()
+        }
+    }
+
+    /// Embeds when there are any, the text content otherwise.
+    pub fn embeds_or_content(embeds: Vec<CreateEmbed>, content: String) -> CommandResponse {
+        if embeds.is_empty() {
+            CommandResponse::content(content)
+        } else {
+            CommandResponse::embeds(embeds)
+        }
+    }
+
+    pub fn handled() -> CommandResponse {
+        CommandResponse::default().mode(ResponseMode::Handled)
+    }
+
+    pub fn ephemeral(mut self, ephemeral: bool) -> CommandResponse {
+        self.ephemeral = ephemeral;
+        self
+    }
+
+    pub fn components(mut self, components: CreateComponents) -> CommandResponse {
+        self.components = Some(components);
+        self
+    }
+
+    pub fn attachment(mut self, attachment: AttachmentType<'static>) -> CommandResponse {
+        self.attachments.push(attachment);
+        self
+    }
+
+    pub fn attachments(mut self, attachments: Vec<AttachmentType<'static>>) -> CommandResponse {
+        self.attachments.extend(attachments);
+        self
+    }
+
+    pub fn mode(mut self, mode: ResponseMode) -> CommandResponse {
+        self.mode = mode;
+        self
+    }
+
+    /// Splits the response into messages within Discord's limits. Overlong
+    /// embed parts are truncated, content is split on line breaks and
+    /// embeds are spread over as many messages as needed. Components and
+    /// attachments stay on the first message.
+    pub fn into_messages(self) -> Vec<CommandResponse> {
+        let CommandResponse {
+            content,
+            embeds,
+            components,
+            attachments,
+            ephemeral,
+            mode,
+        } = self;
+        let message = |content, embeds| CommandResponse {
+            content,
+            embeds,
+            ephemeral,
+            mode,
+            ..Default::default()
+        };
+
+        let mut messages: Vec<CommandResponse> = content
+            .map(|content| split_content(&content))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|chunk| message(Some(chunk), vec![]))
+            .collect();
+
+        let embed_groups = group_embeds(embeds.into_iter().map(truncate_embed).collect());
+        let mut embed_groups = embed_groups.into_iter();
+        // The last content chunk carries the first embeds, like the
+        // original response did.
+        if let Some(last) = messages.last_mut() {
+            last.embeds = embed_groups.next().unwrap_or_default();
+        }
+        messages.extend(embed_groups.map(|group| message(None, group)));
+
+        if messages.is_empty() {
+            messages.push(message(None, vec![]));
+        }
+        messages[0].components = components;
+        messages[0].attachments = attachments;
+        messages
+    }
+}
+
+/// Splits `content` into chunks of at most [`CONTENT_LIMIT`] characters,
+/// preferring line breaks. A content that's a single code block is split
+/// into several code blocks.
+pub fn split_content(content: &str) -> Vec<String> {
+    if content.chars().count() <= CONTENT_LIMIT {
+        return vec![content.to_string()];
+    }
+    let fenced = content
+        .strip_prefix(CODE_FENCE)
+        .and_then(|c| c.strip_suffix(CODE_FENCE))
+        .filter(|body| !body.contains(CODE_FENCE));
+    let (body, limit) = match fenced {
+        Some(body) => (
+            body.trim_matches('\n'),
+            CONTENT_LIMIT - 2 * (CODE_FENCE.len() + 1),
+        ),
+        None => (content, CONTENT_LIMIT),
+    };
+
+    let mut chunks = vec![];
+    let mut current = String::new();
+    let mut current_len = 0;
+    for line in body.split_inclusive('\n') {
+        let mut line = line;
+        while !line.is_empty() {
+            let line_len = line.chars().count();
+            if current_len + line_len <= limit {
+                current.push_str(line);
+                current_len += line_len;
+                break;
+            }
+            if current_len > 0 {
+                chunks.push(mem::take(&mut current));
+                current_len = 0;
+                continue;
+            }
+            // A single line over the limit is cut wherever it has to be.
+            let cut = line
+                .char_indices()
+                .nth(limit)
+                .map_or(line.len(), |(i, _)| i);
+            chunks.push(line[..cut].to_string());
+            line = &line[cut..];
+        }
+    }
+    if current_len > 0 {
+        chunks.push(current);
+    }
+
+    match fenced {
+        Some(_) => chunks
+            .into_iter()
+            .map(|chunk| {
+                format!(
+                    "{}\n{}\n{}",
+                    CODE_FENCE,
+                    chunk.trim_end_matches('\n'),
+                    CODE_FENCE
+                )
+            })
+            .collect(),
+        None => chunks,
+    }
+}
+
+fn truncate_value(value: &mut Value, max: usize) {
+    if let Some(text) = value.as_str() {
+        if text.chars().count() > max {
+            *value = Value::from(truncate(text, max));
+        }
+    }
+}
+
+/// Cuts every part of `embed` down to Discord's limits.
+pub fn truncate_embed(mut embed: CreateEmbed) -> CreateEmbed {
+    if let Some(title) = embed.0.get_mut("title") {
+        truncate_value(title, TITLE_LIMIT);
+    }
+    if let Some(description) = embed.0.get_mut("description") {
+        truncate_value(description, DESCRIPTION_LIMIT);
+    }
+    if let Some(Value::Array(fields)) = embed.0.get_mut("fields") {
+        fields.truncate(FIELDS_PER_EMBED);
+        for field in fields {
+            truncate_value(&mut field["name"], FIELD_NAME_LIMIT);
+            truncate_value(&mut field["value"], FIELD_VALUE_LIMIT);
+        }
+    }
+    if let Some(footer) = embed.0.get_mut("footer") {
+        truncate_value(&mut footer["text"], FOOTER_LIMIT);
+    }
+    if let Some(author) = embed.0.get_mut("author") {
+        truncate_value(&mut author["name"], AUTHOR_LIMIT);
+    }
+    embed
+}
+
+/// The characters of `embed` that count towards [`EMBED_TOTAL_LIMIT`].
+pub fn embed_len(embed: &CreateEmbed) -> usize {
+    let len = |value: Option<&Value>| {
+        value
+            .and_then(Value::as_str)
+            .map_or(0, |s| s.chars().count())
+    };
+    let fields = match embed.0.get("fields") {
+        Some(Value::Array(fields)) => fields
+            .iter()
+            .map(|f| len(f.get("name")) + len(f.get("value")))
+            .sum(),
+        _ => 0,
+    };
+    len(embed.0.get("title"))
+        + len(embed.0.get("description"))
+        + len(embed.0.get("footer").and_then(|f| f.get("text")))
+        + len(embed.0.get("author").and_then(|a| a.get("name")))
+        + fields
+}
+
+/// Groups embeds into messages of at most [`EMBEDS_PER_MESSAGE`] embeds and
+/// [`EMBED_TOTAL_LIMIT`] characters.
+fn group_embeds(embeds: Vec<CreateEmbed>) -> Vec<Vec<CreateEmbed>> {
+    let mut groups: Vec<Vec<CreateEmbed>> = vec![];
+    let mut total = 0;
+    for embed in embeds {
+        let len = embed_len(&embed);
+        match groups.last_mut() {
+            Some(group) if group.len() < EMBEDS_PER_MESSAGE && total + len <= EMBED_TOTAL_LIMIT => {
+                total += len;
+                group.push(embed);
+            }
+            _ => {
+                total = len;
+                groups.push(vec![embed]);
+            }
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{fixtures, MockDiscord};
+
+    fn numbered_lines(count: usize) -> String {
+        (0..count)
+            .map(|i| format!("line {:04}", i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn embed_with_title(title: &str) -> CreateEmbed {
+        let mut embed = CreateEmbed::default();
+        embed.title(title);
+        embed
+    }
+
+    #[test]
+    fn short_content_is_kept() {
+        assert_eq!(split_content("hello"), vec![String::from("hello")]);
+    }
+
+    #[test]
+    fn long_content_is_split_on_lines() {
+        let content = numbered_lines(500);
+        let chunks = split_content(&content);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.chars().count() <= CONTENT_LIMIT));
+        assert!(chunks.iter().all(|c| c.starts_with("line ")));
+        assert_eq!(chunks.concat(), content);
+    }
+
+    #[test]
+    fn code_blocks_stay_fenced() {
+        let content = format!("```\n{}\n```", numbered_lines(500));
+        let chunks = split_content(&content);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= CONTENT_LIMIT);
+            assert!(chunk.starts_with("```\nline ") && chunk.ends_with("\n```"));
+        }
+    }
+
+    #[test]
+    fn lines_over_the_limit_are_cut() {
+        let content = "x".repeat(CONTENT_LIMIT * 2 + 1);
+        let chunks = split_content(&content);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), content);
+    }
+
+    #[test]
+    fn embeds_are_truncated() {
+        let mut embed = embed_with_title(&"t".repeat(300));
+        embed.field("name", "v".repeat(2000), false);
+        let embed = truncate_embed(embed);
+
+        assert_eq!(
+            embed.0["title"].as_str().unwrap().chars().count(),
+            TITLE_LIMIT
+        );
+        let value = embed.0["fields"][0]["value"].as_str().unwrap();
+        assert_eq!(value.chars().count(), FIELD_VALUE_LIMIT);
+        assert!(value.ends_with('…'));
+    }
+
+    #[test]
+    fn embeds_are_spread_over_messages() {
+        let embeds = (0..12).map(|i| embed_with_title(&i.to_string())).collect();
+        let messages = CommandResponse::embeds(embeds)
+            .components(CreateComponents::default())
+            .ephemeral(true)
+            .into_messages();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].embeds.len(), EMBEDS_PER_MESSAGE);
+        assert_eq!(messages[1].embeds.len(), 2);
+        assert!(messages[0].components.is_some() && messages[1].components.is_none());
+        assert!(messages.iter().all(|m| m.ephemeral));
+    }
+
+    #[test]
+    fn embeds_follow_the_content() {
+        let mut response = CommandResponse::content(numbered_lines(500));
+        response.embeds.push(embed_with_title("last"));
+        let messages = response.into_messages();
+
+        let last = messages.last().unwrap();
+        assert!(last.content.is_some());
+        assert_eq!(last.embeds.len(), 1);
+        assert!(messages[..messages.len() - 1]
+            .iter()
+            .all(|m| m.embeds.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn overflow_is_sent_as_followups() {
+        let discord = MockDiscord::start().await;
+        let followups = format!("/webhooks/{}/interaction-token", fixtures::APPLICATION_ID);
+        discord.route("POST", &followups, fixtures::message(2000, ""));
+        let cmd = fixtures::command("test", json!([]), json!({}));
+        let embeds = (0..12).map(|i| embed_with_title(&i.to_string())).collect();
+        cmd.respond(&discord.context(), CommandResponse::embeds(embeds))
+            .await
+            .unwrap();
+
+        let responses = discord.responses();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["embeds"].as_array().unwrap().len(), 10);
+        let requests = discord.requests();
+        let followup = requests.last().unwrap();
+        assert_eq!(
+            (followup.method.as_str(), &followup.path),
+            ("POST", &followups)
+        );
+        assert_eq!(followup.body["embeds"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn handled_responses_send_nothing() {
+        let discord = MockDiscord::start().await;
+        let cmd = fixtures::command("test", json!([]), json!({}));
+        cmd.respond(&discord.context(), CommandResponse::handled())
+            .await
+            .unwrap();
+
+        assert!(discord.requests().is_empty());
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::application::component::ComponentType;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId};
+use serenity::prelude::TypeMapKey;
+use tracing::*;
+
+use crate::builders::roles::{role_menu_components, role_menu_embed};
+use crate::storage::{JsonStore, StorageError};
+
+use super::hierarchy::check_role_hierarchy;
+use super::{Handler, HandlerError};
+
+pub const CUSTOM_ID_PREFIX: &str = "rolemenu";
+/// Discord allows 25 buttons per message and 25 options per select menu.
+pub const MAX_MENU_ROLES: usize = 25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MenuStyle {
+    Buttons,
+    Select,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoleMenu {
+    pub id: u64,
+    pub title: String,
+    pub style: MenuStyle,
+    pub roles: Vec<RoleId>,
+    /// How many of the menu's roles a member may hold at once.
+    pub max_selections: Option<u64>,
+    /// Picking a role drops the member's other roles from the menu.
+    pub exclusive: bool,
+    pub required_role: Option<RoleId>,
+    pub published: Option<(ChannelId, MessageId)>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RoleChange {
+    pub add: Vec<RoleId>,
+    pub remove: Vec<RoleId>,
+}
+
+impl RoleMenu {
+    fn limit(&self) -> Option<u64> {
+        if self.exclusive {
+            Some(1)
+        } else {
+            self.max_selections
+        }
+    }
+
+    fn check_required(&self, held: &[RoleId]) -> Result<(), HandlerError> {
+        match self.required_role {
+            Some(required) if !held.contains(&required) => {
+                Err(HandlerError::RequiredRole(required))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The changes that leave a member holding `held` with exactly
+    /// `selected` out of this menu's roles.
+    pub fn select(&self, held: &[RoleId], selected: &[RoleId]) -> Result<RoleChange, HandlerError> {
+        self.check_required(held)?;
+        let selected: Vec<RoleId> = self
+            .roles
+            .iter()
+            .filter(|r| selected.contains(r))
+            .copied()
+            .collect();
+        if let Some(limit) = self.limit() {
+            if selected.len() as u64 > limit {
+                return Err(HandlerError::TooManyRoles(limit));
+            }
+        }
+        Ok(RoleChange {
+            add: selected
+                .iter()
+                .filter(|r| !held.contains(r))
+                .copied()
+                .collect(),
+            remove: self
+                .roles
+                .iter()
+                .filter(|r| held.contains(r) && !selected.contains(r))
+                .copied()
+                .collect(),
+        })
+    }
+
+    /// The changes a click on `role`'s button makes for a member holding
+    /// `held`.
+    pub fn toggle(&self, held: &[RoleId], role: RoleId) -> Result<RoleChange, HandlerError> {
+        if !self.roles.contains(&role) {
+            return Err(HandlerError::UnexpectedData);
+        }
+        let mut selected: Vec<RoleId> = self
+            .roles
+            .iter()
+            .filter(|r| held.contains(r))
+            .copied()
+            .collect();
+        if selected.contains(&role) {
+            selected.retain(|r| *r != role);
+        } else if self.exclusive {
+            selected = vec![role];
+        } else {
+            selected.push(role);
+        }
+        self.select(held, &selected)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildMenus {
+    next_id: u64,
+    menus: Vec<RoleMenu>,
+}
+
+#[derive(Debug)]
+pub struct RoleMenus(JsonStore<HashMap<GuildId, GuildMenus>>);
+
+impl TypeMapKey for RoleMenus {
+    type Value = RoleMenus;
+}
+
+impl Default for RoleMenus {
+    fn default() -> Self {
+        RoleMenus(JsonStore::open("role_menus"))
+    }
+}
+
+impl RoleMenus {
+    pub fn menu(&self, guild_id: GuildId, id: u64) -> Option<&RoleMenu> {
+        self.0
+            .get()
+            .get(&guild_id)
+            .and_then(|g| g.menus.iter().find(|m| m.id == id))
+    }
+
+    pub fn create(
+        &mut self,
+        guild_id: GuildId,
+        title: &str,
+        style: MenuStyle,
+        max_selections: Option<u64>,
+        exclusive: bool,
+        required_role: Option<RoleId>,
+    ) -> Result<RoleMenu, StorageError> {
+        self.0.update(|guilds| {
+            let guild = guilds.entry(guild_id).or_default();
+            guild.next_id += 1;
+            let menu = RoleMenu {
+                id: guild.next_id,
+                title: title.to_string(),
+                style,
+                roles: vec![],
+                max_selections,
+                exclusive,
+                required_role,
+                published: None,
+            };
+            guild.menus.push(menu.clone());
+            menu
+        })
+    }
+
+    /// Applies `f` to the menu and returns the updated copy, or `None` if
+    /// the menu doesn't exist.
+    pub fn update<R>(
+        &mut self,
+        guild_id: GuildId,
+        id: u64,
+        f: impl FnOnce(&mut RoleMenu) -> R,
+    ) -> Result<Option<(RoleMenu, R)>, StorageError> {
+        self.0.update(|guilds| {
+            let menu = guilds
+                .get_mut(&guild_id)?
+                .menus
+                .iter_mut()
+                .find(|m| m.id == id)?;
+            let result = f(menu);
+            Some((menu.clone(), result))
+        })
+    }
+}
+
+/// Parses `rolemenu:<menu>` or `rolemenu:<menu>:<role>`.
+fn parse_custom_id(custom_id: &str) -> Option<(u64, Option<RoleId>)> {
+    let mut parts = custom_id.split(':');
+    if parts.next()? != CUSTOM_ID_PREFIX {
+        return None;
+    }
+    let menu = parts.next()?.parse().ok()?;
+    let role = match parts.next() {
+        Some(role) => Some(RoleId(role.parse().ok()?)),
+        None => None,
+    };
+    Some((menu, role))
+}
+
+pub fn is_role_menu(custom_id: &str) -> bool {
+    parse_custom_id(custom_id).is_some()
+}
+
+/// Brings a published menu's message in line with the stored menu.
+pub async fn refresh_published(context: &Context, guild_id: GuildId, menu: &RoleMenu) {
+    let Some((channel_id, message_id)) = menu.published else {
+        return;
+    };
+    let roles = match guild_id.roles(context).await {
+        Ok(roles) => roles,
+        Err(err) => {
+            warn!(?err, menu = menu.id, "could not fetch roles for role menu");
+            return;
+        }
+    };
+    let components = role_menu_components(menu, &roles);
+    if let Err(err) = channel_id
+        .edit_message(context, message_id, |m| {
+            m.set_embed(role_menu_embed(menu)).components(|c| {
+                *c = components;
+                c
+            })
+        })
+        .await
+    {
+        warn!(?err, menu = menu.id, "could not update role menu message");
+    }
+}
+
+impl Handler {
+    pub async fn handle_role_menu(
+        &self,
+        context: &Context,
+        component: &MessageComponentInteraction,
+    ) -> Result<(), HandlerError> {
+        let (menu_id, role) =
+            parse_custom_id(&component.data.custom_id).ok_or(HandlerError::UnexpectedData)?;
+        let guild_id = component.guild_id.ok_or(HandlerError::NotGuild)?;
+        let member = component
+            .member
+            .as_ref()
+            .ok_or(HandlerError::UserNotFound)?;
+        let menu = context
+            .data
+            .read()
+            .await
+            .get::<RoleMenus>()
+            .and_then(|menus| menus.menu(guild_id, menu_id).cloned())
+            .ok_or(HandlerError::MenuNotFound(menu_id))?;
+
+        let change = match (component.data.component_type, role) {
+            (ComponentType::Button, Some(role)) => menu.toggle(&member.roles, role)?,
+            (ComponentType::SelectMenu, None) => {
+                let selected: Vec<RoleId> = component
+                    .data
+                    .values
+                    .iter()
+                    .filter_map(|v| v.parse().ok().map(RoleId))
+                    .collect();
+                menu.select(&member.roles, &selected)?
+            }
+            _ => return Err(HandlerError::UnexpectedData),
+        };
+
+        // Roles may have been moved or taken over by an integration since
+        // they were added to the menu.
+        if !change.add.is_empty() {
+            check_role_hierarchy(context, guild_id, None, &change.add).await?;
+        }
+
+        let reason = format!("Role menu #{}", menu.id);
+        for role in &change.add {
+            context
+                .http
+                .add_member_role(guild_id.0, member.user.id.0, role.0, Some(&reason))
+                .await?;
+        }
+        for role in &change.remove {
+            context
+                .http
+                .remove_member_role(guild_id.0, member.user.id.0, role.0, Some(&reason))
+                .await?;
+        }
+
+        let mut lines: Vec<String> = change.add.iter().map(|r| format!("+ <@&{}>", r)).collect();
+        lines.extend(change.remove.iter().map(|r| format!("- <@&{}>", r)));
+        let content = if lines.is_empty() {
+            String::from("Nothing changed.")
+        } else {
+            lines.join("\n")
+        };
+        component
+            .create_interaction_response(context, |res| {
+                res.interaction_response_data(|d| d.ephemeral(true).content(content))
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: RoleId = RoleId(1);
+    const GREEN: RoleId = RoleId(2);
+    const BLUE: RoleId = RoleId(3);
+    const VERIFIED: RoleId = RoleId(4);
+    const OTHER: RoleId = RoleId(5);
+
+    fn menu() -> RoleMenu {
+        RoleMenu {
+            id: 1,
+            title: String::from("Colors"),
+            style: MenuStyle::Buttons,
+            roles: vec![RED, GREEN, BLUE],
+            max_selections: None,
+            exclusive: false,
+            required_role: None,
+            published: None,
+        }
+    }
+
+    fn change(add: &[RoleId], remove: &[RoleId]) -> RoleChange {
+        RoleChange {
+            add: add.to_vec(),
+            remove: remove.to_vec(),
+        }
+    }
+
+    #[test]
+    fn toggles_roles_on_and_off() {
+        let menu = menu();
+        assert_eq!(menu.toggle(&[OTHER], RED).unwrap(), change(&[RED], &[]));
+        assert_eq!(
+            menu.toggle(&[RED, OTHER], RED).unwrap(),
+            change(&[], &[RED])
+        );
+        assert_eq!(
+            menu.select(&[RED, GREEN, OTHER], &[GREEN]).unwrap(),
+            change(&[], &[RED])
+        );
+    }
+
+    #[test]
+    fn exclusive_menus_swap_roles() {
+        let menu = RoleMenu {
+            exclusive: true,
+            ..menu()
+        };
+        assert_eq!(
+            menu.toggle(&[RED, OTHER], GREEN).unwrap(),
+            change(&[GREEN], &[RED])
+        );
+        assert!(matches!(
+            menu.select(&[], &[RED, GREEN]),
+            Err(HandlerError::TooManyRoles(1))
+        ));
+    }
+
+    #[test]
+    fn enforces_the_selection_limit() {
+        let menu = RoleMenu {
+            max_selections: Some(2),
+            ..menu()
+        };
+        assert_eq!(menu.toggle(&[RED], GREEN).unwrap(), change(&[GREEN], &[]));
+        assert!(matches!(
+            menu.toggle(&[RED, GREEN], BLUE),
+            Err(HandlerError::TooManyRoles(2))
+        ));
+        // Dropping a role is fine even when the member is over the limit.
+        assert_eq!(
+            menu.toggle(&[RED, GREEN, BLUE], BLUE).unwrap(),
+            change(&[], &[BLUE])
+        );
+    }
+
+    #[test]
+    fn needs_the_required_role() {
+        let menu = RoleMenu {
+            required_role: Some(VERIFIED),
+            ..menu()
+        };
+        assert!(matches!(
+            menu.toggle(&[], RED),
+            Err(HandlerError::RequiredRole(VERIFIED))
+        ));
+        assert_eq!(menu.toggle(&[VERIFIED], RED).unwrap(), change(&[RED], &[]));
+    }
+
+    #[test]
+    fn ignores_roles_off_the_menu() {
+        let menu = menu();
+        assert!(matches!(
+            menu.toggle(&[], OTHER),
+            Err(HandlerError::UnexpectedData)
+        ));
+        assert_eq!(
+            menu.select(&[OTHER], &[OTHER, BLUE]).unwrap(),
+            change(&[BLUE], &[])
+        );
+    }
+}
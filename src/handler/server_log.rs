@@ -0,0 +1,557 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::event::MessageUpdateEvent;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::user::User;
+use serenity::model::Timestamp;
+use serenity::prelude::TypeMapKey;
+use serenity::utils::Color;
+use tracing::*;
+
+use crate::storage::{JsonStore, StorageError};
+use crate::util::truncate;
+
+use super::{Handler, HandlerError};
+
+const MAX_CACHED_MESSAGES: usize = 5_000;
+const FIELD_LIMIT: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct CachedMessage {
+    pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
+    pub author: UserId,
+    pub content: String,
+    pub attachments: Vec<String>,
+}
+
+impl From<&Message> for CachedMessage {
+    fn from(msg: &Message) -> Self {
+        CachedMessage {
+            channel_id: msg.channel_id,
+            guild_id: msg.guild_id,
+            author: msg.author.id,
+            content: msg.content.clone(),
+            attachments: msg.attachments.iter().map(|a| a.filename.clone()).collect(),
+        }
+    }
+}
+
+/// Keeps the most recent messages so deletions and edits can show what was
+/// there before. Oldest entries are evicted first.
+///
+/// Removed messages stay in the insertion order until they reach its front,
+/// each entry is tagged with when it was inserted so a stale one never
+/// evicts a message cached again under the same id.
+#[derive(Debug, Default)]
+pub struct MessageCache {
+    next: u64,
+    order: VecDeque<(u64, MessageId)>,
+    messages: HashMap<MessageId, (u64, CachedMessage)>,
+}
+
+impl MessageCache {
+    pub fn insert(&mut self, id: MessageId, message: CachedMessage) {
+        if let Some((_, cached)) = self.messages.get_mut(&id) {
+            *cached = message;
+            return;
+        }
+        self.next += 1;
+        self.messages.insert(id, (self.next, message));
+        self.order.push_back((self.next, id));
+        while self.messages.len() > MAX_CACHED_MESSAGES
+            || self.order.len() > 2 * MAX_CACHED_MESSAGES
+        {
+            let Some((inserted, oldest)) = self.order.pop_front() else {
+                break;
+            };
+            if self
+                .messages
+                .get(&oldest)
+                .is_some_and(|(i, _)| *i == inserted)
+            {
+                self.messages.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn get(&self, id: MessageId) -> Option<&CachedMessage> {
+        self.messages.get(&id).map(|(_, message)| message)
+    }
+
+    pub fn remove(&mut self, id: MessageId) -> Option<CachedMessage> {
+        self.messages.remove(&id).map(|(_, message)| message)
+    }
+
+    fn forget_guild(&mut self, guild_id: GuildId) {
+        self.messages
+            .retain(|_, (_, m)| m.guild_id != Some(guild_id));
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ServerLogSettings {
+    pub channel: Option<ChannelId>,
+}
+
+#[derive(Debug)]
+pub struct ServerLog {
+    settings: JsonStore<HashMap<GuildId, ServerLogSettings>>,
+    pub cache: MessageCache,
+    /// Messages the bot is deleting and reports itself, like automod.
+    own_deletes: HashSet<MessageId>,
+}
+
+impl TypeMapKey for ServerLog {
+    type Value = ServerLog;
+}
+
+impl Default for ServerLog {
+    fn default() -> Self {
+        ServerLog {
+            settings: JsonStore::open("server_log"),
+            cache: MessageCache::default(),
+            own_deletes: HashSet::new(),
+        }
+    }
+}
+
+impl ServerLog {
+    pub fn channel(&self, guild_id: GuildId) -> Option<ChannelId> {
+        self.settings.get().get(&guild_id).and_then(|s| s.channel)
+    }
+
+    pub fn set_channel(
+        &mut self,
+        guild_id: GuildId,
+        channel: Option<ChannelId>,
+    ) -> Result<(), StorageError> {
+        if channel.is_none() {
+            self.cache.forget_guild(guild_id);
+        }
+        self.settings
+            .update(|settings| settings.entry(guild_id).or_default().channel = channel)
+    }
+
+    /// Caches `message` if its guild logs to a channel; other messages
+    /// would never be shown.
+    pub fn remember(&mut self, id: MessageId, message: CachedMessage) {
+        if message.guild_id.and_then(|g| self.channel(g)).is_some() {
+            self.cache.insert(id, message);
+        }
+    }
+}
+
+fn user_tag(user: &User) -> String {
+    format!("{}#{:04}", user.name, user.discriminator)
+}
+
+fn content_or_placeholder(content: &str, attachments: &[String]) -> String {
+    let mut text = if content.is_empty() {
+        String::from("*No text content*")
+    } else {
+        content.to_string()
+    };
+    if !attachments.is_empty() {
+        text += &format!("\n📎 {}", attachments.join(", "));
+    }
+    truncate(&text, FIELD_LIMIT)
+}
+
+impl Handler {
+    async fn log_channel(&self, context: &Context, guild_id: GuildId) -> Option<ChannelId> {
+        context
+            .data
+            .read()
+            .await
+            .get::<ServerLog>()?
+            .channel(guild_id)
+    }
+
+    pub(super) async fn post_log(
+        &self,
+        context: &Context,
+        guild_id: GuildId,
+        mut embed: CreateEmbed,
+    ) {
+        let Some(channel_id) = self.log_channel(context, guild_id).await else {
+            return;
+        };
+        embed.timestamp(Timestamp::now());
+        if let Err(err) = channel_id
+            .send_message(context, |m| m.set_embed(embed))
+            .await
+        {
+            warn!(?err, ?channel_id, "could not post to server-log channel");
+        }
+    }
+
+    /// Deletes `msg` without a server-log entry, for deletions the caller
+    /// reports itself.
+    pub(super) async fn delete_unlogged(
+        &self,
+        context: &Context,
+        msg: &Message,
+    ) -> Result<(), HandlerError> {
+        let mark = |own: bool| async move {
+            if let Some(log) = context.data.write().await.get_mut::<ServerLog>() {
+                if own {
+                    log.own_deletes.insert(msg.id);
+                } else {
+                    log.own_deletes.remove(&msg.id);
+                }
+            }
+        };
+        mark(true).await;
+        if let Err(err) = msg.delete(context).await {
+            mark(false).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    pub async fn cache_message(&self, context: &Context, msg: &Message) {
+        let Some(guild_id) = msg.guild_id else {
+            return;
+        };
+        // Checked under a read lock first, most guilds don't log messages.
+        if self.log_channel(context, guild_id).await.is_none() {
+            return;
+        }
+        let mut data = context.data.write().await;
+        if let Some(log) = data.get_mut::<ServerLog>() {
+            log.remember(msg.id, CachedMessage::from(msg));
+        }
+    }
+
+    pub async fn log_message_update(&self, context: &Context, event: &MessageUpdateEvent) {
+        let (Some(guild_id), Some(new_content)) = (event.guild_id, event.content.as_ref()) else {
+            return;
+        };
+
+        let old = {
+            let mut data = context.data.write().await;
+            let Some(log) = data.get_mut::<ServerLog>() else {
+                return;
+            };
+            let old = log.cache.get(event.id).cloned();
+            if let Some(mut updated) = old.clone() {
+                updated.content = new_content.clone();
+                log.cache.insert(event.id, updated);
+            }
+            old
+        };
+        if old.as_ref().is_some_and(|old| &old.content == new_content) {
+            return;
+        }
+        // Without the old content only a new edit timestamp tells an edit
+        // apart from embeds being loaded.
+        if old.is_none() && event.edited_timestamp.is_none() {
+            return;
+        }
+        if event.author.as_ref().is_some_and(|a| a.bot) {
+            return;
+        }
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Message edited")
+            .color(Color::BLUE)
+            .description(format!(
+                "[Jump to message](https://discord.com/channels/{}/{}/{}) in <#{}>",
+                guild_id, event.channel_id, event.id, event.channel_id
+            ))
+            .field(
+                "Before",
+                match &old {
+                    Some(old) => content_or_placeholder(&old.content, &[]),
+                    None => String::from("*Not cached*"),
+                },
+                false,
+            )
+            .field("After", content_or_placeholder(new_content, &[]), false);
+        if let Some(author) = &event.author {
+            embed.field("Author", format!("<@{}>", author.id), true);
+        } else if let Some(old) = &old {
+            embed.field("Author", format!("<@{}>", old.author), true);
+        }
+        self.post_log(context, guild_id, embed).await;
+    }
+
+    pub async fn log_message_delete(
+        &self,
+        context: &Context,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        let Some(guild_id) = guild_id else {
+            return;
+        };
+        let (cached, own) = {
+            let mut data = context.data.write().await;
+            let Some(log) = data.get_mut::<ServerLog>() else {
+                return;
+            };
+            (
+                log.cache.remove(message_id),
+                log.own_deletes.remove(&message_id),
+            )
+        };
+        if own {
+            return;
+        }
+        if self.log_channel(context, guild_id).await == Some(channel_id) {
+            return;
+        }
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Message deleted")
+            .color(Color::RED)
+            .description(format!("In <#{}>", channel_id));
+        match cached {
+            Some(message) => {
+                embed
+                    .field("Author", format!("<@{}>", message.author), true)
+                    .field(
+                        "Content",
+                        content_or_placeholder(&message.content, &message.attachments),
+                        false,
+                    );
+            }
+            None => {
+                embed.field("Content", "*Not cached*", false);
+            }
+        }
+        embed.footer(|f| f.text(format!("Message ID: {}", message_id)));
+        self.post_log(context, guild_id, embed).await;
+    }
+
+    pub async fn log_message_delete_bulk(
+        &self,
+        context: &Context,
+        channel_id: ChannelId,
+        message_ids: &[MessageId],
+        guild_id: Option<GuildId>,
+    ) {
+        let Some(guild_id) = guild_id else {
+            return;
+        };
+        {
+            let mut data = context.data.write().await;
+            if let Some(log) = data.get_mut::<ServerLog>() {
+                for id in message_ids {
+                    log.cache.remove(*id);
+                }
+            }
+        }
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Messages purged")
+            .color(Color::RED)
+            .description(format!(
+                "{} messages were deleted in <#{}>",
+                message_ids.len(),
+                channel_id
+            ));
+        self.post_log(context, guild_id, embed).await;
+    }
+
+    pub async fn log_member_join(&self, context: &Context, member: &Member) {
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Member joined")
+            .color(Color::DARK_GREEN)
+            .thumbnail(member.user.face())
+            .description(format!("<@{}> {}", member.user.id, user_tag(&member.user)))
+            .field(
+                "Account created",
+                format!("<t:{}:R>", member.user.created_at().unix_timestamp()),
+                true,
+            );
+        self.post_log(context, member.guild_id, embed).await;
+    }
+
+    pub async fn log_member_leave(&self, context: &Context, guild_id: GuildId, user: &User) {
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Member left")
+            .color(Color::DARK_RED)
+            .thumbnail(user.face())
+            .description(format!("<@{}> {}", user.id, user_tag(user)));
+        self.post_log(context, guild_id, embed).await;
+    }
+
+    /// Logs nickname and role changes. Without the previous state from the
+    /// cache there is nothing to diff against, so nothing is posted.
+    pub async fn log_member_update(&self, context: &Context, old: Option<&Member>, new: &Member) {
+        let Some(old) = old else {
+            return;
+        };
+
+        let added: Vec<String> = new
+            .roles
+            .iter()
+            .filter(|r| !old.roles.contains(r))
+            .map(|r| format!("<@&{}>", r))
+            .collect();
+        let removed: Vec<String> = old
+            .roles
+            .iter()
+            .filter(|r| !new.roles.contains(r))
+            .map(|r| format!("<@&{}>", r))
+            .collect();
+        let nick_changed = old.nick != new.nick;
+        if !nick_changed && added.is_empty() && removed.is_empty() {
+            return;
+        }
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Member updated")
+            .color(Color::BLUE)
+            .thumbnail(new.user.face())
+            .description(format!("<@{}> {}", new.user.id, user_tag(&new.user)));
+        if nick_changed {
+            let show =
+                |nick: &Option<String>| nick.clone().unwrap_or_else(|| String::from("*None*"));
+            embed.field("Nickname before", show(&old.nick), true).field(
+                "Nickname after",
+                show(&new.nick),
+                true,
+            );
+        }
+        if !added.is_empty() {
+            embed.field(
+                "Roles added",
+                truncate(&added.join(" "), FIELD_LIMIT),
+                false,
+            );
+        }
+        if !removed.is_empty() {
+            embed.field(
+                "Roles removed",
+                truncate(&removed.join(" "), FIELD_LIMIT),
+                false,
+            );
+        }
+        self.post_log(context, new.guild_id, embed).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{fixtures, MockDiscord};
+
+    const LOGGED: GuildId = GuildId(1);
+    const UNLOGGED: GuildId = GuildId(2);
+
+    fn message(guild_id: GuildId) -> CachedMessage {
+        CachedMessage {
+            channel_id: ChannelId(10),
+            guild_id: Some(guild_id),
+            author: UserId(20),
+            content: String::from("hello"),
+            attachments: vec![],
+        }
+    }
+
+    #[test]
+    fn only_caches_messages_of_logged_guilds() {
+        let mut log = ServerLog {
+            settings: JsonStore::temporary("server_log"),
+            cache: MessageCache::default(),
+            own_deletes: HashSet::new(),
+        };
+        log.set_channel(LOGGED, Some(ChannelId(3))).unwrap();
+        log.remember(MessageId(1), message(LOGGED));
+        log.remember(MessageId(2), message(UNLOGGED));
+        assert!(log.cache.get(MessageId(1)).is_some());
+        assert!(log.cache.get(MessageId(2)).is_none());
+
+        log.set_channel(LOGGED, None).unwrap();
+        assert!(log.cache.get(MessageId(1)).is_none());
+        log.remember(MessageId(3), message(LOGGED));
+        assert!(log.cache.get(MessageId(3)).is_none());
+        assert_eq!(log.cache.messages.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn skips_deletes_and_edits_with_nothing_to_show() {
+        let discord = MockDiscord::start().await;
+        let context = discord.context();
+        let mut log = ServerLog {
+            settings: JsonStore::temporary("server_log"),
+            cache: MessageCache::default(),
+            own_deletes: HashSet::from([MessageId(1)]),
+        };
+        log.set_channel(LOGGED, Some(ChannelId(3))).unwrap();
+        context.data.write().await.insert::<ServerLog>(log);
+        let handler = Handler::default();
+
+        handler
+            .log_message_delete(&context, ChannelId(10), MessageId(1), Some(LOGGED))
+            .await;
+        let update = |edited: Option<&str>| -> MessageUpdateEvent {
+            serde_json::from_value(json!({
+                "id": "2",
+                "channel_id": "10",
+                "guild_id": LOGGED.to_string(),
+                "content": "hello",
+                "edited_timestamp": edited,
+            }))
+            .unwrap()
+        };
+        handler.log_message_update(&context, &update(None)).await;
+        assert!(discord.requests().is_empty());
+
+        handler
+            .log_message_update(&context, &update(Some(fixtures::JOINED_AT)))
+            .await;
+        handler
+            .log_message_delete(&context, ChannelId(10), MessageId(1), Some(LOGGED))
+            .await;
+        let titles: Vec<_> = discord
+            .requests()
+            .iter()
+            .map(|r| r.body["embeds"][0]["title"].clone())
+            .collect();
+        assert_eq!(titles, ["Message edited", "Message deleted"]);
+    }
+
+    #[test]
+    fn evicts_the_oldest_messages() {
+        let mut cache = MessageCache::default();
+        let count = MAX_CACHED_MESSAGES as u64;
+        for id in 1..=count {
+            cache.insert(MessageId(id), message(LOGGED));
+        }
+        // Cached again after a removal, it's now among the newest.
+        cache.remove(MessageId(1));
+        cache.insert(MessageId(1), message(LOGGED));
+        cache.insert(MessageId(count + 1), message(LOGGED));
+
+        assert_eq!(cache.messages.len(), MAX_CACHED_MESSAGES);
+        assert!(cache.get(MessageId(1)).is_some());
+        assert!(cache.get(MessageId(2)).is_none());
+        assert!(cache.get(MessageId(3)).is_some());
+
+        for id in 1..=count * 3 {
+            cache.remove(MessageId(id));
+            cache.insert(MessageId(id), message(LOGGED));
+            cache.remove(MessageId(id));
+        }
+        assert_eq!(cache.messages.len(), 0);
+        assert!(cache.order.len() <= 2 * MAX_CACHED_MESSAGES);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Arc;
+
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::model::id::GuildId;
+use serenity::prelude::{Mutex, TypeMapKey};
+
+/// The client's shard manager, for commands reporting on the shards.
+pub struct ShardManagerContainer;
+
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<Mutex<ShardManager>>;
+}
+
+/// Which of this process' shards are up and which commands are registered.
+/// Every shard registers the commands of the guilds it receives, this makes
+/// sure global commands and each guild are only registered once.
+#[derive(Debug, Default)]
+pub struct Shards {
+    /// How many shards this process runs. Unknown until the first shard is
+    /// ready when autosharding.
+    expected: Option<u64>,
+    /// The shard count of the whole bot, as Discord reported it.
+    total: Option<u64>,
+    ready: BTreeSet<u64>,
+    global_commands: bool,
+    guilds: HashSet<GuildId>,
+    shutting_down: bool,
+}
+
+impl TypeMapKey for Shards {
+    type Value = Shards;
+}
+
+impl Shards {
+    pub fn new(expected: Option<u64>) -> Shards {
+        Shards {
+            expected,
+            ..Default::default()
+        }
+    }
+
+    /// Whether every shard is ready and commands are registered.
+    pub fn is_ready(&self) -> bool {
+        !self.shutting_down
+            && self.global_commands
+            && self
+                .expected
+                .is_some_and(|expected| self.ready.len() as u64 >= expected)
+    }
+
+    pub fn expected(&self) -> Option<u64> {
+        self.expected
+    }
+
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    pub fn ready_shards(&self) -> &BTreeSet<u64> {
+        &self.ready
+    }
+
+    /// Marks `shard` ready, `total` being the shard count Discord reported.
+    pub fn set_ready(&mut self, shard: u64, total: u64) {
+        self.expected.get_or_insert(total);
+        self.total = Some(total);
+        self.ready.insert(shard);
+    }
+
+    pub fn set_disconnected(&mut self, shard: u64) {
+        self.ready.remove(&shard);
+    }
+
+    pub fn set_shutting_down(&mut self) {
+        self.shutting_down = true;
+    }
+
+    /// Whether the calling shard should register the global commands. Only
+    /// the first caller gets `true` until [`Shards::release_global_commands`].
+    pub fn claim_global_commands(&mut self) -> bool {
+        !std::mem::replace(&mut self.global_commands, true)
+    }
+
+    pub fn release_global_commands(&mut self) {
+        self.global_commands = false;
+    }
+
+    /// The guilds among `guilds` nobody registered commands for yet, which
+    /// the caller is now responsible for.
+    pub fn claim_guilds(&mut self, guilds: impl IntoIterator<Item = GuildId>) -> Vec<GuildId> {
+        guilds
+            .into_iter()
+            .filter(|guild| self.guilds.insert(*guild))
+            .collect()
+    }
+
+    pub fn release_guilds(&mut self, guilds: &[GuildId]) {
+        for guild in guilds {
+            self.guilds.remove(guild);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_everything_once() {
+        let mut shards = Shards::new(Some(2));
+        assert!(shards.claim_global_commands());
+        assert!(!shards.claim_global_commands());
+        assert_eq!(
+            shards.claim_guilds([GuildId(1), GuildId(2)]),
+            vec![GuildId(1), GuildId(2)]
+        );
+        assert_eq!(
+            shards.claim_guilds([GuildId(2), GuildId(3)]),
+            vec![GuildId(3)]
+        );
+
+        shards.release_guilds(&[GuildId(3)]);
+        assert_eq!(shards.claim_guilds([GuildId(3)]), vec![GuildId(3)]);
+        shards.release_global_commands();
+        assert!(shards.claim_global_commands());
+    }
+
+    #[test]
+    fn ready_once_every_shard_is() {
+        let mut shards = Shards::new(Some(2));
+        shards.claim_global_commands();
+        shards.set_ready(0, 4);
+        assert!(!shards.is_ready());
+        shards.set_ready(1, 4);
+        assert!(shards.is_ready());
+
+        shards.set_disconnected(0);
+        assert!(!shards.is_ready());
+        shards.set_ready(0, 4);
+        shards.set_shutting_down();
+        assert!(!shards.is_ready());
+    }
+
+    #[test]
+    fn autosharding_expects_the_reported_total() {
+        let mut shards = Shards::new(None);
+        shards.claim_global_commands();
+        shards.set_ready(0, 2);
+        assert!(!shards.is_ready());
+        shards.set_ready(1, 2);
+        assert!(shards.is_ready());
+    }
+}
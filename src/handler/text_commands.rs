@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde_json::{json, Map, Value};
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::prelude::TypeMapKey;
+
+use crate::storage::{JsonStore, StorageError};
+
+use super::command_details::channel_permissions;
+use super::HandlerError;
+
+/// Per-guild prefixes for text commands. Guilds without an entry don't get
+/// text commands at all.
+#[derive(Debug)]
+pub struct TextPrefixes(JsonStore<HashMap<GuildId, String>>);
+
+impl TypeMapKey for TextPrefixes {
+    type Value = TextPrefixes;
+}
+
+impl Default for TextPrefixes {
+    fn default() -> Self {
+        TextPrefixes(JsonStore::open("prefixes"))
+    }
+}
+
+impl TextPrefixes {
+    pub fn get(&self, guild_id: GuildId) -> Option<&str> {
+        self.0.get().get(&guild_id).map(|p| p.as_str())
+    }
+
+    pub fn set(&mut self, guild_id: GuildId, prefix: Option<String>) -> Result<(), StorageError> {
+        self.0.update(|prefixes| match prefix {
+            Some(prefix) => {
+                prefixes.insert(guild_id, prefix);
+            }
+            None => {
+                prefixes.remove(&guild_id);
+            }
+        })
+    }
+}
+
+/// Splits on whitespace, keeping "double quoted" runs together.
+pub fn tokenize(input: &str) -> VecDeque<String> {
+    let mut tokens = VecDeque::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push_back(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push_back(current);
+    }
+    tokens
+}
+
+fn parse_mention(token: &str, prefix: &str) -> Option<u64> {
+    let inner = token
+        .strip_prefix(prefix)
+        .and_then(|t| t.strip_suffix('>'))
+        .map(|t| t.trim_start_matches('!'));
+    inner.unwrap_or(token).parse().ok()
+}
+
+/// Ids referenced by user, channel and role options, to be resolved before
+/// the interaction is built.
+#[derive(Debug, Default)]
+pub struct Mentions {
+    users: HashSet<UserId>,
+    channels: HashSet<ChannelId>,
+    roles: HashSet<RoleId>,
+}
+
+fn option_kind(definition: &Value) -> Option<CommandOptionType> {
+    definition
+        .get("type")
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+}
+
+fn definition_name(definition: &Value) -> &str {
+    definition
+        .get("name")
+        .and_then(|n| n.as_str())
+        .unwrap_or_default()
+}
+
+fn convert_value(
+    kind: CommandOptionType,
+    name: &str,
+    raw: &str,
+    mentions: &mut Mentions,
+) -> Result<Value, HandlerError> {
+    let invalid = || HandlerError::InvalidArgument(format!("{}: {}", name, raw));
+    Ok(match kind {
+        CommandOptionType::String => Value::from(raw),
+        CommandOptionType::Integer => Value::from(raw.parse::<i64>().map_err(|_| invalid())?),
+        CommandOptionType::Number => Value::from(raw.parse::<f64>().map_err(|_| invalid())?),
+        CommandOptionType::Boolean => match raw.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Value::from(true),
+            "false" | "no" | "off" | "0" => Value::from(false),
+            _ => return Err(invalid()),
+        },
+        CommandOptionType::User | CommandOptionType::Mentionable => {
+            let id = parse_mention(raw, "<@").ok_or_else(invalid)?;
+            mentions.users.insert(UserId(id));
+            Value::from(id.to_string())
+        }
+        CommandOptionType::Channel => {
+            let id = parse_mention(raw, "<#").ok_or_else(invalid)?;
+            mentions.channels.insert(ChannelId(id));
+            Value::from(id.to_string())
+        }
+        CommandOptionType::Role => {
+            let id = parse_mention(raw, "<@&").ok_or_else(invalid)?;
+            mentions.roles.insert(RoleId(id));
+            Value::from(id.to_string())
+        }
+        _ => return Err(invalid()),
+    })
+}
+
+/// Holds `value` to the choices, bounds and lengths of its definition, as
+/// Discord does for slash commands. Choices can also be given by name.
+fn check_value(definition: &Value, name: &str, value: Value) -> Result<Value, HandlerError> {
+    let invalid = |why: String| HandlerError::InvalidArgument(format!("{} {}", name, why));
+    if let Some(choices) = definition
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .filter(|c| !c.is_empty())
+    {
+        let chosen = choices.iter().find(|choice| {
+            choice.get("value") == Some(&value)
+                || matches!(
+                    (choice.get("name").and_then(|n| n.as_str()), value.as_str()),
+                    (Some(choice), Some(given)) if choice.eq_ignore_ascii_case(given)
+                )
+        });
+        let names: Vec<String> = choices
+            .iter()
+            .filter_map(|c| c.get("value"))
+            .map(|v| v.as_str().map_or_else(|| v.to_string(), String::from))
+            .collect();
+        return match chosen.and_then(|c| c.get("value")) {
+            Some(chosen) => Ok(chosen.clone()),
+            None => Err(invalid(format!("must be one of {}", names.join(", ")))),
+        };
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(min) = definition.get("min_value").and_then(|m| m.as_f64()) {
+            if number < min {
+                return Err(invalid(format!("must be at least {}", min)));
+            }
+        }
+        if let Some(max) = definition.get("max_value").and_then(|m| m.as_f64()) {
+            if number > max {
+                return Err(invalid(format!("must be at most {}", max)));
+            }
+        }
+    }
+    if let (Some(text), Some(CommandOptionType::String)) = (value.as_str(), option_kind(definition))
+    {
+        let length = text.chars().count() as u64;
+        if let Some(min) = definition.get("min_length").and_then(|m| m.as_u64()) {
+            if length < min {
+                return Err(invalid(format!("must be at least {} characters", min)));
+            }
+        }
+        if let Some(max) = definition.get("max_length").and_then(|m| m.as_u64()) {
+            if length > max {
+                return Err(invalid(format!("must be at most {} characters", max)));
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Maps tokens onto a command's option definitions the way Discord would
+/// fill them in: one token picks each subcommand level, then options are
+/// filled positionally and a trailing string option takes the rest.
+pub fn parse_options(
+    definitions: &[Value],
+    tokens: &mut VecDeque<String>,
+    mentions: &mut Mentions,
+) -> Result<Vec<Value>, HandlerError> {
+    let is_subcommand = |d: &Value| {
+        matches!(
+            option_kind(d),
+            Some(CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup)
+        )
+    };
+
+    if definitions.first().is_some_and(is_subcommand) {
+        let token = tokens.pop_front().ok_or(HandlerError::EmptyCommand)?;
+        let definition = definitions
+            .iter()
+            .find(|d| definition_name(d).eq_ignore_ascii_case(&token))
+            .ok_or(HandlerError::UnrecognizedCommand(token))?;
+        let sub_definitions = definition
+            .get("options")
+            .and_then(|o| o.as_array())
+            .cloned()
+            .unwrap_or_default();
+        return Ok(vec![json!({
+            "name": definition_name(definition),
+            "type": definition.get("type"),
+            "options": parse_options(&sub_definitions, tokens, mentions)?,
+        })]);
+    }
+
+    let mut options = vec![];
+    for (i, definition) in definitions.iter().enumerate() {
+        let kind = option_kind(definition).ok_or(HandlerError::UnexpectedData)?;
+        let name = definition_name(definition);
+        let raw = if i + 1 == definitions.len() && kind == CommandOptionType::String {
+            let rest: Vec<String> = tokens.drain(..).collect();
+            Some(rest.join(" ")).filter(|r| !r.is_empty())
+        } else {
+            tokens.pop_front()
+        };
+        match raw {
+            Some(raw) => options.push(json!({
+                "name": name,
+                "type": definition.get("type"),
+                "value": check_value(definition, name, convert_value(kind, name, &raw, mentions)?)?,
+            })),
+            None if definition.get("required") == Some(&Value::Bool(true)) => {
+                return Err(HandlerError::InvalidArgument(format!("missing {}", name)));
+            }
+            None => {}
+        }
+    }
+    Ok(options)
+}
+
+async fn resolve_mentions(
+    context: &Context,
+    guild_id: GuildId,
+    mentions: &Mentions,
+) -> Result<Value, HandlerError> {
+    let mut users = Map::new();
+    let mut members = Map::new();
+    for user_id in &mentions.users {
+        let user = user_id.to_user(context).await?;
+        users.insert(user_id.to_string(), serde_json::to_value(&user)?);
+        if let Ok(member) = guild_id.member(context, *user_id).await {
+            members.insert(user_id.to_string(), serde_json::to_value(&member)?);
+        }
+    }
+
+    let mut channels = Map::new();
+    for channel_id in &mentions.channels {
+        let channel = channel_id.to_channel(context).await?;
+        channels.insert(channel_id.to_string(), serde_json::to_value(&channel)?);
+    }
+
+    let mut roles = Map::new();
+    if !mentions.roles.is_empty() {
+        let guild_roles = guild_id.roles(context).await?;
+        for role_id in &mentions.roles {
+            let role = guild_roles
+                .get(role_id)
+                .ok_or_else(|| HandlerError::InvalidArgument(format!("<@&{}>", role_id)))?;
+            roles.insert(role_id.to_string(), serde_json::to_value(role)?);
+        }
+    }
+
+    Ok(json!({
+        "users": users,
+        "members": members,
+        "channels": channels,
+        "roles": roles,
+    }))
+}
+
+/// Builds the interaction a slash command with the same arguments would
+/// have produced, so text commands can share the slash command handlers.
+pub async fn interaction_from_message(
+    context: &Context,
+    msg: &Message,
+    definition: CreateApplicationCommand,
+    mut tokens: VecDeque<String>,
+) -> Result<ApplicationCommandInteraction, HandlerError> {
+    let guild_id = msg.guild_id.ok_or(HandlerError::NotGuild)?;
+    let definition = serde_json::to_value(&definition.0)?;
+    let option_definitions = definition
+        .get("options")
+        .and_then(|o| o.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut mentions = Mentions::default();
+    let options = parse_options(&option_definitions, &mut tokens, &mut mentions)?;
+    let resolved = resolve_mentions(context, guild_id, &mentions).await?;
+
+    let author = guild_id.member(context, msg.author.id).await?;
+    let permissions = channel_permissions(context, guild_id, msg.channel_id, &author).await?;
+    let mut member = serde_json::to_value(&author)?;
+    member["permissions"] = Value::from(permissions.bits().to_string());
+
+    let interaction = json!({
+        "id": msg.id.to_string(),
+        "application_id": context.cache.current_user_id().to_string(),
+        "type": 2,
+        "data": {
+            "id": "0",
+            "name": definition_name(&definition),
+            "type": 1,
+            "options": options,
+            "resolved": resolved,
+        },
+        "guild_id": guild_id.to_string(),
+        "channel_id": msg.channel_id.to_string(),
+        "member": member,
+        "user": msg.author,
+        "token": "",
+        "version": 1,
+        "locale": "en-US",
+    });
+    Ok(serde_json::from_value(interaction)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::guild::Member;
+    use serenity::model::permissions::Permissions;
+
+    use super::*;
+    use crate::testing::{fixtures, MockDiscord};
+
+    fn definitions() -> Vec<Value> {
+        let mut cmd = CreateApplicationCommand::default();
+        cmd.name("test")
+            .description("test")
+            .create_option(|o| {
+                o.kind(CommandOptionType::Integer)
+                    .name("count")
+                    .description("count")
+                    .min_int_value(1)
+                    .max_int_value(100)
+                    .required(true)
+            })
+            .create_option(|o| {
+                o.kind(CommandOptionType::String)
+                    .name("style")
+                    .description("style")
+                    .add_string_choice("Buttons", "buttons")
+                    .add_string_choice("Select menu", "select")
+            })
+            .create_option(|o| {
+                o.kind(CommandOptionType::String)
+                    .name("title")
+                    .description("title")
+                    .max_length(5)
+            });
+        let cmd = serde_json::to_value(&cmd.0).unwrap();
+        cmd["options"].as_array().unwrap().clone()
+    }
+
+    fn parse(input: &str) -> Result<Vec<Value>, HandlerError> {
+        parse_options(
+            &definitions(),
+            &mut tokenize(input),
+            &mut Mentions::default(),
+        )
+    }
+
+    #[test]
+    fn options_follow_their_definition() {
+        let options = parse("5 \"select menu\" hello").unwrap();
+        assert_eq!(options[0]["value"], 5);
+        assert_eq!(options[1]["value"], "select");
+        assert_eq!(options[2]["value"], "hello");
+        assert_eq!(parse("100 buttons").unwrap()[1]["value"], "buttons");
+
+        for (input, problem) in [
+            ("0", "count must be at least 1"),
+            ("101", "count must be at most 100"),
+            ("5 dropdown", "style must be one of buttons, select"),
+            ("5 buttons toolong", "title must be at most 5 characters"),
+        ] {
+            match parse(input) {
+                Err(HandlerError::InvalidArgument(message)) => assert_eq!(message, problem),
+                other => panic!("{} gave {:?}", input, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn permissions_are_those_of_the_channel() {
+        let discord = MockDiscord::start().await;
+        let mut guild = fixtures::guild();
+        let mut everyone = fixtures::role(fixtures::GUILD_ID, "@everyone", 0);
+        // View channel, send messages and manage messages.
+        everyone["permissions"] = json!((1u64 << 10 | 1 << 11 | 1 << 13).to_string());
+        guild["roles"] = json!([everyone]);
+        discord.route("GET", &format!("/guilds/{}", fixtures::GUILD_ID), guild);
+        discord.route(
+            "GET",
+            &format!("/channels/{}", fixtures::CHANNEL_ID),
+            json!({
+                "id": fixtures::CHANNEL_ID.to_string(),
+                "guild_id": fixtures::GUILD_ID.to_string(),
+                "type": 0,
+                "name": "general",
+                "position": 0,
+                "nsfw": false,
+                "permission_overwrites": [{
+                    "id": fixtures::GUILD_ID.to_string(),
+                    "type": 0,
+                    "allow": "0",
+                    "deny": (1u64 << 13).to_string(),
+                }],
+            }),
+        );
+        let context = discord.context();
+        let member: Member =
+            serde_json::from_value(fixtures::member(fixtures::INVOKER_ID, "invoker", None))
+                .unwrap();
+
+        let permissions = channel_permissions(
+            &context,
+            GuildId(fixtures::GUILD_ID),
+            ChannelId(fixtures::CHANNEL_ID),
+            &member,
+        )
+        .await
+        .unwrap();
+
+        assert!(permissions.contains(Permissions::SEND_MESSAGES));
+        assert!(!permissions.contains(Permissions::MANAGE_MESSAGES));
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::model::user::User;
+use serenity::model::Timestamp;
+use serenity::prelude::TypeMapKey;
+use tracing::*;
+
+use crate::storage::{JsonStore, StorageError};
+
+use super::hierarchy::check_role_hierarchy;
+use super::Handler;
+
+pub const DEFAULT_JOIN_MESSAGE: &str =
+    "Welcome {user} to {server}! You are member #{member_count}.";
+pub const DEFAULT_LEAVE_MESSAGE: &str = "{username} has left {server}.";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WelcomeSettings {
+    pub channel: Option<ChannelId>,
+    pub join_message: Option<String>,
+    pub leave_message: Option<String>,
+    pub dm_message: Option<String>,
+    pub auto_role: Option<RoleId>,
+}
+
+impl WelcomeSettings {
+    pub fn join_message(&self) -> &str {
+        self.join_message.as_deref().unwrap_or(DEFAULT_JOIN_MESSAGE)
+    }
+
+    pub fn leave_message(&self) -> &str {
+        self.leave_message
+            .as_deref()
+            .unwrap_or(DEFAULT_LEAVE_MESSAGE)
+    }
+}
+
+#[derive(Debug)]
+pub struct Welcome(JsonStore<HashMap<GuildId, WelcomeSettings>>);
+
+impl TypeMapKey for Welcome {
+    type Value = Welcome;
+}
+
+impl Default for Welcome {
+    fn default() -> Self {
+        Welcome(JsonStore::open("welcome"))
+    }
+}
+
+impl Welcome {
+    #[cfg(test)]
+    pub fn temporary() -> Welcome {
+        Welcome(JsonStore::temporary("welcome"))
+    }
+
+    pub fn settings(&self, guild_id: GuildId) -> Option<&WelcomeSettings> {
+        self.0.get().get(&guild_id)
+    }
+
+    pub fn set(
+        &mut self,
+        guild_id: GuildId,
+        settings: Option<WelcomeSettings>,
+    ) -> Result<(), StorageError> {
+        self.0.update(|guilds| match settings {
+            Some(settings) => {
+                guilds.insert(guild_id, settings);
+            }
+            None => {
+                guilds.remove(&guild_id);
+            }
+        })
+    }
+}
+
+/// Values substituted into welcome and goodbye templates.
+#[derive(Clone, Debug)]
+pub struct TemplateValues {
+    pub user: User,
+    pub server: String,
+    pub member_count: u64,
+}
+
+impl TemplateValues {
+    pub fn new(context: &Context, guild_id: GuildId, user: &User) -> TemplateValues {
+        let (server, member_count) = context
+            .cache
+            .guild_field(guild_id, |g| (g.name.clone(), g.member_count))
+            .unwrap_or_default();
+        TemplateValues {
+            user: user.clone(),
+            server,
+            member_count,
+        }
+    }
+}
+
+fn format_age(seconds: i64) -> String {
+    let days = seconds.max(0) / (24 * 60 * 60);
+    match days {
+        0 => String::from("less than a day"),
+        1 => String::from("1 day"),
+        2..=364 => format!("{} days", days),
+        _ => format!("{} years", days / 365),
+    }
+}
+
+/// Fills in `{user}`, `{username}`, `{server}`, `{member_count}` and
+/// `{account_age}`.
+pub fn render_template(template: &str, values: &TemplateValues) -> String {
+    let age = Timestamp::now().unix_timestamp() - values.user.created_at().unix_timestamp();
+    template
+        .replace("{user}", &format!("<@{}>", values.user.id))
+        .replace("{username}", &values.user.name)
+        .replace("{server}", &values.server)
+        .replace("{member_count}", &values.member_count.to_string())
+        .replace("{account_age}", &format_age(age))
+}
+
+impl Handler {
+    async fn welcome_settings(
+        &self,
+        context: &Context,
+        guild_id: GuildId,
+    ) -> Option<WelcomeSettings> {
+        context
+            .data
+            .read()
+            .await
+            .get::<Welcome>()?
+            .settings(guild_id)
+            .cloned()
+    }
+
+    pub async fn welcome_member(&self, context: &Context, member: &Member) {
+        let Some(settings) = self.welcome_settings(context, member.guild_id).await else {
+            return;
+        };
+        let values = TemplateValues::new(context, member.guild_id, &member.user);
+
+        if let Some(role) = settings.auto_role {
+            // The role may have been moved above the bot or turned into a
+            // managed one since it was picked.
+            let granted = match check_role_hierarchy(context, member.guild_id, None, &[role]).await
+            {
+                Ok(()) => context
+                    .http
+                    .add_member_role(
+                        member.guild_id.0,
+                        member.user.id.0,
+                        role.0,
+                        Some("Auto-role"),
+                    )
+                    .await
+                    .map_err(Into::into),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = granted {
+                warn!(?err, ?role, "could not assign auto-role");
+            }
+        }
+        if let Some(channel_id) = settings.channel {
+            let text = render_template(settings.join_message(), &values);
+            if let Err(err) = channel_id.say(context, text).await {
+                warn!(?err, ?channel_id, "could not post welcome message");
+            }
+        }
+        if let Some(dm) = &settings.dm_message {
+            let text = render_template(dm, &values);
+            if let Err(err) = member
+                .user
+                .direct_message(context, |m| m.content(text))
+                .await
+            {
+                debug!(?err, "could not send welcome DM");
+            }
+        }
+    }
+
+    pub async fn farewell_member(&self, context: &Context, guild_id: GuildId, user: &User) {
+        let Some(settings) = self.welcome_settings(context, guild_id).await else {
+            return;
+        };
+        let Some(channel_id) = settings.channel else {
+            return;
+        };
+        let values = TemplateValues::new(context, guild_id, user);
+        let text = render_template(settings.leave_message(), &values);
+        if let Err(err) = channel_id.say(context, text).await {
+            warn!(?err, ?channel_id, "could not post goodbye message");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixtures;
+
+    #[test]
+    fn fills_in_placeholders() {
+        let user: User =
+            serde_json::from_value(fixtures::user(fixtures::INVOKER_ID, "invoker")).unwrap();
+        let values = TemplateValues {
+            user,
+            server: String::from("Test Server"),
+            member_count: 42,
+        };
+        assert_eq!(
+            render_template(DEFAULT_JOIN_MESSAGE, &values),
+            format!(
+                "Welcome <@{}> to Test Server! You are member #42.",
+                fixtures::INVOKER_ID
+            )
+        );
+        assert_eq!(
+            render_template("{username} left {server} {unknown}", &values),
+            "invoker left Test Server {unknown}"
+        );
+        // Fixture ids date back to when Discord started counting.
+        assert!(render_template("{account_age}", &values).ends_with(" years"));
+    }
+
+    #[test]
+    fn rounds_account_ages() {
+        let day = 24 * 60 * 60;
+        assert_eq!(format_age(-5), "less than a day");
+        assert_eq!(format_age(day - 1), "less than a day");
+        assert_eq!(format_age(day), "1 day");
+        assert_eq!(format_age(30 * day), "30 days");
+        assert_eq!(format_age(800 * day), "2 years");
+    }
+}
@@ -0,0 +1,201 @@
+//! A small HTTP server for the orchestrator: `/healthz` answers while the
+//! process is up, `/readyz` once every shard is ready and commands are
+//! registered, and `/metrics`
+//! exports [`Metrics`] for Prometheus.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::client::bridge::gateway::{ShardId, ShardRunnerInfo};
+use serenity::prelude::{Mutex, RwLock, TypeMap};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::*;
+
+use crate::handler::metrics::Metrics;
+use crate::handler::shards::Shards;
+
+/// The shard runners as tracked by the shard manager.
+pub type ShardRunners = Arc<Mutex<HashMap<ShardId, ShardRunnerInfo>>>;
+
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+/// How long a client gets to send its request before it's dropped.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Reply {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Reply {
+    fn text(status: &'static str, body: impl Into<String>) -> Reply {
+        Reply {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+}
+
+pub async fn serve(
+    addr: SocketAddr,
+    data: Arc<RwLock<TypeMap>>,
+    runners: ShardRunners,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!(%addr, "health server listening");
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle(stream, data.clone(), runners.clone()));
+    }
+}
+
+/// Reads up to the end of the request head, giving up on clients that take
+/// longer than `limit` or send too much.
+async fn read_head(stream: &mut (impl AsyncRead + Unpin), limit: Duration) -> Option<Vec<u8>> {
+    let read = async {
+        let mut buffer = vec![];
+        let mut chunk = [0; 1024];
+        while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return None,
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            }
+            if buffer.len() > MAX_REQUEST_LEN {
+                return None;
+            }
+        }
+        Some(buffer)
+    };
+    tokio::time::timeout(limit, read).await.ok().flatten()
+}
+
+async fn handle(mut stream: TcpStream, data: Arc<RwLock<TypeMap>>, runners: ShardRunners) {
+    let Some(buffer) = read_head(&mut stream, REQUEST_TIMEOUT).await else {
+        return;
+    };
+
+    let head = String::from_utf8_lossy(&buffer);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let reply = route(method, path, &data, &runners).await;
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        reply.status,
+        reply.content_type,
+        reply.body.len(),
+        reply.body
+    );
+    if let Err(err) = stream.write_all(response.as_bytes()).await {
+        debug!(?err, "could not answer health request");
+    }
+}
+
+async fn is_ready(data: &RwLock<TypeMap>) -> bool {
+    data.read()
+        .await
+        .get::<Shards>()
+        .is_some_and(Shards::is_ready)
+}
+
+async fn route(method: &str, path: &str, data: &RwLock<TypeMap>, runners: &ShardRunners) -> Reply {
+    if method != "GET" {
+        return Reply::text("405 Method Not Allowed", "method not allowed\n");
+    }
+    let path = path.split('?').next().unwrap_or_default();
+    match path {
+        "/healthz" => Reply::text("200 OK", "ok\n"),
+        "/readyz" => {
+            if is_ready(data).await {
+                Reply::text("200 OK", "ready\n")
+            } else {
+                Reply::text("503 Service Unavailable", "not ready\n")
+            }
+        }
+        "/metrics" => {
+            let mut latencies: Vec<(u64, _)> = runners
+                .lock()
+                .await
+                .iter()
+                .map(|(id, runner)| (id.0, runner.latency))
+                .collect();
+            latencies.sort_by_key(|(id, _)| *id);
+            let ready = is_ready(data).await;
+            let body = match data.read().await.get::<Metrics>() {
+                Some(metrics) => metrics.render(ready, &latencies),
+                None => Metrics::default().render(ready, &latencies),
+            };
+            Reply {
+                status: "200 OK",
+                content_type: "text/plain; version=0.0.4; charset=utf-8",
+                body,
+            }
+        }
+        _ => Reply::text("404 Not Found", "not found\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn readiness_follows_the_shards() {
+        let data = RwLock::new(TypeMap::new());
+        let runners = ShardRunners::default();
+        assert_eq!(
+            route("GET", "/healthz", &data, &runners).await.status,
+            "200 OK"
+        );
+        assert_eq!(
+            route("GET", "/readyz", &data, &runners).await.status,
+            "503 Service Unavailable"
+        );
+
+        let mut shards = Shards::new(Some(1));
+        shards.claim_global_commands();
+        shards.set_ready(0, 1);
+        data.write().await.insert::<Shards>(shards);
+        assert_eq!(
+            route("GET", "/readyz", &data, &runners).await.status,
+            "200 OK"
+        );
+        let reply = route("GET", "/metrics?x=1", &data, &runners).await;
+        assert!(reply.body.contains("bot_ready 1"));
+        assert_eq!(
+            route("GET", "/", &data, &runners).await.status,
+            "404 Not Found"
+        );
+        assert_eq!(
+            route("POST", "/healthz", &data, &runners).await.status,
+            "405 Method Not Allowed"
+        );
+    }
+
+    #[tokio::test]
+    async fn drops_slow_requests() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(b"GET /healthz HTTP/1.1\r\n")
+            .await
+            .unwrap();
+        assert!(read_head(&mut server, Duration::from_millis(20))
+            .await
+            .is_none());
+
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(b"GET /healthz HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        assert!(read_head(&mut server, Duration::from_millis(20))
+            .await
+            .is_some());
+    }
+}
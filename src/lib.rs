@@ -1,41 +1,156 @@
 mod builders;
 mod commands;
+pub mod config;
 pub mod handler;
+pub mod health;
+mod storage;
+#[cfg(test)]
+mod testing;
 pub mod util;
 
 use commands::CommandsEnum;
-use handler::{Handler, HandlerError};
-use std::time::SystemTime;
+use config::Config;
+use handler::{
+    analytics::{Analytics, InteractionRecord},
+    automod::Automod,
+    bot_status::BotStatus,
+    cases::CaseLog,
+    cooldown::Cooldowns,
+    errors::{error_chain, panic_message, user_message, ErrorId},
+    flush,
+    invocation::Invocation,
+    member_growth::{MemberChange, MemberGrowth},
+    metrics::Metrics,
+    presence::PresenceTracker,
+    response::CommandResponse,
+    role_menus::{is_role_menu, RoleMenus},
+    server_log::ServerLog,
+    shards::{ShardManagerContainer, Shards},
+    text_commands::{interaction_from_message, tokenize, TextPrefixes},
+    welcome::Welcome,
+    Handler, HandlerError,
+};
+use std::panic::AssertUnwindSafe;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use tracing::*;
 
 use serenity::{
     async_trait,
+    client::bridge::gateway::{event::ShardStageUpdateEvent, ShardManager},
+    futures::FutureExt,
+    gateway::ConnectionStage,
     model::prelude::{
-        interaction::{application_command::ApplicationCommandInteraction, Interaction},
-        Message, Ready,
+        interaction::Interaction, ChannelId, Guild, GuildId, Member, Message, MessageId,
+        MessageUpdateEvent, Presence, Ready, User,
     },
-    prelude::{Context, EventHandler, GatewayIntents},
+    prelude::{Context, EventHandler, Mutex, RwLock, TypeMap},
     Client,
 };
 
-use serenity::model::gateway::Activity;
-use serenity::prelude::TypeMapKey;
-use tokio::try_join;
-
-#[derive(Clone, Debug)]
-struct LastChanged(SystemTime);
+use tokio::{pin, select, time::sleep, try_join};
 
-impl TypeMapKey for LastChanged {
-    type Value = LastChanged;
-}
+use crate::commands::{global::GlobalCommands, guild::GuildCommands};
 
-use crate::commands::guild::GuildCommands;
+/// How long a command may run before it's deferred, leaving some of the
+/// three seconds Discord waits for an answer to send the deferral.
+const DEFER_AFTER: Duration = Duration::from_millis(2000);
 
 #[async_trait]
 impl EventHandler for Handler {
-    #[instrument(skip(self, _context))]
-    async fn message(&self, _context: Context, _msg: Message) {
-        info!("handling message");
+    #[instrument(skip(self, context, msg))]
+    async fn message(&self, context: Context, msg: Message) {
+        trace!("handling message");
+        self.cache_message(&context, &msg).await;
+        if self.run_automod(&context, &msg).await {
+            return;
+        }
+        self.try_handle_text_command(&context, &msg).await;
+    }
+
+    #[instrument(skip(self, context, _old_if_available, _new))]
+    async fn message_update(
+        &self,
+        context: Context,
+        _old_if_available: Option<Message>,
+        _new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        self.log_message_update(&context, &event).await;
+    }
+
+    #[instrument(skip(self, context))]
+    async fn message_delete(
+        &self,
+        context: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        self.log_message_delete(&context, channel_id, deleted_message_id, guild_id)
+            .await;
+    }
+
+    #[instrument(skip(self, context))]
+    async fn message_delete_bulk(
+        &self,
+        context: Context,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        guild_id: Option<GuildId>,
+    ) {
+        self.log_message_delete_bulk(
+            &context,
+            channel_id,
+            &multiple_deleted_messages_ids,
+            guild_id,
+        )
+        .await;
+    }
+
+    #[instrument(skip(self, context, new_member))]
+    async fn guild_member_addition(&self, context: Context, new_member: Member) {
+        self.log_member_join(&context, &new_member).await;
+        self.welcome_member(&context, &new_member).await;
+        self.track_member_count(&context, new_member.guild_id, MemberChange::Join)
+            .await;
+    }
+
+    #[instrument(skip(self, context, user, _member_data_if_available))]
+    async fn guild_member_removal(
+        &self,
+        context: Context,
+        guild_id: GuildId,
+        user: User,
+        _member_data_if_available: Option<Member>,
+    ) {
+        self.log_member_leave(&context, guild_id, &user).await;
+        self.farewell_member(&context, guild_id, &user).await;
+        self.track_member_count(&context, guild_id, MemberChange::Leave)
+            .await;
+    }
+
+    #[instrument(skip(self, context, old_if_available, new))]
+    async fn guild_member_update(
+        &self,
+        context: Context,
+        old_if_available: Option<Member>,
+        new: Member,
+    ) {
+        self.log_member_update(&context, old_if_available.as_ref(), &new)
+            .await;
+    }
+
+    #[instrument(skip(self, context, new_data))]
+    async fn presence_update(&self, context: Context, new_data: Presence) {
+        self.track_presence(&context, &new_data).await;
+    }
+
+    #[instrument(skip(self, context, guild))]
+    async fn guild_create(&self, context: Context, guild: Guild, _is_new: bool) {
+        self.track_member_count(&context, guild.id, MemberChange::Snapshot)
+            .await;
+        self.track_guild_presences(&context, &guild).await;
     }
 
     #[instrument(skip(self, context))]
@@ -46,98 +161,275 @@ impl EventHandler for Handler {
             guilds = ?ready.guilds.iter().map(|ug| ug.id).collect::<Vec<_>>()
         );
 
-        if let Err(err) = try_join!(self.setup_guild_commands(&context, ready),) {
+        let [shard, total] = ready.shard.unwrap_or([0, 1]);
+        if let Err(err) = try_join!(
+            self.setup_guild_commands(&context, ready.guilds.iter().map(|ug| ug.id)),
+            self.setup_global_commands(&context),
+        ) {
             error!(?err, "could not setup application commands, shutting down");
             context.shard.shutdown_clean();
             return;
         }
+        context
+            .data
+            .write()
+            .await
+            .entry::<Shards>()
+            .or_insert_with(Shards::default)
+            .set_ready(shard, total);
+        self.start_status_rotation(&context).await;
+        self.start_flush(&context).await;
+    }
+
+    async fn shard_stage_update(&self, context: Context, event: ShardStageUpdateEvent) {
+        debug!(shard = event.shard_id.0, old = ?event.old, new = ?event.new, "shard stage changed");
+        if event.new != ConnectionStage::Connected {
+            if let Some(shards) = context.data.write().await.get_mut::<Shards>() {
+                shards.set_disconnected(event.shard_id.0);
+            }
+        }
     }
 
     #[instrument(skip(self, context))]
     async fn interaction_create(&self, context: Context, interaction: Interaction) {
         if let Interaction::ApplicationCommand(cmd) = interaction {
-            let last_changed = (context.data.read().await).get::<LastChanged>().cloned();
-            let now = SystemTime::now();
-            if let Some(last_changed) = last_changed {
-                let elapsed = now.duration_since(last_changed.0).unwrap();
-                if elapsed.as_secs() > 20 {
-                    context
-                        .set_activity(Activity::watching(&cmd.member.as_ref().unwrap().user.name))
-                        .await;
-                    let mut data = context.data.write().await;
-                    data.insert::<LastChanged>(LastChanged(now));
+            self.dispatch(&context, &Invocation::from(cmd)).await;
+        } else if let Interaction::MessageComponent(component) = interaction {
+            if !is_role_menu(&component.data.custom_id) {
+                return;
+            }
+            if let Err(err) = self.handle_role_menu(&context, &component).await {
+                debug!(?err, "role menu interaction failed");
+                if let Err(e) = component
+                    .create_interaction_response(&context, |res| {
+                        res.interaction_response_data(|d| {
+                            d.ephemeral(true).content(err.to_string())
+                        })
+                    })
+                    .await
+                {
+                    error!(err = ?e, "could not answer role menu interaction");
                 }
+            }
+        }
+    }
+}
+
+impl Handler {
+    /// Runs a slash or text command and reports its outcome.
+    async fn dispatch(&self, context: &Context, cmd: &Invocation) {
+        let started = Instant::now();
+        // A panicking command shouldn't take the event task down with it.
+        let handle_res = AssertUnwindSafe(self.run_command(context, cmd))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| Err(HandlerError::Panic(panic_message(&*panic))));
+
+        let latency = cmd
+            .answered_at()
+            .map_or_else(|| started.elapsed(), |at| at - started);
+        let record = InteractionRecord::new(cmd, latency, &handle_res);
+        {
+            let mut data = context.data.write().await;
+            data.entry::<Metrics>()
+                .or_insert_with(Metrics::default)
+                .record(&record);
+            data.entry::<Analytics>()
+                .or_insert_with(Analytics::default)
+                .record(record);
+        }
+
+        if let Err(err) = handle_res {
+            let id = ErrorId::new();
+            if err.is_internal() {
+                error!(%id, chain = %error_chain(&err), ?err, "error during interaction processing");
             } else {
-                context
-                    .set_activity(Activity::watching(&cmd.member.as_ref().unwrap().user.name))
-                    .await;
-                let mut data = context.data.write().await;
-                data.insert::<LastChanged>(LastChanged(now));
+                debug!(%id, ?err, "command failed");
+            }
+            // A deferred command shows the bot as thinking until it's answered.
+            if err.should_followup() || cmd.is_deferred() {
+                if let Err(e) = cmd.send_error(context, &user_message(&err, id)).await {
+                    error!(
+                        err = ?e,
+                        %id,
+                        "could not send follow-up message",
+                    );
+                }
             }
+            self.report_error(context, cmd, &err, id).await;
+        };
+    }
 
-            let handle_res = match self
-                .try_handle_commands::<GuildCommands>(&context, &cmd)
+    async fn run_command(&self, context: &Context, cmd: &Invocation) -> Result<(), HandlerError> {
+        let response = match self
+            .try_handle_commands::<GuildCommands>(context, cmd)
+            .await
+        {
+            Some(r) => r,
+            None => match self
+                .try_handle_commands::<GlobalCommands>(context, cmd)
                 .await
             {
                 Some(r) => r,
                 None => Err(HandlerError::UnrecognizedCommand(cmd.data.name.to_string())),
-            };
-
-            if let Err(err) = handle_res {
-                error!(?err, "error during interaction processing");
-                if err.should_followup() {
-                    if let Err(e) = cmd
-                        .create_followup_message(&context, |msg| {
-                            msg.ephemeral(true).content(err.to_string())
-                        })
-                        .await
-                    {
-                        error!(
-                            err = ?e,
-                            "could not send follow-up message",
-                        );
-                    }
-                }
-            };
-        }
+            },
+        };
+        cmd.respond(context, response?).await
     }
-}
 
-impl Handler {
     #[instrument(skip_all)]
     async fn try_handle_commands<'a, T>(
         &self,
         context: &Context,
-        cmd: &ApplicationCommandInteraction,
-    ) -> Option<Result<(), HandlerError>>
+        cmd: &Invocation,
+    ) -> Option<Result<CommandResponse, HandlerError>>
     where
         T: CommandsEnum,
     {
-        let read = context.data.read().await;
-        if let Some(cmd_map) = read.get::<T>() {
-            if let Some(app_cmd) = cmd_map.get(&cmd.data.id) {
-                trace!(?app_cmd, "handing off to app command handler");
-                Some(app_cmd.handle(cmd, self, context).await)
-            } else {
-                None
-            }
+        let app_cmd = if cmd.is_text() {
+            T::from_str(&cmd.data.name).ok()?
         } else {
-            Some(Err(HandlerError::TypeMapNotFound))
+            match context.data.read().await.get::<T>() {
+                Some(cmd_map) => cmd_map.get(&cmd.data.id).copied()?,
+                None => return Some(Err(HandlerError::TypeMapNotFound)),
+            }
+        };
+
+        if T::GUILD_ONLY && cmd.guild_id.is_none() {
+            return Some(Err(HandlerError::NotGuild));
+        }
+
+        if let Err(err) = self.check_cooldown(context, cmd).await {
+            return Some(Err(err));
+        }
+
+        if app_cmd.is_slow() {
+            if let Err(err) = cmd.defer(context).await {
+                return Some(Err(err));
+            }
         }
+
+        trace!(?app_cmd, "handing off to app command handler");
+        let handling = app_cmd.handle(cmd, self, context);
+        pin!(handling);
+        Some(select! {
+            res = &mut handling => res,
+            _ = sleep(DEFER_AFTER) => {
+                debug!(?app_cmd, "command is taking long, deferring");
+                if let Err(err) = cmd.defer(context).await {
+                    error!(?err, "could not defer command");
+                }
+                handling.await
+            }
+        })
+    }
+
+    async fn check_cooldown(
+        &self,
+        context: &Context,
+        cmd: &Invocation,
+    ) -> Result<(), HandlerError> {
+        let mut data = context.data.write().await;
+        let cooldowns = data.entry::<Cooldowns>().or_insert_with(Cooldowns::default);
+        cooldowns.check(cmd).map_err(|wait| {
+            debug!(?wait, command = %cmd.data.name, "command on cooldown");
+            HandlerError::Cooldown(wait.as_secs_f64().ceil() as u64)
+        })
+    }
+
+    /// Treats `<prefix><command> [args...]` in guilds with a prefix set as
+    /// the matching slash command.
+    async fn try_handle_text_command(&self, context: &Context, msg: &Message) {
+        let Some(guild_id) = msg.guild_id.filter(|_| !msg.author.bot) else {
+            return;
+        };
+        let prefix = match context.data.read().await.get::<TextPrefixes>() {
+            Some(prefixes) => prefixes.get(guild_id).map(str::to_string),
+            None => None,
+        };
+        let Some(rest) = prefix.as_deref().and_then(|p| msg.content.strip_prefix(p)) else {
+            return;
+        };
+
+        let mut tokens = tokenize(rest);
+        let Some(name) = tokens.pop_front().map(|name| name.to_lowercase()) else {
+            return;
+        };
+        if !self.config.is_enabled(&name) {
+            return;
+        }
+        let definition = if let Ok(command) = GuildCommands::from_str(&name) {
+            command.to_application_command()
+        } else if let Ok(command) = GlobalCommands::from_str(&name) {
+            command.to_application_command()
+        } else {
+            return;
+        };
+
+        trace!(%name, "handling text command");
+        let cmd = match interaction_from_message(context, msg, definition, tokens).await {
+            Ok(interaction) => Invocation::from_text(interaction, msg.clone()),
+            Err(err) => {
+                debug!(?err, "could not parse text command");
+                if let Err(e) = msg.reply(context, err.to_string()).await {
+                    error!(err = ?e, "could not answer text command");
+                }
+                return;
+            }
+        };
+        self.dispatch(context, &cmd).await;
     }
 }
 
-pub async fn setup_client(token: String) -> Client {
-    let intents = GatewayIntents::GUILD_MESSAGES
-        | GatewayIntents::DIRECT_MESSAGES
-        | GatewayIntents::MESSAGE_CONTENT
-        | GatewayIntents::GUILD_MEMBERS
-        | GatewayIntents::GUILD_PRESENCES;
+/// Builds the client from a validated `config`.
+pub async fn setup_client(config: Config) -> Result<Client, serenity::Error> {
+    // Stores are opened below, so they need to know where to look first.
+    storage::set_data_dir(config.storage.data_dir.clone());
+    let intents = config
+        .gateway_intents()
+        .expect("intents are checked when the config is validated");
+    let token = config.token.clone();
+    let activity_interval_secs = config.limits.activity_interval_secs;
+    let shard_count = config.sharding.shard_count();
+    let cooldowns = Cooldowns::new(config.cooldowns.cooldown_config());
 
-    let handler = Handler::new().expect("couldn't load log message data from xivapi");
+    let handler = Handler::new(config);
 
-    Client::builder(&token, intents)
+    let client = Client::builder(&token, intents)
+        .type_map_insert::<Shards>(Shards::new(shard_count))
+        .type_map_insert::<Cooldowns>(cooldowns)
+        .type_map_insert::<Analytics>(Analytics::default())
+        .type_map_insert::<Metrics>(Metrics::default())
+        .type_map_insert::<CaseLog>(CaseLog::default())
+        .type_map_insert::<ServerLog>(ServerLog::default())
+        .type_map_insert::<TextPrefixes>(TextPrefixes::default())
+        .type_map_insert::<Automod>(Automod::default())
+        .type_map_insert::<Welcome>(Welcome::default())
+        .type_map_insert::<RoleMenus>(RoleMenus::default())
+        .type_map_insert::<MemberGrowth>(MemberGrowth::default())
+        .type_map_insert::<PresenceTracker>(PresenceTracker::default())
+        .type_map_insert::<BotStatus>(BotStatus::new(activity_interval_secs))
         .event_handler(handler)
+        .await?;
+    client
+        .data
+        .write()
         .await
-        .expect("error creating client")
+        .insert::<ShardManagerContainer>(client.shard_manager.clone());
+    Ok(client)
+}
+
+/// Stops answering readiness probes and closes every shard's connection.
+/// `Client::start` returns once they're all down. Most stores are written on
+/// every change, the rest are flushed here.
+pub async fn shutdown(client_data: &RwLock<TypeMap>, shard_manager: &Mutex<ShardManager>) {
+    let pending = {
+        let mut data = client_data.write().await;
+        if let Some(shards) = data.get_mut::<Shards>() {
+            shards.set_shutting_down();
+        }
+        flush::take_pending(&mut data)
+    };
+    flush::write_all(pending);
+    shard_manager.lock().await.shutdown_all().await;
 }
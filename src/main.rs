@@ -1,29 +1,93 @@
-use discord_bot::setup_client;
+use discord_bot::config::{Config, LogFormat, LoggingConfig, ShardingConfig};
+use discord_bot::{health, setup_client, shutdown};
 use dotenv::dotenv;
-use std::env;
+use std::process;
 
 use tracing::*;
 use tracing_subscriber::EnvFilter;
 
+fn init_logging(logging: &LoggingConfig) {
+    let filter = match &logging.filter {
+        Some(filter) => EnvFilter::new(filter),
+        None => EnvFilter::from_default_env(),
+    };
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match logging.format {
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Compact => subscriber.compact().init(),
+        LogFormat::Full => subscriber.init(),
+    }
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM where there are signals.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("could not listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 #[tokio::main]
 #[instrument]
 async fn main() {
     dotenv().ok();
-    tracing_subscriber::fmt()
-        .pretty()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
-    // Configure the client with your Discord bot token in the environment.
-    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    // Logging is configured by the file, so errors in it can only be printed.
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("Invalid configuration: {}", err);
+        process::exit(1);
+    });
+    init_logging(&config.logging);
+    let health_addr = config.health_addr;
+    let sharding = config.sharding;
 
     // Build our client.
-    let mut client = setup_client(token).await;
+    let mut client = setup_client(config).await.unwrap_or_else(|err| {
+        error!(?err, "could not create the client");
+        process::exit(1);
+    });
+
+    // Serve health checks and metrics when an address is configured.
+    if let Some(addr) = health_addr {
+        let data = client.data.clone();
+        let runners = client.shard_manager.lock().await.runners.clone();
+        tokio::spawn(async move {
+            if let Err(err) = health::serve(addr, data, runners).await {
+                error!(?err, "health server stopped");
+            }
+        });
+    }
+
+    let data = client.data.clone();
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("shutting down");
+        shutdown(&data, &shard_manager).await;
+    });
 
-    // Finally, start a single shard, and start listening to events.
+    // Finally, start the configured shards, and start listening to events.
     //
     // Shards will automatically attempt to reconnect, and will perform
     // exponential backoff until it reconnects.
-    if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+    let started = match sharding {
+        ShardingConfig::Single => client.start().await,
+        ShardingConfig::Auto => client.start_autosharded().await,
+        ShardingConfig::Count { total } => client.start_shards(total).await,
+        ShardingConfig::Range { first, last, total } => {
+            client.start_shard_range([first, last], total).await
+        }
+    };
+    if let Err(why) = started {
+        error!(?why, "client error");
     }
 }
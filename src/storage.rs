@@ -0,0 +1,157 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+use tracing::*;
+
+pub const DEFAULT_DATA_DIR: &str = "data";
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Could not access storage file")]
+    Io(#[from] std::io::Error),
+    #[error("Could not encode stored data")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Sets the directory stores are opened in. Only the first call has an
+/// effect, and it has to happen before any store is opened.
+pub fn set_data_dir(dir: PathBuf) {
+    if DATA_DIR.set(dir).is_err() {
+        warn!("data directory already set");
+    }
+}
+
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = DATA_DIR.get() {
+        return dir.clone();
+    }
+    env::var("DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_DATA_DIR))
+}
+
+/// A value persisted as a JSON file in the data directory, written back on
+/// every update.
+#[derive(Debug)]
+pub struct JsonStore<T> {
+    path: PathBuf,
+    value: T,
+    /// Whether there are changes from [`JsonStore::update_later`] that
+    /// haven't been written yet.
+    dirty: bool,
+}
+
+/// A snapshot of a store, ready to be written from a blocking thread.
+#[derive(Debug)]
+pub struct PendingSave {
+    path: PathBuf,
+    bytes: Vec<u8>,
+}
+
+impl PendingSave {
+    pub fn write(self) -> Result<(), StorageError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, &self.bytes)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl<T> JsonStore<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    pub fn open(name: &str) -> JsonStore<T> {
+        Self::open_or(name, T::default)
+    }
+
+    /// Like [`JsonStore::open`], starting from `initial` instead of the
+    /// default when nothing is stored.
+    pub fn open_or(name: &str, initial: impl FnOnce() -> T) -> JsonStore<T> {
+        Self::open_at_or(data_dir().join(format!("{}.json", name)), initial)
+    }
+
+    /// A store in a fresh file under the system temp directory.
+    #[cfg(test)]
+    pub fn temporary(name: &str) -> JsonStore<T> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let file = format!(
+            "bot-{}-{}-{}.json",
+            std::process::id(),
+            name,
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        Self::open_at_or(env::temp_dir().join(file), T::default)
+    }
+
+    fn open_at_or(path: impl AsRef<Path>, initial: impl FnOnce() -> T) -> JsonStore<T> {
+        let path = path.as_ref().to_path_buf();
+        let value = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                error!(?err, ?path, "stored data is corrupt, starting empty");
+                initial()
+            }),
+            Err(err) if err.kind() == ErrorKind::NotFound => initial(),
+            Err(err) => {
+                error!(?err, ?path, "could not read stored data, starting empty");
+                initial()
+            }
+        };
+        JsonStore {
+            path,
+            value,
+            dirty: false,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Result<R, StorageError> {
+        let res = f(&mut self.value);
+        self.save()?;
+        Ok(res)
+    }
+
+    /// Like [`JsonStore::update`] without writing the file, for values that
+    /// change too often. [`JsonStore::take_pending`] picks the change up.
+    pub fn update_later<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.dirty = true;
+        f(&mut self.value)
+    }
+
+    /// The value to write if it changed since it was last written.
+    pub fn take_pending(&mut self) -> Result<Option<PendingSave>, StorageError> {
+        if !self.dirty {
+            return Ok(None);
+        }
+        let pending = self.snapshot()?;
+        self.dirty = false;
+        Ok(Some(pending))
+    }
+
+    fn snapshot(&self) -> Result<PendingSave, StorageError> {
+        Ok(PendingSave {
+            path: self.path.clone(),
+            bytes: serde_json::to_vec_pretty(&self.value)?,
+        })
+    }
+
+    fn save(&mut self) -> Result<(), StorageError> {
+        self.snapshot()?.write()?;
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+//! Offline harness for running command handlers against a fake Discord.
+//!
+//! [`MockDiscord`] is a local HTTP server the bot's REST client is pointed
+//! at. It records every request and answers from canned routes, so tests can
+//! invoke a handler with a synthetic interaction from [`fixtures`] and assert
+//! on the response it sent.
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use serenity::cache::Cache;
+use serenity::client::bridge::gateway::ShardMessenger;
+use serenity::futures::channel::mpsc;
+use serenity::http::HttpBuilder;
+use serenity::model::event::ReadyEvent;
+use serenity::prelude::{Context, RwLock, TypeMap};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::handler::response::CommandResponse;
+
+const API_PREFIX: &str = "/api/v10";
+
+/// A request the bot sent to the fake Discord.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path without the API version prefix or query, e.g. `/guilds/1`.
+    pub path: String,
+    pub query: Option<String>,
+    pub body: Value,
+}
+
+#[derive(Clone, Debug)]
+struct Route {
+    method: String,
+    path: String,
+    body: Value,
+}
+
+#[derive(Clone, Debug, Default)]
+struct State {
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    routes: Arc<Mutex<Vec<Route>>>,
+}
+
+pub struct MockDiscord {
+    url: String,
+    state: State,
+}
+
+impl MockDiscord {
+    pub async fn start() -> MockDiscord {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let state = State::default();
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, server_state.clone()));
+            }
+        });
+        MockDiscord { url, state }
+    }
+
+    /// Answers `method` requests to `path` (e.g. `/guilds/1`) with `body`.
+    /// Requests without a route get an empty `204 No Content`.
+    pub fn route(&self, method: &str, path: &str, body: Value) -> &MockDiscord {
+        self.state.routes.lock().unwrap().push(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            body,
+        });
+        self
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// The data of every interaction response that was sent.
+    pub fn responses(&self) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.method == "POST" && r.path.ends_with("/callback"))
+            .map(|r| r.body["data"].clone())
+            .collect()
+    }
+
+    /// A context whose REST client talks to this server, with an empty
+    /// type map and a cache that only knows the bot's own user.
+    pub fn context(&self) -> Context {
+        let http = HttpBuilder::new("test-token")
+            .application_id(fixtures::APPLICATION_ID)
+            .proxy(self.url.as_str())
+            .unwrap()
+            .ratelimiter_disabled(true)
+            .build();
+        let cache = Cache::new();
+        let mut ready: ReadyEvent = serde_json::from_value(json!({
+            "v": 10,
+            "user": fixtures::current_user(),
+            "guilds": [],
+            "session_id": "test-session",
+            "shard": null,
+            "application": { "id": fixtures::APPLICATION_ID.to_string(), "flags": 0 },
+        }))
+        .unwrap();
+        cache.update(&mut ready);
+        let (tx, _) = mpsc::unbounded();
+        Context {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+            shard: ShardMessenger::new(tx),
+            shard_id: 0,
+            http: Arc::new(http),
+            cache: Arc::new(cache),
+        }
+    }
+}
+
+/// The embeds of a response as Discord would receive them.
+pub fn embeds(response: &CommandResponse) -> Vec<Value> {
+    response
+        .embeds
+        .iter()
+        .map(|embed| serde_json::to_value(&embed.0).unwrap())
+        .collect()
+}
+
+/// The value of the embed field called `name`.
+pub fn field<'a>(embed: &'a Value, name: &str) -> Option<&'a str> {
+    embed["fields"]
+        .as_array()?
+        .iter()
+        .find(|f| f["name"] == name)?["value"]
+        .as_str()
+}
+
+async fn serve(mut stream: TcpStream, state: State) {
+    let mut buffer = vec![];
+    let mut chunk = [0; 4096];
+    let head_end = loop {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+        }
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let length: usize = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    while buffer.len() < head_end + length {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+        }
+    }
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (target, None),
+    };
+    let path = path.strip_prefix(API_PREFIX).unwrap_or(path).to_string();
+    let body = serde_json::from_slice(&buffer[head_end..]).unwrap_or(Value::Null);
+
+    let route = state
+        .routes
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|r| r.method == method && r.path == path)
+        .cloned();
+    state.requests.lock().unwrap().push(RecordedRequest {
+        method,
+        path,
+        query,
+        body,
+    });
+
+    let response = match route {
+        Some(route) => {
+            let body = route.body.to_string();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        None => String::from("HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n"),
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+/// Payloads for a small guild with an invoking member and a few others.
+pub mod fixtures {
+    use serde_json::{json, Value};
+    use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+
+    use crate::handler::invocation::Invocation;
+
+    pub const APPLICATION_ID: u64 = 900;
+    pub const GUILD_ID: u64 = 100;
+    pub const CHANNEL_ID: u64 = 200;
+    pub const INVOKER_ID: u64 = 300;
+    pub const OWNER_ID: u64 = 301;
+    pub const OTHER_ID: u64 = 302;
+    /// When every fixture member joined.
+    pub const JOINED_AT: &str = "2020-01-01T00:00:00+00:00";
+
+    pub fn user(id: u64, name: &str) -> Value {
+        json!({
+            "id": id.to_string(),
+            "username": name,
+            "discriminator": "0001",
+            "avatar": null,
+            "bot": false,
+        })
+    }
+
+    /// The bot's own user, as `GET /users/@me` returns it.
+    pub fn current_user() -> Value {
+        let mut user = user(APPLICATION_ID, "bot");
+        user["bot"] = json!(true);
+        user["mfa_enabled"] = json!(false);
+        user["verified"] = json!(true);
+        user
+    }
+
+    pub fn member(id: u64, name: &str, nick: Option<&str>) -> Value {
+        json!({
+            "user": user(id, name),
+            "nick": nick,
+            "roles": [],
+            "joined_at": JOINED_AT,
+            "deaf": false,
+            "mute": false,
+            "guild_id": GUILD_ID.to_string(),
+        })
+    }
+
+    /// Everyone in the fixture guild, as `GET /guilds/{id}/members` returns.
+    pub fn members() -> Value {
+        json!([
+            member(INVOKER_ID, "invoker", None),
+            member(OWNER_ID, "owner", Some("The Owner")),
+            member(OTHER_ID, "other", Some("Someone")),
+        ])
+    }
+
+    pub fn role(id: u64, name: &str, position: i64) -> Value {
+        json!({
+            "id": id.to_string(),
+            "name": name,
+            "color": 0,
+            "hoist": false,
+            "managed": false,
+            "mentionable": false,
+            "permissions": "0",
+            "position": position,
+            "guild_id": GUILD_ID.to_string(),
+        })
+    }
+
+    /// A message the bot sent in the fixture channel.
+    pub fn message(id: u64, content: &str) -> Value {
+        json!({
+            "id": id.to_string(),
+            "channel_id": CHANNEL_ID.to_string(),
+            "author": user(APPLICATION_ID, "bot"),
+            "content": content,
+            "timestamp": JOINED_AT,
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        })
+    }
+
+    /// The guild as `GET /guilds/{id}?with_counts=true` returns it.
+    pub fn guild() -> Value {
+        json!({
+            "id": GUILD_ID.to_string(),
+            "name": "Test Server",
+            "icon": null,
+            "splash": null,
+            "discovery_splash": null,
+            "owner_id": OWNER_ID.to_string(),
+            "afk_channel_id": null,
+            "afk_timeout": 300,
+            "widget_enabled": false,
+            "widget_channel_id": null,
+            "verification_level": 1,
+            "default_message_notifications": 0,
+            "explicit_content_filter": 0,
+            "roles": [role(GUILD_ID, "@everyone", 0)],
+            "emojis": [],
+            "features": ["COMMUNITY"],
+            "mfa_level": 0,
+            "application_id": null,
+            "system_channel_id": CHANNEL_ID.to_string(),
+            "system_channel_flags": 0,
+            "rules_channel_id": null,
+            "vanity_url_code": null,
+            "description": "A server for tests",
+            "banner": null,
+            "premium_tier": 1,
+            "premium_subscription_count": 3,
+            "preferred_locale": "en-US",
+            "public_updates_channel_id": null,
+            "nsfw_level": 0,
+            "stickers": [],
+            "premium_progress_bar_enabled": false,
+            "approximate_member_count": 3,
+            "approximate_presence_count": 2,
+        })
+    }
+
+    /// A slash command invoked by the fixture invoker, who has every
+    /// permission. `options` are the top level options, `resolved` the
+    /// entities they reference.
+    pub fn command(name: &str, options: Value, resolved: Value) -> Invocation {
+        let mut member = member(INVOKER_ID, "invoker", None);
+        member["permissions"] = Value::from("8");
+        command_by(member, name, options, resolved)
+    }
+
+    /// Like [`command`], invoked by `member` instead. The member needs its
+    /// `permissions` set.
+    pub fn command_by(member: Value, name: &str, options: Value, resolved: Value) -> Invocation {
+        let interaction = json!({
+            "id": "1000",
+            "application_id": APPLICATION_ID.to_string(),
+            "type": 2,
+            "data": {
+                "id": "1001",
+                "name": name,
+                "type": 1,
+                "options": options,
+                "resolved": resolved,
+            },
+            "guild_id": GUILD_ID.to_string(),
+            "channel_id": CHANNEL_ID.to_string(),
+            "member": member,
+            "token": "interaction-token",
+            "version": 1,
+            "locale": "en-US",
+        });
+        serde_json::from_value::<ApplicationCommandInteraction>(interaction)
+            .unwrap()
+            .into()
+    }
+
+    /// `/name sub` with the given options on the subcommand.
+    pub fn subcommand(name: &str, sub: &str, options: Value) -> Invocation {
+        command(
+            name,
+            json!([{ "name": sub, "type": 1, "options": options }]),
+            json!({}),
+        )
+    }
+}
@@ -41,3 +41,14 @@ impl LocalizedString {
         self.en == str.as_ref()
     }
 }
+
+/// Shortens `text` to at most `max` characters, marking the cut with an
+/// ellipsis.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}